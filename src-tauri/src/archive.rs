@@ -0,0 +1,457 @@
+//! Library archives: a single zip holding every table, the covers and the
+//! settings, meant to move a whole library between machines.
+//!
+//! The zip contains `library.json` (a `LibraryDump`), `settings.json` (the raw
+//! settings store value) and one `covers/{volume_id}.jpg` per downloaded cover.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::Manager;
+use zip::write::SimpleFileOptions;
+
+const FORMAT_VERSION: u32 = 1;
+const LIBRARY_ENTRY: &str = "library.json";
+const SETTINGS_ENTRY: &str = "settings.json";
+const COVERS_DIR: &str = "covers/";
+
+// Tables that make up the library, parents before children so rows can be
+// restored in this order.
+const TABLES: &[&str] = &[
+    "books",
+    "authors",
+    "book_authors",
+    "categories",
+    "book_categories",
+    "groups",
+    "book_groups",
+    "custom_fields",
+    "book_custom_fields",
+    "book_identifiers",
+    "borrowers",
+    "loans",
+    "readings",
+    "book_reviews",
+    "locations",
+    "copies",
+];
+
+type Row = serde_json::Map<String, Value>;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LibraryDump {
+    format_version: u32,
+    /// Version of the last migration the dumped database had applied.
+    schema_version: i64,
+    /// Rows of each table, tables the schema didn't have yet are missing.
+    tables: BTreeMap<String, Vec<Row>>,
+}
+
+async fn table_columns(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+) -> anyhow::Result<Vec<String>> {
+    Ok(
+        sqlx::query_scalar::<_, String>("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+            .bind(table)
+            .fetch_all(conn)
+            .await?,
+    )
+}
+
+async fn dump_tables(sqlite_pool: &sqlx::SqlitePool) -> anyhow::Result<LibraryDump> {
+    let mut conn = sqlite_pool.acquire().await?;
+    let mut tables = BTreeMap::new();
+    for table in TABLES {
+        let columns = table_columns(&mut conn, table).await?;
+
+        // SQLite builds the JSON, so every column keeps its storage type
+        let object = columns
+            .iter()
+            .map(|c| format!("'{c}', \"{c}\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!("SELECT json_object({object}) FROM \"{table}\" ORDER BY rowid");
+
+        let rows = sqlx::query_scalar::<_, String>(&sql)
+            .fetch_all(&mut *conn)
+            .await?
+            .iter()
+            .map(|json| serde_json::from_str::<Row>(json))
+            .collect::<Result<Vec<_>, _>>()?;
+        tables.insert(table.to_string(), rows);
+    }
+
+    Ok(LibraryDump {
+        format_version: FORMAT_VERSION,
+        schema_version: crate::migrations::latest_version(),
+        tables,
+    })
+}
+
+async fn write_archive<W: Write + Seek>(
+    sqlite_pool: &sqlx::SqlitePool,
+    books_dir: &Path,
+    settings: &Value,
+    writer: W,
+) -> anyhow::Result<usize> {
+    let dump = dump_tables(sqlite_pool).await?;
+    let book_count = dump.tables.get("books").map_or(0, |rows| rows.len());
+
+    let mut zip = zip::ZipWriter::new(writer);
+    let deflated =
+        SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    // JPEGs don't get any smaller
+    let stored = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+    zip.start_file(LIBRARY_ENTRY, deflated)?;
+    serde_json::to_writer(&mut zip, &dump)?;
+
+    zip.start_file(SETTINGS_ENTRY, deflated)?;
+    serde_json::to_writer(&mut zip, settings)?;
+
+    if let Ok(entries) = std::fs::read_dir(books_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".jpg") {
+                continue;
+            }
+            zip.start_file(format!("{COVERS_DIR}{name}"), stored)?;
+            std::io::copy(&mut std::fs::File::open(entry.path())?, &mut zip)?;
+        }
+    }
+
+    zip.finish()?;
+    Ok(book_count)
+}
+
+fn json_to_sql<'q>(
+    query: sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+    value: &Value,
+) -> sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>> {
+    match value {
+        Value::Null => query.bind(None::<String>),
+        Value::Bool(b) => query.bind(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => query.bind(i),
+            None => query.bind(n.as_f64()),
+        },
+        Value::String(s) => query.bind(s.clone()),
+        other => query.bind(other.to_string()),
+    }
+}
+
+/// Replaces the whole database with the dump.
+///
+/// The schema is rebuilt from scratch by running the migrations up to the
+/// dump's version, the rows are inserted, then the remaining migrations run
+/// so their backfills apply to the restored data. All in one transaction.
+async fn restore_dump(sqlite_pool: &sqlx::SqlitePool, dump: &LibraryDump) -> anyhow::Result<()> {
+    let mut tx = sqlite_pool.begin().await?;
+    crate::migrations::reset_schema(&mut tx, dump.schema_version).await?;
+
+    for table in TABLES {
+        let Some(rows) = dump.tables.get(*table) else {
+            continue;
+        };
+        let columns = table_columns(&mut tx, table).await?;
+
+        for row in rows {
+            // Column names end up in the SQL, only accept the ones the table has
+            if let Some(unknown) = row.keys().find(|k| !columns.contains(k)) {
+                anyhow::bail!("Unknown column {table}.{unknown} in archive");
+            }
+            let names: Vec<&String> = row.keys().collect();
+            let sql = format!(
+                "INSERT INTO \"{table}\" ({}) VALUES ({})",
+                names
+                    .iter()
+                    .map(|n| format!("\"{n}\""))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                vec!["?"; names.len()].join(", ")
+            );
+            let mut query = sqlx::query(&sql);
+            for name in &names {
+                query = json_to_sql(query, &row[*name]);
+            }
+            query.execute(&mut *tx).await?;
+        }
+    }
+
+    crate::migrations::migrate_after(&mut tx, dump.schema_version).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+// Everything in the archive, read up front so nothing is touched before we
+// know the archive is usable.
+struct Archive {
+    dump: LibraryDump,
+    settings: Option<Value>,
+    covers: Vec<(String, Vec<u8>)>,
+}
+
+fn read_archive<R: Read + Seek>(reader: R) -> anyhow::Result<Archive> {
+    let mut zip = zip::ZipArchive::new(reader)?;
+
+    let dump: LibraryDump = match zip.by_name(LIBRARY_ENTRY) {
+        Ok(entry) => serde_json::from_reader(entry)?,
+        Err(_) => anyhow::bail!("Not a library archive, {LIBRARY_ENTRY} is missing"),
+    };
+    if dump.format_version != FORMAT_VERSION {
+        anyhow::bail!("Unsupported archive format {}", dump.format_version);
+    }
+    let current = crate::migrations::latest_version();
+    if dump.schema_version > current {
+        anyhow::bail!(
+            "The archive was made by a newer version of the app (schema {}, this app has {current})",
+            dump.schema_version
+        );
+    }
+    if dump.schema_version < 1 {
+        anyhow::bail!("Invalid schema version {}", dump.schema_version);
+    }
+
+    let settings = match zip.by_name(SETTINGS_ENTRY) {
+        Ok(entry) => Some(serde_json::from_reader(entry)?),
+        Err(_) => None,
+    };
+
+    let mut covers = Vec::new();
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        // enclosed_name rejects absolute paths and ".." components
+        let Some(path) = entry.enclosed_name() else {
+            continue;
+        };
+        let Ok(file) = path.strip_prefix(COVERS_DIR) else {
+            continue;
+        };
+        let Some(name) = file
+            .to_str()
+            .filter(|n| n.ends_with(".jpg") && !n.contains('/'))
+        else {
+            continue;
+        };
+        let name = name.to_string();
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        covers.push((name, data));
+    }
+
+    Ok(Archive {
+        dump,
+        settings,
+        covers,
+    })
+}
+
+/// Writes the library, covers and settings to a zip at `path`, returns the
+/// number of books in it.
+pub async fn export_library_archive(
+    pool: &tauri_plugin_sql::DbPool,
+    path: &Path,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<usize> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let books_dir = app_handle.path().app_data_dir()?.join("books");
+    let settings = crate::settings::load_settings_value(app_handle).map_err(anyhow::Error::msg)?;
+
+    let file = std::fs::File::create(path)?;
+    write_archive(sqlite_pool, &books_dir, &settings, file).await
+}
+
+/// Replaces the library, covers and settings with the content of the archive
+/// at `path`, returns the number of books restored.
+pub async fn restore_library_archive(
+    pool: &tauri_plugin_sql::DbPool,
+    path: &Path,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<usize> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let archive = read_archive(std::fs::File::open(path)?)?;
+
+    restore_dump(sqlite_pool, &archive.dump).await?;
+
+    let books_dir = app_handle.path().app_data_dir()?.join("books");
+    std::fs::create_dir_all(&books_dir)?;
+    for entry in std::fs::read_dir(&books_dir)?.flatten() {
+        if entry.file_name().to_string_lossy().ends_with(".jpg") {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    for (name, data) in &archive.covers {
+        std::fs::write(books_dir.join(name), data)?;
+    }
+
+    if let Some(settings) = &archive.settings {
+        crate::settings::save_settings_value(app_handle, settings.clone())
+            .map_err(anyhow::Error::msg)?;
+    }
+
+    Ok(archive
+        .dump
+        .tables
+        .get("books")
+        .map_or(0, |rows| rows.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    async fn memory_pool() -> sqlx::SqlitePool {
+        // A single connection, every connection to :memory: is its own database
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for migration in crate::migrations::all() {
+            sqlx::raw_sql(migration.sql).execute(&pool).await.unwrap();
+        }
+        pool
+    }
+
+    // At least one row in every table of TABLES
+    const LIBRARY: &str = r#"
+    INSERT INTO books (volume_id, title, page_count, is_ebook, published_date)
+    VALUES ('vol-1', 'The Hobbit', 310, 0, '1937-09-21'), ('vol-2', 'Dylan Dog', NULL, NULL, NULL);
+    UPDATE books SET series = 'Dylan Dog', number = 12 WHERE volume_id = 'vol-2';
+    INSERT INTO authors (name) VALUES ('Tolkien, J. R. R.'), ('Tiziano Sclavi');
+    INSERT INTO book_authors (volume_id, author_id, position) VALUES ('vol-1', 1, 0), ('vol-2', 2, 0);
+    INSERT INTO categories (name) VALUES ('Fantasy');
+    INSERT INTO book_categories (volume_id, category_id) VALUES ('vol-1', 1);
+    INSERT INTO groups (name) VALUES ('Comics');
+    INSERT INTO book_groups (volume_id, group_id) VALUES ('vol-2', 1);
+    INSERT INTO custom_fields (name) VALUES ('Condition');
+    INSERT INTO book_custom_fields (volume_id, field_id, value) VALUES ('vol-1', 1, 'Very good');
+    INSERT INTO book_identifiers (volume_id, type, identifier, canonical_isbn13)
+    VALUES ('vol-1', 'ISBN_13', '9780261102217', '9780261102217'),
+           ('vol-2', 'EAN_13', '9771121580009', '9771121580009');
+    INSERT INTO borrowers (name) VALUES ('Sam');
+    INSERT INTO loans (volume_id, borrower_id, lent_on, due_on, returned_on)
+    VALUES ('vol-1', 1, '2024-01-02', '2024-02-01', '2024-01-20'),
+           ('vol-2', 1, '2024-03-01', NULL, NULL);
+    INSERT INTO readings (volume_id, status, started_on, finished_on, current_page)
+    VALUES ('vol-1', 'read', '2024-01-01', '2024-01-15', NULL), ('vol-1', 'reading', '2024-05-01', NULL, 42);
+    INSERT INTO book_reviews (volume_id, rating, review) VALUES ('vol-1', 4.5, 'Lovely');
+    INSERT INTO locations (parent_id, name, kind) VALUES (NULL, 'Study', 'room'), (1, 'Tall bookcase', 'bookcase');
+    INSERT INTO copies (volume_id, price, currency, grade_scale, grade, signed, location_id)
+    VALUES ('vol-1', 12.5, 'EUR', 'book', 'Fine', 1, 2), ('vol-2', NULL, NULL, 'comic', '9.4', 0, NULL);
+    "#;
+
+    fn temp_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("archive-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn archive_round_trip() {
+        let source = memory_pool().await;
+        sqlx::raw_sql(LIBRARY).execute(&source).await.unwrap();
+        let before = dump_tables(&source).await.unwrap();
+        for table in TABLES {
+            assert!(!before.tables[*table].is_empty(), "{table} has no rows");
+        }
+
+        let books_dir = temp_dir();
+        std::fs::write(books_dir.join("vol-1.jpg"), b"jpeg").unwrap();
+        let settings = serde_json::json!({ "backupRetention": 3 });
+        let mut zip = Cursor::new(Vec::new());
+        let count = write_archive(&source, &books_dir, &settings, &mut zip)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+        std::fs::remove_dir_all(&books_dir).unwrap();
+
+        // Restored over a library that already has books of its own
+        let target = memory_pool().await;
+        sqlx::raw_sql("INSERT INTO books (volume_id, title) VALUES ('vol-9', 'Gone')")
+            .execute(&target)
+            .await
+            .unwrap();
+        let archive = read_archive(Cursor::new(zip.into_inner())).unwrap();
+        restore_dump(&target, &archive.dump).await.unwrap();
+
+        let after = dump_tables(&target).await.unwrap();
+        assert_eq!(after.tables, before.tables);
+        assert_eq!(archive.settings, Some(settings));
+        assert_eq!(
+            archive.covers,
+            [("vol-1.jpg".to_string(), b"jpeg".to_vec())]
+        );
+
+        // The triggers filled the search index of the restored rows
+        let hits: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM books_fts WHERE books_fts MATCH 'hobbit'")
+                .fetch_one(&target)
+                .await
+                .unwrap();
+        assert_eq!(hits, 1);
+    }
+
+    #[tokio::test]
+    async fn older_archives_run_the_newer_migrations() {
+        let source = memory_pool().await;
+        sqlx::raw_sql(LIBRARY).execute(&source).await.unwrap();
+        let mut dump = dump_tables(&source).await.unwrap();
+
+        // As written before locations existed
+        dump.schema_version = 12;
+        dump.tables.remove("locations");
+        for copy in dump.tables.get_mut("copies").unwrap() {
+            copy.remove("location_id");
+        }
+
+        let target = memory_pool().await;
+        restore_dump(&target, &dump).await.unwrap();
+        let after = dump_tables(&target).await.unwrap();
+
+        assert!(after.tables["locations"].is_empty());
+        for copy in &after.tables["copies"] {
+            assert_eq!(copy["location_id"], Value::Null);
+        }
+        for table in TABLES
+            .iter()
+            .filter(|t| !["locations", "copies"].contains(t))
+        {
+            assert_eq!(after.tables[*table], dump.tables[*table], "{table}");
+        }
+    }
+
+    #[tokio::test]
+    async fn covers_outside_the_covers_folder_are_ignored() {
+        let pool = memory_pool().await;
+        let dump = dump_tables(&pool).await.unwrap();
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        zip.start_file(LIBRARY_ENTRY, options).unwrap();
+        serde_json::to_writer(&mut zip, &dump).unwrap();
+        for name in [
+            "covers/../../evil.jpg",
+            "/covers/absolute.jpg",
+            "covers/nested/deep.jpg",
+            "covers/notes.txt",
+            "vol-0.jpg",
+            "covers/vol-1.jpg",
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(b"jpeg").unwrap();
+        }
+        let zip = zip.finish().unwrap();
+
+        let archive = read_archive(Cursor::new(zip.into_inner())).unwrap();
+        let names: Vec<&str> = archive.covers.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["vol-1.jpg"]);
+        assert_eq!(archive.settings, None);
+    }
+}
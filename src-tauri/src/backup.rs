@@ -0,0 +1,388 @@
+//! Rotating snapshots of `books.db` in `{app_data_dir}/backups`.
+//!
+//! A snapshot is taken when the app starts and after every
+//! `backupEveryWrites` writes, only the newest `backupRetention` are kept.
+//! Snapshots are plain SQLite files written with `VACUUM INTO`, so they can
+//! also be opened with any SQLite tool.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use serde::Serialize;
+use sqlx::Connection;
+use tauri::Emitter;
+use tauri::Manager;
+use tauri_plugin_sql::DbInstances;
+
+const BACKUPS_DIR: &str = "backups";
+const PREFIX: &str = "books-";
+const EXTENSION: &str = ".db";
+const DEFAULT_RETENTION: u32 = 10;
+const DEFAULT_EVERY_WRITES: u32 = 25;
+
+/// Writes since the last snapshot, managed by the app.
+#[derive(Default)]
+pub struct BackupState {
+    writes: AtomicU32,
+    // Keeps a write-triggered snapshot from racing the startup one
+    lock: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub size: u64,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+}
+
+fn backups_dir(app_handle: &tauri::AppHandle) -> anyhow::Result<PathBuf> {
+    Ok(app_handle.path().app_data_dir()?.join(BACKUPS_DIR))
+}
+
+/// Retention from the settings, 0 turns automatic snapshots off.
+fn retention(app_handle: &tauri::AppHandle) -> u32 {
+    crate::settings::load_settings(app_handle)
+        .ok()
+        .and_then(|s| s.backup_retention)
+        .unwrap_or(DEFAULT_RETENTION)
+}
+
+fn every_writes(app_handle: &tauri::AppHandle) -> u32 {
+    crate::settings::load_settings(app_handle)
+        .ok()
+        .and_then(|s| s.backup_every_writes)
+        .unwrap_or(DEFAULT_EVERY_WRITES)
+}
+
+/// Snapshots in `dir`, newest first. Names sort by time, so they're sorted by
+/// name rather than by a modification time that copying the folder resets.
+fn list_dir(dir: &Path) -> anyhow::Result<Vec<BackupInfo>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with(PREFIX) || !name.ends_with(EXTENSION) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        let created_at = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        backups.push(BackupInfo {
+            name,
+            size: metadata.len(),
+            created_at,
+        });
+    }
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Writes a snapshot of the database to `dir` and returns its path.
+async fn snapshot_to(sqlite_pool: &sqlx::SqlitePool, dir: &Path) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+
+    // Local time with milliseconds, two snapshots in the same second still
+    // get their own file
+    let stamp: String =
+        sqlx::query_scalar("SELECT strftime('%Y-%m-%d_%H-%M-%f', 'now', 'localtime')")
+            .fetch_one(sqlite_pool)
+            .await?;
+    let path = dir.join(format!("{PREFIX}{}{EXTENSION}", stamp.replace('.', "-")));
+
+    // VACUUM INTO refuses to overwrite, and the snapshot is consistent even
+    // while other connections write
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().to_string())
+        .execute(sqlite_pool)
+        .await?;
+    Ok(path)
+}
+
+/// Deletes the oldest snapshots in `dir` beyond `keep`.
+fn prune(dir: &Path, keep: usize) -> anyhow::Result<()> {
+    for backup in list_dir(dir)?.into_iter().skip(keep) {
+        std::fs::remove_file(dir.join(backup.name))?;
+    }
+    Ok(())
+}
+
+/// Takes a snapshot and prunes the old ones, unless the retention is 0.
+pub async fn snapshot(
+    pool: &tauri_plugin_sql::DbPool,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<Option<PathBuf>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let keep = retention(app_handle);
+    if keep == 0 {
+        return Ok(None);
+    }
+
+    let state = app_handle.state::<BackupState>();
+    let _guard = state.lock.lock().await;
+
+    let dir = backups_dir(app_handle)?;
+    let path = snapshot_to(sqlite_pool, &dir).await?;
+    prune(&dir, keep as usize)?;
+    state.writes.store(0, Ordering::Relaxed);
+    Ok(Some(path))
+}
+
+/// Takes a snapshot in the background, used on startup and by `record_write`.
+/// Failures are emitted as `backup-failed` with the error message.
+pub fn spawn_snapshot(app_handle: &tauri::AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let instances = app_handle.state::<DbInstances>();
+        let guard = instances.0.read().await;
+        let Some(pool) = guard.get("sqlite:books.db") else {
+            return;
+        };
+        if let Err(e) = snapshot(pool, &app_handle).await {
+            let _ = app_handle.emit("backup-failed", &e.to_string());
+        }
+    });
+}
+
+/// Counts a successful write and takes a snapshot every `backupEveryWrites`
+/// of them, 0 only snapshots on startup.
+pub fn record_write(app_handle: &tauri::AppHandle) {
+    let every = every_writes(app_handle);
+    let state = app_handle.state::<BackupState>();
+    let writes = state.writes.fetch_add(1, Ordering::Relaxed) + 1;
+    if every > 0 && writes >= every {
+        state.writes.store(0, Ordering::Relaxed);
+        spawn_snapshot(app_handle);
+    }
+}
+
+pub fn list_backups(app_handle: &tauri::AppHandle) -> anyhow::Result<Vec<BackupInfo>> {
+    list_dir(&backups_dir(app_handle)?)
+}
+
+/// Copies every table of the attached `snapshot` database into `main`.
+///
+/// The schema is rebuilt at the snapshot's version, the rows are copied, then
+/// the newer migrations run, like restoring a library archive. Full-text
+/// tables aren't copied, the triggers fill them as the rows go in.
+async fn copy_from_snapshot(conn: &mut sqlx::SqliteConnection) -> anyhow::Result<()> {
+    let version: i64 =
+        sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM snapshot._sqlx_migrations")
+            .fetch_one(&mut *conn)
+            .await?;
+    let latest = crate::migrations::latest_version();
+    if version > latest {
+        anyhow::bail!(
+            "The backup was made by a newer version of the app (schema {version}, this app has {latest})"
+        );
+    }
+    if version < 1 {
+        anyhow::bail!("Not a backup of this app's database");
+    }
+
+    let mut tx = conn.begin().await?;
+    crate::migrations::reset_schema(&mut tx, version).await?;
+
+    // Regular tables only, the shadow tables of a virtual table share its
+    // name as a prefix
+    let tables = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT t.name FROM main.sqlite_master t
+        WHERE t.type = 'table' AND t.name NOT LIKE 'sqlite_%' AND t.name != '_sqlx_migrations'
+          AND t.sql NOT LIKE 'CREATE VIRTUAL TABLE%'
+          AND NOT EXISTS (
+            SELECT 1 FROM main.sqlite_master v
+            WHERE v.type = 'table' AND v.sql LIKE 'CREATE VIRTUAL TABLE%'
+              AND t.name LIKE v.name || '\_%' ESCAPE '\'
+          )
+          AND t.name IN (SELECT name FROM snapshot.sqlite_master WHERE type = 'table')
+        ORDER BY t.rowid
+        "#,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for table in tables {
+        let columns = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM pragma_table_info(?, 'main') ORDER BY cid",
+        )
+        .bind(&table)
+        .fetch_all(&mut *tx)
+        .await?
+        .iter()
+        .map(|c| format!("\"{c}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+        sqlx::query(&format!(
+            "INSERT INTO main.\"{table}\" ({columns}) SELECT {columns} FROM snapshot.\"{table}\" ORDER BY rowid"
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    crate::migrations::migrate_after(&mut tx, version).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replaces the database with the snapshot `name`, after taking a snapshot of
+/// the current state so the restore itself can be undone.
+pub async fn restore_backup(
+    pool: &tauri_plugin_sql::DbPool,
+    app_handle: &tauri::AppHandle,
+    name: &str,
+) -> anyhow::Result<()> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let dir = backups_dir(app_handle)?;
+    let keep = retention(app_handle);
+
+    let state = app_handle.state::<BackupState>();
+    let _guard = state.lock.lock().await;
+    restore_from(sqlite_pool, &dir, name, keep as usize).await
+}
+
+async fn restore_from(
+    sqlite_pool: &sqlx::SqlitePool,
+    dir: &Path,
+    name: &str,
+    keep: usize,
+) -> anyhow::Result<()> {
+    // Only names we listed, nothing that could point outside the folder
+    if !list_dir(dir)?.iter().any(|b| b.name == name) {
+        anyhow::bail!("Unknown backup {name}");
+    }
+    let path = dir.join(name);
+    snapshot_to(sqlite_pool, dir).await?;
+
+    // ATTACH can't run inside a transaction and only applies to this
+    // connection, so everything happens on one
+    let mut conn = sqlite_pool.acquire().await?;
+    sqlx::query("ATTACH DATABASE ? AS snapshot")
+        .bind(path.to_string_lossy().to_string())
+        .execute(&mut *conn)
+        .await?;
+    let result = copy_from_snapshot(&mut conn).await;
+    sqlx::query("DETACH DATABASE snapshot")
+        .execute(&mut *conn)
+        .await?;
+    result?;
+
+    // Pruned once the restore is done, the snapshot restored from could be the
+    // oldest one. A retention of 0 only turns off the automatic snapshots.
+    if keep > 0 {
+        prune(dir, keep)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A library in its own folder, snapshots go to its `backups` folder.
+    async fn library() -> (sqlx::SqlitePool, PathBuf) {
+        let root = std::env::temp_dir().join(format!("library-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(root.join("books.db"))
+            .create_if_missing(true);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        for migration in crate::migrations::all() {
+            sqlx::raw_sql(migration.sql).execute(&pool).await.unwrap();
+        }
+        sqlx::raw_sql("CREATE TABLE _sqlx_migrations (version BIGINT PRIMARY KEY)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO _sqlx_migrations (version) VALUES (?)")
+            .bind(crate::migrations::latest_version())
+            .execute(&pool)
+            .await
+            .unwrap();
+        (pool, root)
+    }
+
+    async fn titles(pool: &sqlx::SqlitePool) -> Vec<String> {
+        sqlx::query_scalar("SELECT title FROM books ORDER BY title")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn add_book(pool: &sqlx::SqlitePool, volume_id: &str, title: &str) {
+        sqlx::query("INSERT INTO books (volume_id, title) VALUES (?, ?)")
+            .bind(volume_id)
+            .bind(title)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_the_newest() {
+        let (pool, root) = library().await;
+        let dir = root.join(BACKUPS_DIR);
+        let mut paths = Vec::new();
+        for _ in 0..4 {
+            paths.push(snapshot_to(&pool, &dir).await.unwrap());
+        }
+
+        prune(&dir, 2).unwrap();
+        let names: Vec<String> = list_dir(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.name)
+            .collect();
+        let newest: Vec<String> = paths
+            .iter()
+            .rev()
+            .take(2)
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, newest);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn restore_brings_back_the_snapshot_rows() {
+        let (pool, root) = library().await;
+        let dir = root.join(BACKUPS_DIR);
+        add_book(&pool, "vol-1", "The Hobbit").await;
+        let first = snapshot_to(&pool, &dir).await.unwrap();
+        let first = first.file_name().unwrap().to_string_lossy().to_string();
+        snapshot_to(&pool, &dir).await.unwrap();
+
+        add_book(&pool, "vol-2", "Dylan Dog").await;
+        sqlx::query("UPDATE books SET title = 'The Lord of the Rings' WHERE volume_id = 'vol-1'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // The oldest snapshot is restored even though retention drops it after
+        restore_from(&pool, &dir, &first, 2).await.unwrap();
+        assert_eq!(titles(&pool).await, ["The Hobbit"]);
+        let backups = list_dir(&dir).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert!(backups.iter().all(|b| b.name != first));
+
+        // The pre-restore snapshot undoes it
+        restore_from(&pool, &dir, &backups[0].name, 2)
+            .await
+            .unwrap();
+        assert_eq!(titles(&pool).await, ["Dylan Dog", "The Lord of the Rings"]);
+
+        assert!(restore_from(&pool, &dir, "../books.db", 2).await.is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,404 @@
+//! ComicInfo.xml, the metadata file CBZ readers and comic servers read from
+//! inside the archive.
+//!
+//! Issues follow the comic importers: the series is the title and ComicInfo's
+//! `Title`, the story title, goes to the Story Title custom field. Custom
+//! fields ComicInfo has no element for are written as
+//! `<CustomField Name="...">`, which readers skip like any element they don't
+//! know, so they survive an export and import.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use serde::Serialize;
+
+use crate::db::Book;
+use crate::utils::{barcode, isbn, normalize_identifier};
+
+pub const FILE_NAME: &str = "ComicInfo.xml";
+
+const CUSTOM_FIELD: &str = "CustomField";
+// Custom fields with an element of their own
+const STORY_TITLE: &str = "Story Title";
+const NOTES: &str = "Notes";
+
+/// A ComicInfo.xml in the shape `insert_book` takes.
+#[derive(Debug, Default, PartialEq)]
+pub struct ComicInfo {
+    pub title: String,
+    pub series: Option<String>,
+    pub number: Option<i64>,
+    pub authors: Vec<String>,
+    pub groups: Vec<String>,
+    pub publisher: Option<String>,
+    pub published_date: Option<String>,
+    pub identifier: Option<String>,
+    pub custom_fields: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ComicInfoImport {
+    pub imported: usize,
+    /// Issues of a series we already have.
+    pub skipped: usize,
+    /// One line per file that couldn't be imported.
+    pub errors: Vec<String>,
+}
+
+/// Splits a published_date like "2012-03" into the Year, Month and Day
+/// elements, ComicInfo writes them without leading zeros.
+fn split_date(date: &str) -> [Option<String>; 3] {
+    let mut parts = date
+        .splitn(3, '-')
+        .map(|part| part.trim().parse::<u32>().ok().map(|p| p.to_string()));
+    [(); 3].map(|_| parts.next().flatten())
+}
+
+fn join_date(year: Option<&str>, month: Option<&str>, day: Option<&str>) -> Option<String> {
+    let year = year?.parse::<u32>().ok()?;
+    Some(
+        [month, day]
+            .into_iter()
+            .map_while(|part| part.and_then(|p| p.parse::<u32>().ok()))
+            .fold(year.to_string(), |date, part| format!("{date}-{part:02}")),
+    )
+}
+
+/// The identifier a GTIN is stored as and the issue number of its barcode
+/// supplement. UPC-As get their leading 0 back, values that aren't a valid
+/// ISBN or EAN-13 are dropped rather than failing the import.
+fn gtin_identifier(gtin: &str) -> (Option<String>, Option<i64>) {
+    let digits = isbn::normalize(gtin);
+    let digits = match digits.len() {
+        12 | 14 | 17 => format!("0{digits}"),
+        _ => digits,
+    };
+    if normalize_identifier(&digits).is_ok() {
+        return (Some(digits), None);
+    }
+    match barcode::parse(&digits) {
+        Ok(barcode) => {
+            let issue = barcode.issue_number();
+            (Some(barcode.ean), issue)
+        }
+        Err(_) => (None, None),
+    }
+}
+
+pub fn to_xml(book: &Book) -> anyhow::Result<String> {
+    let custom_field = |name: &str| book.custom_fields.get(name).cloned();
+    let list = |values: &[String]| Some(values.join(", "));
+
+    let title = match &book.series {
+        Some(series) => custom_field(STORY_TITLE)
+            .or(Some(&book.title).filter(|title| *title != series).cloned()),
+        None => Some(book.title.clone()),
+    };
+    let [year, month, day] = split_date(book.published_date.as_deref().unwrap_or_default());
+    let gtin = book
+        .identifiers
+        .iter()
+        .find(|i| matches!(i.type_.as_str(), "EAN_13" | "ISBN_13" | "ISBN_10"))
+        .map(|i| i.identifier.clone());
+
+    // In the order of the schema
+    let elements = [
+        ("Title", title),
+        ("Series", book.series.clone()),
+        ("Number", book.number.map(|n| n.to_string())),
+        ("Notes", custom_field(NOTES)),
+        ("Year", year),
+        ("Month", month),
+        ("Day", day),
+        ("Writer", list(&book.authors)),
+        ("Publisher", book.publisher.clone()),
+        ("Tags", list(&book.groups)),
+        ("GTIN", gtin),
+    ];
+    let mut custom_fields: Vec<(&String, &String)> = book
+        .custom_fields
+        .iter()
+        .filter(|(name, _)| *name != NOTES && (*name != STORY_TITLE || book.series.is_none()))
+        .collect();
+    custom_fields.sort();
+
+    let mut writer = quick_xml::Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+    writer
+        .create_element("ComicInfo")
+        .with_attributes([
+            ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+            ("xmlns:xsd", "http://www.w3.org/2001/XMLSchema"),
+        ])
+        .write_inner_content(|writer| {
+            for (name, value) in &elements {
+                let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) else {
+                    continue;
+                };
+                writer
+                    .create_element(*name)
+                    .write_text_content(BytesText::new(value))?;
+            }
+            for (name, value) in &custom_fields {
+                writer
+                    .create_element(CUSTOM_FIELD)
+                    .with_attribute(("Name", name.as_str()))
+                    .write_text_content(BytesText::new(value))?;
+            }
+            Ok(())
+        })?;
+
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+pub fn parse(content: &str) -> anyhow::Result<ComicInfo> {
+    let mut reader = quick_xml::Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut depth = 0;
+    let mut is_comicinfo = false;
+    let mut fields: HashMap<String, String> = HashMap::new();
+    let mut custom_fields = HashMap::new();
+    // The element we're in and the Name of a <CustomField>
+    let mut element = String::new();
+    let mut custom_field_name: Option<String> = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                depth += 1;
+                element = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if depth == 1 {
+                    is_comicinfo = element == "ComicInfo";
+                }
+                custom_field_name = match e.try_get_attribute("Name")? {
+                    Some(name) if element == CUSTOM_FIELD => {
+                        Some(name.unescape_value()?.trim().to_string())
+                    }
+                    _ => None,
+                };
+            }
+            Event::Text(e) if depth == 2 => {
+                let text = e.unescape()?.trim().to_string();
+                if text.is_empty() {
+                    continue;
+                }
+                match custom_field_name.take() {
+                    Some(name) if !name.is_empty() => {
+                        custom_fields.insert(name, text);
+                    }
+                    Some(_) => {}
+                    None => {
+                        fields.insert(element.clone(), text);
+                    }
+                }
+            }
+            Event::End(_) => depth -= 1,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !is_comicinfo {
+        anyhow::bail!("Not a {FILE_NAME}, the root element isn't <ComicInfo>");
+    }
+
+    let get = |name: &str| fields.get(name).map(String::as_str);
+    let list = |name: &str| -> Vec<String> {
+        get(name)
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let series = get("Series").map(str::to_string);
+    let title = series
+        .as_deref()
+        .or(get("Title"))
+        .ok_or_else(|| anyhow::anyhow!("No <Series> or <Title>"))?
+        .to_string();
+    if series.is_some() {
+        if let Some(story_title) = get("Title") {
+            custom_fields.insert(STORY_TITLE.to_string(), story_title.to_string());
+        }
+    }
+    if let Some(notes) = get("Notes") {
+        custom_fields.insert(NOTES.to_string(), notes.to_string());
+    }
+
+    let (identifier, barcode_issue) = get("GTIN").map(gtin_identifier).unwrap_or_default();
+
+    Ok(ComicInfo {
+        title,
+        series,
+        number: get("Number").and_then(|n| n.parse().ok()).or(barcode_issue),
+        authors: list("Writer"),
+        groups: list("Tags"),
+        publisher: get("Publisher").map(str::to_string),
+        published_date: join_date(get("Year"), get("Month"), get("Day")),
+        identifier,
+        custom_fields,
+    })
+}
+
+pub async fn export_book(
+    pool: &tauri_plugin_sql::DbPool,
+    app_handle: &tauri::AppHandle,
+    volume_id: &str,
+    save_path: &Path,
+) -> anyhow::Result<()> {
+    let book = crate::db::get_book(pool, app_handle, volume_id).await?;
+    std::fs::write(save_path, to_xml(&book)?)?;
+    Ok(())
+}
+
+/// Every `.xml` file under `dir`, with comics unpacked to a folder each.
+fn find_xml_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Adds a book for every ComicInfo.xml in `dir` and its subfolders. Issues
+/// whose series and number we already have are skipped, so importing the
+/// folder again only adds what's new.
+pub async fn import_folder(
+    pool: &tauri_plugin_sql::DbPool,
+    dir: &Path,
+) -> anyhow::Result<ComicInfoImport> {
+    let mut report = ComicInfoImport::default();
+
+    for path in find_xml_files(dir)? {
+        let name = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        let info = match std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| crate::importers::decode(&bytes))
+            .and_then(|content| parse(&content))
+        {
+            Ok(info) => info,
+            Err(e) => {
+                report.errors.push(format!("{name}: {e}"));
+                continue;
+            }
+        };
+
+        if let Some(number) = info.number {
+            let series = info.series.as_deref().unwrap_or(&info.title);
+            if crate::db::find_series_issue(pool, series, number)
+                .await?
+                .is_some()
+            {
+                report.skipped += 1;
+                continue;
+            }
+        }
+
+        let inserted = crate::db::insert_book(
+            pool,
+            &info.title,
+            info.series.as_deref(),
+            info.number,
+            &info.authors,
+            &info.groups,
+            info.publisher.as_deref(),
+            info.published_date.as_deref(),
+            info.identifier.as_deref(),
+            info.custom_fields,
+        )
+        .await;
+        match inserted {
+            Ok(_) => report.imported += 1,
+            Err(e) => report.errors.push(format!("{name}: {e}")),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::BookIdentifier;
+
+    fn issue() -> Book {
+        Book {
+            volume_id: "vol-2".to_string(),
+            title: "Dylan Dog".to_string(),
+            series: Some("Dylan Dog".to_string()),
+            number: Some(12),
+            publisher: Some("Sergio Bonelli Editore".to_string()),
+            published_date: Some("1987-09".to_string()),
+            authors: vec!["Tiziano Sclavi".to_string(), "Claudio Villa".to_string()],
+            groups: vec!["Horror".to_string()],
+            identifiers: vec![BookIdentifier {
+                type_: "EAN_13".to_string(),
+                identifier: "9771121580009".to_string(),
+            }],
+            custom_fields: HashMap::from([
+                (STORY_TITLE.to_string(), "Killers & <Monsters>".to_string()),
+                (NOTES.to_string(), "First print".to_string()),
+                ("Condition".to_string(), "Fine".to_string()),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn comicinfo_round_trip() {
+        let book = issue();
+        let parsed = parse(&to_xml(&book).unwrap()).unwrap();
+        assert_eq!(
+            parsed,
+            ComicInfo {
+                title: book.title.clone(),
+                series: book.series.clone(),
+                number: book.number,
+                authors: book.authors.clone(),
+                groups: book.groups.clone(),
+                publisher: book.publisher.clone(),
+                published_date: book.published_date.clone(),
+                identifier: Some("9771121580009".to_string()),
+                custom_fields: book.custom_fields.clone(),
+            }
+        );
+    }
+
+    #[test]
+    fn number_falls_back_to_the_gtin_supplement() {
+        let book = Book {
+            number: None,
+            identifiers: vec![BookIdentifier {
+                type_: "EAN_13".to_string(),
+                identifier: "977112158000912".to_string(),
+            }],
+            ..issue()
+        };
+        let parsed = parse(&to_xml(&book).unwrap()).unwrap();
+        assert_eq!(parsed.identifier.as_deref(), Some("9771121580009"));
+        assert_eq!(parsed.number, Some(12));
+    }
+}
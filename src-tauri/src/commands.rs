@@ -9,6 +9,8 @@ use tauri_plugin_sql::DbInstances;
 use crate::db::find_books_containing_title;
 use crate::db::get_book;
 use crate::db::Book;
+use crate::providers::download_thumbnail;
+use crate::providers::NormalizedBook;
 use crate::AppConfig;
 
 #[tauri::command]
@@ -36,44 +38,112 @@ pub async fn fetch_isbn(
 
     // Load settings on demand from the store
     let settings = crate::settings::load_settings(&app_handle)?;
-    let api_key = settings
-        .google_books_api_key
-        .as_deref()
-        .ok_or_else(|| "Google Books API key not configured".to_string())?;
-
-    match config
-        .client
-        .fetch_and_store_by_isbn(pool, &isbn, &app_handle, api_key)
-        .await
-    {
-        Ok(Some(volume_id)) => {
-            let book = get_book(pool, &app_handle, &volume_id).await.unwrap();
-            let books = find_books_containing_title(pool, book.title.as_str())
-                .await
-                .unwrap();
-
-            // If we find other books with the same or similar title to the one we just
-            // added it's very likely that it's a comic book series that uses ISBNs.
-            //
-            // Google Books recognizes those comics, though they all have the same title
-            // and they don't include the number of the single comic.
-            //
-            // So we must let the user insert it manually. Unlike ISINs we can't try
-            // to infer the number from the last digits of the code, so we can't even
-            // suggest a possible number to the user.
-            //
-            // If there's only a book it's the one that we just added.
-            if books.len() > 1 {
-                let _ = app_handle.emit("possible-comic-found", &book);
-            } else {
-                let _ = app_handle.emit("book-added", &book);
+
+    // Try every provider in order until one of them knows the ISBN
+    let mut found = None;
+    let mut errors = Vec::new();
+    for provider in &config.providers {
+        match provider.fetch_by_isbn(&isbn, &settings).await {
+            Ok(Some(book)) => {
+                found = Some(book);
+                break;
             }
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{}: {e}", provider.name())),
+        }
+    }
+
+    let Some(normalized) = found else {
+        if errors.is_empty() {
+            return Err(format!("No results found for ISBN: {isbn}"));
+        }
+        return Err(format!("Failed to import book: {}", errors.join("; ")));
+    };
+
+    save_normalized_book(pool, &config, &app_handle, normalized).await
+}
+
+#[tauri::command]
+pub async fn preview_isbn(
+    isbn: String,
+    config: State<'_, AppConfig>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<NormalizedBook>, String> {
+    let settings = crate::settings::load_settings(&app_handle)?;
 
-            Ok(book)
+    // Same provider order as fetch_isbn, but nothing is saved and every
+    // candidate of the first provider that answers is returned.
+    let mut errors = Vec::new();
+    for provider in &config.providers {
+        match provider.fetch_candidates_by_isbn(&isbn, &settings).await {
+            Ok(candidates) if !candidates.is_empty() => return Ok(candidates),
+            Ok(_) => {}
+            Err(e) => errors.push(format!("{}: {e}", provider.name())),
         }
-        Ok(None) => Err(format!("No results found for ISBN: {isbn}")),
-        Err(e) => Err(format!("Failed to import book: {e}")),
     }
+
+    if errors.is_empty() {
+        Err(format!("No results found for ISBN: {isbn}"))
+    } else {
+        Err(format!("Failed to preview book: {}", errors.join("; ")))
+    }
+}
+
+#[tauri::command]
+pub async fn commit_preview(
+    draft: NormalizedBook,
+    config: State<'_, AppConfig>,
+    app_handle: tauri::AppHandle,
+) -> Result<Book, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    save_normalized_book(pool, &config, &app_handle, draft).await
+}
+
+async fn save_normalized_book(
+    pool: &tauri_plugin_sql::DbPool,
+    config: &AppConfig,
+    app_handle: &tauri::AppHandle,
+    normalized: NormalizedBook,
+) -> Result<Book, String> {
+    crate::db::upsert_normalized_book(pool, &normalized)
+        .await
+        .map_err(|e| format!("Failed to import book: {e}"))?;
+    crate::backup::record_write(app_handle);
+
+    if let Some(thumb_url) = &normalized.thumbnail {
+        download_thumbnail(&config.http, &normalized.volume_id, thumb_url, app_handle)
+            .await
+            .ok();
+    }
+
+    let volume_id = normalized.volume_id;
+    let book = get_book(pool, app_handle, &volume_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let books = find_books_containing_title(pool, book.title.as_str())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // If we find other books with the same or similar title to the one we just
+    // added it's very likely that it's a comic book series that uses ISBNs.
+    //
+    // Google Books recognizes those comics, though they all have the same title
+    // and they don't include the number of the single comic.
+    //
+    // So we must let the user insert it manually, or scan the barcode with its
+    // supplement so add_comic_issue_by_barcode reads the number from it.
+    //
+    // If there's only a book it's the one that we just added.
+    if books.len() > 1 {
+        let _ = app_handle.emit("possible-comic-found", &book);
+    } else {
+        let _ = app_handle.emit("book-added", &book);
+    }
+
+    Ok(book)
 }
 
 #[tauri::command]
@@ -83,7 +153,38 @@ pub async fn get_all_books(app_handle: tauri::AppHandle) -> Result<Vec<crate::db
 
     let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
 
-    crate::db::fetch_all_books(&pool, &app_handle)
+    crate::db::fetch_all_books(pool, &app_handle)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn query_books(
+    query: crate::db::BookQuery,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::BookPage, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::query_books(pool, &app_handle, &query)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_books(
+    query: String,
+    limit: Option<i64>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::db::SearchResult>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::search_books(pool, &app_handle, &query, limit.unwrap_or(50))
         .await
         .map_err(|e| e.to_string())
 }
@@ -98,12 +199,13 @@ pub async fn get_single_book(
 
     let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
 
-    crate::db::get_book(&pool, &app_handle, &volume_id)
+    crate::db::get_book(pool, &app_handle, &volume_id)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn add_book(
     title: String,
     series: Option<String>,
@@ -137,6 +239,7 @@ pub async fn add_book(
     )
     .await
     .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
 
     let _ = app_handle.emit("book-added", &vol_id);
 
@@ -155,6 +258,27 @@ pub async fn isbn_exists(isbn: String, app_handle: tauri::AppHandle) -> Result<b
     Ok(exists)
 }
 
+/// The book we already own with this ISBN, so scanning it again can add
+/// another copy.
+#[tauri::command]
+pub async fn find_book_by_isbn(
+    isbn: String,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<Book>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::find_book_by_isbn(pool, &isbn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_isbn_details(isbn: String) -> Result<crate::utils::isbn::IsbnDetails, String> {
+    crate::utils::isbn::details(&isbn).ok_or_else(|| format!("Invalid ISBN: {isbn}"))
+}
+
 #[tauri::command]
 pub async fn update_book(
     payload: crate::db::UpdateBookPayload,
@@ -166,6 +290,7 @@ pub async fn update_book(
     crate::db::update_book(pool, payload)
         .await
         .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
     let _ = app_handle.emit("book-updated", &"ok");
     Ok(())
 }
@@ -182,18 +307,24 @@ pub async fn set_book_number(
     crate::db::set_book_number(pool, volume_id, number)
         .await
         .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
     let _ = app_handle.emit("book-updated", &"ok");
     Ok(())
 }
 
 #[tauri::command]
-pub async fn delete_book(volume_id: String, app_handle: tauri::AppHandle) -> Result<(), String> {
+pub async fn delete_book(
+    volume_id: String,
+    force: Option<bool>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
     let instances = app_handle.state::<DbInstances>();
     let guard = instances.0.read().await;
     let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
-    crate::db::delete_book(pool, &volume_id)
+    crate::db::delete_book(pool, &volume_id, force.unwrap_or(false))
         .await
         .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
     let _ = app_handle.emit("book-updated", &"ok");
     Ok(())
 }
@@ -226,19 +357,291 @@ pub async fn export_books_csv(app_handle: tauri::AppHandle) -> Result<String, St
         .await
         .map_err(|e| e.to_string())?;
 
-    Ok(format!("Books exported to {}", path_buf.display()))
+    Ok(format!(
+        "Books exported to {}. Copies aren't part of a CSV export, back up the library to keep them",
+        path_buf.display()
+    ))
 }
 
+/// Exports books as citations. Without a `selection` the whole library is
+/// exported.
 #[tauri::command]
-pub async fn import_books_csv(app_handle: tauri::AppHandle) -> Result<String, String> {
+pub async fn export_books(
+    format: crate::exporters::ExportFormat,
+    selection: Option<crate::exporters::ExportSelection>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
     let path = app_handle
         .dialog()
         .file()
-        .add_filter("CSV", &["csv"])
+        .add_filter(format.name(), &[format.extension()])
+        .set_file_name(format!("books.{}", format.extension()))
+        .blocking_save_file();
+
+    let Some(save_path) = path else {
+        return Err("Export cancelled".to_string());
+    };
+
+    let path_buf = match save_path {
+        tauri_plugin_dialog::FilePath::Path(p) => p,
+        tauri_plugin_dialog::FilePath::Url(u) => {
+            return Err(format!("URL paths not supported: {}", u));
+        }
+    };
+
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let count =
+        crate::exporters::export_books(pool, format, &selection.unwrap_or_default(), &path_buf)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    Ok(format!(
+        "Exported {} books to {}",
+        count,
+        path_buf.display()
+    ))
+}
+
+/// Imports a CSV export, of this app or of an app we have an importer for.
+/// Without a `path` the user picks the file, without a `format` it's
+/// detected. The report carries both so a dry run can be followed by the real
+/// import.
+#[tauri::command]
+pub async fn import_books_csv(
+    path: Option<String>,
+    format: Option<crate::importers::ImportFormat>,
+    mode: Option<crate::db::ImportMode>,
+    dry_run: Option<bool>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::ImportReport, String> {
+    let path_buf = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let path = app_handle
+                .dialog()
+                .file()
+                .add_filter("CSV", &["csv", "tsv", "txt", "xml"])
+                .blocking_pick_file();
+
+            let Some(file_path) = path else {
+                return Err("Import cancelled".to_string());
+            };
+
+            match file_path {
+                tauri_plugin_dialog::FilePath::Path(p) => p,
+                tauri_plugin_dialog::FilePath::Url(u) => {
+                    return Err(format!("URL paths not supported: {}", u));
+                }
+            }
+        }
+    };
+    let dry_run = dry_run.unwrap_or(false);
+
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let report = crate::db::import_books_from_csv(
+        pool,
+        &path_buf,
+        format,
+        mode.unwrap_or_default(),
+        dry_run,
+        &app_handle,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !dry_run && report.inserted + report.updated > 0 {
+        crate::backup::record_write(&app_handle);
+    }
+    if !dry_run && report.inserted > 0 {
+        let _ = app_handle.emit("book-added", &"ok");
+    }
+    if !dry_run && report.updated > 0 {
+        let _ = app_handle.emit("book-updated", &"ok");
+    }
+
+    Ok(report)
+}
+
+/// Imports a Calibre library. Without a `path` the user picks the library
+/// folder, the one with `metadata.db` in it. The real import that follows a
+/// dry run goes through `import_books_csv` with the report's path and format.
+#[tauri::command]
+pub async fn import_calibre_library(
+    path: Option<String>,
+    mode: Option<crate::db::ImportMode>,
+    dry_run: Option<bool>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::ImportReport, String> {
+    let path_buf = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let Some(folder) = app_handle.dialog().file().blocking_pick_folder() else {
+                return Err("Import cancelled".to_string());
+            };
+
+            match folder {
+                tauri_plugin_dialog::FilePath::Path(p) => p,
+                tauri_plugin_dialog::FilePath::Url(u) => {
+                    return Err(format!("URL paths not supported: {}", u));
+                }
+            }
+        }
+    };
+    let dry_run = dry_run.unwrap_or(false);
+
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let report = crate::db::import_calibre_library(
+        pool,
+        &path_buf,
+        mode.unwrap_or_default(),
+        dry_run,
+        &app_handle,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !dry_run && report.inserted + report.updated > 0 {
+        crate::backup::record_write(&app_handle);
+    }
+    if !dry_run && report.inserted > 0 {
+        let _ = app_handle.emit("book-added", &"ok");
+    }
+    if !dry_run && report.updated > 0 {
+        let _ = app_handle.emit("book-updated", &"ok");
+    }
+
+    Ok(report)
+}
+
+/// Saves one book as a ComicInfo.xml, to put in its CBZ.
+#[tauri::command]
+pub async fn export_comicinfo(
+    volume_id: String,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let path = app_handle
+        .dialog()
+        .file()
+        .add_filter("ComicInfo", &["xml"])
+        .set_file_name(crate::comicinfo::FILE_NAME)
+        .blocking_save_file();
+
+    let Some(save_path) = path else {
+        return Err("Export cancelled".to_string());
+    };
+
+    let path_buf = match save_path {
+        tauri_plugin_dialog::FilePath::Path(p) => p,
+        tauri_plugin_dialog::FilePath::Url(u) => {
+            return Err(format!("URL paths not supported: {}", u));
+        }
+    };
+
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::comicinfo::export_book(pool, &app_handle, &volume_id, &path_buf)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(format!("Book exported to {}", path_buf.display()))
+}
+
+/// Adds the comics described by the ComicInfo.xml files in a folder. Without
+/// a `path` the user picks the folder.
+#[tauri::command]
+pub async fn import_comicinfo_folder(
+    path: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::comicinfo::ComicInfoImport, String> {
+    let path_buf = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let Some(folder) = app_handle.dialog().file().blocking_pick_folder() else {
+                return Err("Import cancelled".to_string());
+            };
+
+            match folder {
+                tauri_plugin_dialog::FilePath::Path(p) => p,
+                tauri_plugin_dialog::FilePath::Url(u) => {
+                    return Err(format!("URL paths not supported: {}", u));
+                }
+            }
+        }
+    };
+
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let report = crate::comicinfo::import_folder(pool, &path_buf)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if report.imported > 0 {
+        crate::backup::record_write(&app_handle);
+        let _ = app_handle.emit("book-added", &"ok");
+    }
+
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn export_library_archive(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let path = app_handle
+        .dialog()
+        .file()
+        .add_filter("Library archive", &["zip"])
+        .set_file_name("library.zip")
+        .blocking_save_file();
+
+    let Some(save_path) = path else {
+        return Err("Export cancelled".to_string());
+    };
+
+    let path_buf = match save_path {
+        tauri_plugin_dialog::FilePath::Path(p) => p,
+        tauri_plugin_dialog::FilePath::Url(u) => {
+            return Err(format!("URL paths not supported: {}", u));
+        }
+    };
+
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let count = crate::archive::export_library_archive(pool, &path_buf, &app_handle)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(format!(
+        "Exported {} books to {}",
+        count,
+        path_buf.display()
+    ))
+}
+
+/// Replaces the whole library with an archive made by `export_library_archive`.
+#[tauri::command]
+pub async fn restore_library_archive(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let path = app_handle
+        .dialog()
+        .file()
+        .add_filter("Library archive", &["zip"])
         .blocking_pick_file();
 
     let Some(file_path) = path else {
-        return Err("Import cancelled".to_string());
+        return Err("Restore cancelled".to_string());
     };
 
     let path_buf = match file_path {
@@ -252,13 +655,14 @@ pub async fn import_books_csv(app_handle: tauri::AppHandle) -> Result<String, St
     let guard = instances.0.read().await;
     let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
 
-    let count = crate::db::import_books_from_csv(pool, &path_buf, &app_handle)
+    let count = crate::archive::restore_library_archive(pool, &path_buf, &app_handle)
         .await
         .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
 
     let _ = app_handle.emit("book-added", &"ok");
 
-    Ok(format!("Imported {} books", count))
+    Ok(format!("Restored {} books", count))
 }
 
 #[tauri::command]
@@ -275,6 +679,54 @@ pub async fn find_comic_by_ean(
     Ok(book)
 }
 
+/// Handles an EAN-13 scanned together with its EAN-2/EAN-5 supplement.
+///
+/// If we already have a comic with the same base code, the issue number is
+/// taken from the supplement and the issue is created right away. Returns None
+/// when the series is unknown, so it has to be added by hand.
+#[tauri::command]
+pub async fn add_comic_issue_by_barcode(
+    barcode: String,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<Book>, String> {
+    let scanned = crate::utils::barcode::parse(&barcode).map_err(|e| e.to_string())?;
+    let Some(number) = scanned.issue_number() else {
+        return Err(format!("No issue number in barcode: {barcode}"));
+    };
+
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let Some(series_book) = crate::db::find_comic_by_ean(pool, &scanned.ean)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+
+    // Scanning the same issue twice shouldn't create a duplicate
+    let existing = crate::db::find_comic_issue(pool, &series_book, number)
+        .await
+        .map_err(|e| e.to_string())?;
+    let volume_id = match existing {
+        Some(volume_id) => volume_id,
+        None => {
+            let volume_id = crate::db::clone_book_as_issue(pool, &series_book.volume_id, number)
+                .await
+                .map_err(|e| e.to_string())?;
+            crate::backup::record_write(&app_handle);
+            let _ = app_handle.emit("book-added", &volume_id);
+            volume_id
+        }
+    };
+
+    let book = get_book(pool, &app_handle, &volume_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(book))
+}
+
 #[tauri::command]
 pub async fn clone_book(volume_id: String, app_handle: tauri::AppHandle) -> Result<String, String> {
     let instances = app_handle.state::<DbInstances>();
@@ -283,6 +735,7 @@ pub async fn clone_book(volume_id: String, app_handle: tauri::AppHandle) -> Resu
     let new_volume_id = crate::db::clone_book(pool, &volume_id)
         .await
         .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
     Ok(new_volume_id)
 }
 
@@ -298,6 +751,7 @@ pub async fn clone_book_with_number(
     let new_volume_id = crate::db::clone_book_with_number(pool, &volume_id, number)
         .await
         .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
     Ok(new_volume_id)
 }
 
@@ -334,6 +788,403 @@ pub async fn set_book_groups(
     let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
 
     crate::db::set_book_groups(pool, volume_id, &groups)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn lend_book(
+    volume_id: String,
+    borrower: String,
+    lent_on: Option<String>,
+    due_on: Option<String>,
+    notes: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::Loan, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let loan = crate::db::lend_book(
+        pool,
+        crate::db::LendBook {
+            volume_id: &volume_id,
+            borrower: &borrower,
+            lent_on: lent_on.as_deref(),
+            due_on: due_on.as_deref(),
+            notes: notes.as_deref(),
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(loan)
+}
+
+#[tauri::command]
+pub async fn return_book(
+    volume_id: String,
+    returned_on: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::Loan, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let loan = crate::db::return_book(pool, &volume_id, returned_on.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(loan)
+}
+
+#[tauri::command]
+pub async fn list_active_loans(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::db::Loan>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::list_active_loans(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_overdue_loans(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::db::Loan>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::list_overdue_loans(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Past and current loans, of one book and/or one borrower when given.
+#[tauri::command]
+pub async fn loan_history(
+    volume_id: Option<String>,
+    borrower: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::db::Loan>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::loan_history(pool, volume_id.as_deref(), borrower.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_all_borrowers(app_handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::get_all_borrowers(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_reading_status(
+    volume_id: String,
+    status: crate::db::ReadingStatus,
+    date: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::ReadingState, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let state = crate::db::set_reading_status(pool, &volume_id, status, date.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(state)
+}
+
+#[tauri::command]
+pub async fn set_reading_progress(
+    volume_id: String,
+    current_page: i64,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::ReadingState, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let state = crate::db::set_reading_progress(pool, &volume_id, current_page)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(state)
+}
+
+#[tauri::command]
+pub async fn rate_book(
+    volume_id: String,
+    rating: Option<f64>,
+    review: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::ReadingState, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let state = crate::db::rate_book(pool, &volume_id, rating, review.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(state)
+}
+
+#[tauri::command]
+pub async fn reading_history(
+    volume_id: String,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::db::Reading>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::reading_history(pool, &volume_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_reading(
+    payload: crate::db::UpdateReadingPayload,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::ReadingState, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let state = crate::db::update_reading(pool, &payload)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(state)
+}
+
+#[tauri::command]
+pub async fn delete_reading(
+    reading_id: i64,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::ReadingState, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let state = crate::db::delete_reading(pool, reading_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(state)
+}
+
+#[tauri::command]
+pub fn get_condition_grades() -> Vec<crate::db::ConditionGrade> {
+    crate::db::condition_grades()
+}
+
+#[tauri::command]
+pub async fn add_copy(
+    volume_id: String,
+    details: crate::db::CopyDetails,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::BookCopy, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let copy = crate::db::add_copy(pool, &volume_id, &details)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(copy)
+}
+
+#[tauri::command]
+pub async fn update_copy(
+    copy_id: i64,
+    details: crate::db::CopyDetails,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::BookCopy, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let copy = crate::db::update_copy(pool, copy_id, &details)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(copy)
+}
+
+#[tauri::command]
+pub async fn delete_copy(copy_id: i64, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::delete_copy(pool, copy_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_locations(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::db::Location>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::get_locations(pool)
         .await
         .map_err(|e| e.to_string())
 }
+
+#[tauri::command]
+pub async fn add_location(
+    name: String,
+    kind: crate::db::LocationKind,
+    parent_id: Option<i64>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::Location, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let location = crate::db::add_location(pool, &name, kind, parent_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    Ok(location)
+}
+
+#[tauri::command]
+pub async fn update_location(
+    location_id: i64,
+    name: String,
+    parent_id: Option<i64>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::Location, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let location = crate::db::update_location(pool, location_id, &name, parent_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    Ok(location)
+}
+
+#[tauri::command]
+pub async fn delete_location(location_id: i64, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::delete_location(pool, location_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    Ok(())
+}
+
+/// Moves books and copies to a location, off the shelves without one.
+#[tauri::command]
+pub async fn move_to_location(
+    items: crate::db::LocationItems,
+    location_id: Option<i64>,
+    app_handle: tauri::AppHandle,
+) -> Result<u64, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    let moved = crate::db::move_to_location(pool, &items, location_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::backup::record_write(&app_handle);
+    let _ = app_handle.emit("book-updated", &"ok");
+    Ok(moved)
+}
+
+#[tauri::command]
+pub async fn list_location_contents(
+    location_id: Option<i64>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::db::LocatedCopy>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::list_location_contents(pool, location_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Checks the codes scanned so far on a location, nothing is changed.
+#[tauri::command]
+pub async fn stocktake(
+    location_id: i64,
+    scanned: Vec<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::db::StocktakeReport, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::db::stocktake(pool, location_id, &scanned)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_backups(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<crate::backup::BackupInfo>, String> {
+    crate::backup::list_backups(&app_handle).map_err(|e| e.to_string())
+}
+
+/// Replaces the library with one of the automatic backups, the current state
+/// is backed up first.
+#[tauri::command]
+pub async fn restore_backup(name: String, app_handle: tauri::AppHandle) -> Result<String, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let guard = instances.0.read().await;
+    let pool = guard.get("sqlite:books.db").ok_or("Database not found")?;
+
+    crate::backup::restore_backup(pool, &app_handle, &name)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _ = app_handle.emit("book-added", &"ok");
+
+    Ok(format!("Restored backup {}", name))
+}
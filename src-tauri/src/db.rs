@@ -1,13 +1,15 @@
 use serde::Deserialize;
 use serde::Serialize;
 use sqlx::FromRow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tauri::Manager;
 use uuid::Uuid;
 
-use crate::utils::get_identifier_type;
+use crate::importers::{CsvRow, ImportFormat};
+use crate::providers::{NormalizedBook, NormalizedIdentifier};
+use crate::utils::{barcode, canonical_isbn13, normalize_identifier};
 
-#[derive(Debug, Serialize, FromRow)]
+#[derive(Debug, Default, Serialize, FromRow)]
 pub struct Book {
     pub volume_id: String,
     pub title: String,
@@ -37,63 +39,368 @@ pub struct Book {
     pub web_reader_link: Option<String>,
     pub access_view_status: Option<String>,
     pub quote_sharing_allowed: Option<i64>,
+    pub metadata_source: Option<String>,
     #[sqlx(skip)]
     pub authors: Vec<String>,
     #[sqlx(skip)]
+    pub categories: Vec<String>,
+    #[sqlx(skip)]
     pub groups: Vec<String>,
     #[sqlx(skip)]
     pub isbns: Vec<String>,
     #[sqlx(skip)]
+    pub identifiers: Vec<BookIdentifier>,
+    #[sqlx(skip)]
     pub custom_fields: HashMap<String, String>,
+    /// The loan the book is out on, if it's lent.
+    #[sqlx(skip)]
+    pub loan: Option<Loan>,
+    /// None until the book is shelved, read or rated.
+    #[sqlx(skip)]
+    pub reading: Option<ReadingState>,
+    /// At least one, in the order they were added.
+    #[sqlx(skip)]
+    pub copies: Vec<BookCopy>,
 }
 
-#[derive(Debug, Serialize, FromRow)]
-struct Author {
-    name: String,
+#[derive(Debug, Clone, Serialize)]
+pub struct BookIdentifier {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub identifier: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UpdateBookPayload {
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Loan {
+    pub loan_id: i64,
     pub volume_id: String,
     pub title: String,
-    pub series: Option<String>,
-    pub number: Option<i64>,
-    pub publisher: Option<String>,
-    pub published_date: Option<String>,
-    pub description: Option<String>,
-    pub page_count: Option<i64>,
-    pub language: Option<String>,
-    pub authors: Vec<String>,
-    pub groups: Vec<String>,
-    pub custom_fields: HashMap<String, String>,
+    pub borrower: String,
+    pub lent_on: String,
+    pub due_on: Option<String>,
+    pub returned_on: Option<String>,
+    pub notes: Option<String>,
+    /// Still out after its due date.
+    pub overdue: bool,
 }
 
-#[derive(sqlx::FromRow)]
-struct Category {
-    name: String,
+// Loans with the title of their book and the name of the borrower, dates are
+// compared to today in local time like they're entered
+const LOAN_SELECT: &str = r#"
+    SELECT
+        l.loan_id, l.volume_id, b.title, br.name AS borrower,
+        l.lent_on, l.due_on, l.returned_on, l.notes,
+        COALESCE(l.returned_on IS NULL AND l.due_on < date('now', 'localtime'), 0) AS overdue
+    FROM loans l
+    JOIN borrowers br ON br.borrower_id = l.borrower_id
+    JOIN books b ON b.volume_id = l.volume_id
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ReadingStatus {
+    WantToRead,
+    Reading,
+    Read,
+    Abandoned,
 }
 
-#[derive(sqlx::FromRow)]
-struct Identifier {
-    #[sqlx(rename = "type")]
-    type_: String,
-    identifier: String,
+impl ReadingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReadingStatus::WantToRead => "want_to_read",
+            ReadingStatus::Reading => "reading",
+            ReadingStatus::Read => "read",
+            ReadingStatus::Abandoned => "abandoned",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            ReadingStatus::WantToRead,
+            ReadingStatus::Reading,
+            ReadingStatus::Read,
+            ReadingStatus::Abandoned,
+        ]
+        .into_iter()
+        .find(|s| s.as_str() == value)
+    }
+
+    /// Whether the reading is over, another one starts a re-read.
+    fn is_finished(&self) -> bool {
+        matches!(self, ReadingStatus::Read | ReadingStatus::Abandoned)
+    }
 }
 
-#[derive(Debug, Serialize, FromRow)]
-pub struct CustomField {
-    pub field_id: i64,
-    pub name: String,
+/// One time through a book.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Reading {
+    pub reading_id: i64,
+    pub volume_id: String,
+    pub status: ReadingStatus,
+    pub started_on: Option<String>,
+    pub finished_on: Option<String>,
+    pub current_page: Option<i64>,
+}
+
+/// Where a book stands: its latest reading, how often it was read and what
+/// we thought of it.
+#[derive(Debug, Clone, Default, Serialize, FromRow)]
+pub struct ReadingState {
+    pub volume_id: String,
+    pub status: Option<ReadingStatus>,
+    pub started_on: Option<String>,
+    pub finished_on: Option<String>,
+    pub current_page: Option<i64>,
+    pub times_read: i64,
+    pub rating: Option<f64>,
+    pub review: Option<String>,
+}
+
+// Reading state of every book that has a reading or a review, filtered with
+// a WHERE on b.volume_id
+const READING_STATE_SELECT: &str = r#"
+    SELECT
+        b.volume_id, r.status, r.started_on, r.finished_on, r.current_page,
+        (SELECT COUNT(*) FROM readings WHERE volume_id = b.volume_id AND status = 'read')
+            AS times_read,
+        br.rating, br.review
+    FROM books b
+    LEFT JOIN readings r ON r.reading_id = (
+        SELECT MAX(reading_id) FROM readings WHERE volume_id = b.volume_id
+    )
+    LEFT JOIN book_reviews br ON br.volume_id = b.volume_id
+"#;
+
+/// Condition grading scales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum GradeScale {
+    /// The bookseller grades, As New to Poor.
+    Book,
+    /// CGC's 10-point scale.
+    Comic,
+}
+
+impl GradeScale {
+    /// Grades from best to worst with their names.
+    pub fn grades(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            GradeScale::Book => &[
+                ("As New", "As New"),
+                ("Fine", "Fine"),
+                ("Near Fine", "Near Fine"),
+                ("Very Good", "Very Good"),
+                ("Good", "Good"),
+                ("Fair", "Fair"),
+                ("Poor", "Poor"),
+            ],
+            GradeScale::Comic => &[
+                ("10.0", "Gem Mint"),
+                ("9.9", "Mint"),
+                ("9.8", "NM/M"),
+                ("9.6", "NM+"),
+                ("9.4", "NM"),
+                ("9.2", "NM-"),
+                ("9.0", "VF/NM"),
+                ("8.5", "VF+"),
+                ("8.0", "VF"),
+                ("7.5", "VF-"),
+                ("7.0", "FN/VF"),
+                ("6.5", "FN+"),
+                ("6.0", "FN"),
+                ("5.5", "FN-"),
+                ("5.0", "VG/FN"),
+                ("4.5", "VG+"),
+                ("4.0", "VG"),
+                ("3.5", "VG-"),
+                ("3.0", "GD/VG"),
+                ("2.5", "GD+"),
+                ("2.0", "GD"),
+                ("1.8", "GD-"),
+                ("1.5", "FR/GD"),
+                ("1.0", "FR"),
+                ("0.5", "PR"),
+            ],
+        }
+    }
+
+    /// The grade as it's stored, comic grades are matched on their number so
+    /// "9" is "9.0".
+    fn grade(&self, grade: &str) -> Option<&'static str> {
+        let grades = self.grades().iter().map(|(grade, _)| *grade);
+        match self {
+            GradeScale::Book => grades
+                .into_iter()
+                .find(|g| g.eq_ignore_ascii_case(grade.trim())),
+            GradeScale::Comic => {
+                let number: f64 = grade.trim().parse().ok()?;
+                grades
+                    .into_iter()
+                    .find(|g| g.parse::<f64>().ok() == Some(number))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConditionGrade {
+    pub scale: GradeScale,
+    pub grade: &'static str,
+    pub label: &'static str,
+}
+
+/// A physical copy of a book.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct BookCopy {
+    pub copy_id: i64,
+    pub volume_id: String,
+    pub acquired_on: Option<String>,
+    /// Where it came from, a shop, a fair, a gift.
+    pub source: Option<String>,
+    pub price: Option<f64>,
+    /// ISO 4217 code, like EUR.
+    pub currency: Option<String>,
+    pub grade_scale: Option<GradeScale>,
+    pub grade: Option<String>,
+    pub signed: bool,
+    pub first_edition: bool,
+    pub notes: Option<String>,
+    /// None until it's shelved.
+    pub location_id: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CopyDetails {
+    pub acquired_on: Option<String>,
+    pub source: Option<String>,
+    pub price: Option<f64>,
+    pub currency: Option<String>,
+    pub grade_scale: Option<GradeScale>,
+    pub grade: Option<String>,
+    #[serde(default)]
+    pub signed: bool,
+    #[serde(default)]
+    pub first_edition: bool,
+    pub notes: Option<String>,
+    pub location_id: Option<i64>,
+}
+
+const COPY_SELECT: &str = r#"
+    SELECT
+        copy_id, volume_id, acquired_on, source, price, currency,
+        grade_scale, grade, signed, first_edition, notes, location_id
+    FROM copies
+"#;
+
+/// Kinds of places books are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum LocationKind {
+    Room,
+    Bookcase,
+    Shelf,
+    Box,
 }
 
-#[derive(Debug, Serialize, FromRow)]
-struct CustomFieldValue {
+impl LocationKind {
+    /// Checks a location of this kind can go in a `parent` of that kind, None
+    /// being the top level. Every kind goes in a kind above it, so locations
+    /// can't end up inside themselves.
+    fn check_parent(&self, parent: Option<LocationKind>) -> anyhow::Result<()> {
+        use LocationKind::*;
+        match (self, parent) {
+            (Room, None) | (Bookcase, Some(Room)) | (Shelf, Some(Bookcase)) => Ok(()),
+            (Box, None | Some(Room | Shelf)) => Ok(()),
+            (Room, _) => anyhow::bail!("A room can't be inside another location"),
+            (Bookcase, _) => anyhow::bail!("A bookcase goes in a room"),
+            (Shelf, _) => anyhow::bail!("A shelf goes in a bookcase"),
+            (Box, _) => anyhow::bail!("A box goes in a room, on a shelf or on its own"),
+        }
+    }
+}
+
+/// A room, bookcase, shelf or box.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Location {
+    pub location_id: i64,
+    pub parent_id: Option<i64>,
     pub name: String,
-    pub value: String,
+    pub kind: LocationKind,
+    /// The names from the room down, like "Study › Bookcase 2 › Top shelf".
+    pub path: String,
+    /// Copies kept right in it, not in the locations inside it.
+    pub copy_count: i64,
+}
+
+// The path of every location, prefixed to the queries that show them
+const LOCATION_PATHS: &str = r#"
+    WITH RECURSIVE location_paths(location_id, path) AS (
+        SELECT location_id, name FROM locations WHERE parent_id IS NULL
+        UNION ALL
+        SELECT l.location_id, p.path || ' › ' || l.name
+        FROM locations l
+        JOIN location_paths p ON p.location_id = l.parent_id
+    )
+"#;
+
+const LOCATION_SELECT: &str = r#"
+    SELECT
+        l.location_id, l.parent_id, l.name, l.kind, p.path,
+        (SELECT COUNT(*) FROM copies WHERE location_id = l.location_id) AS copy_count
+    FROM locations l
+    JOIN location_paths p ON p.location_id = l.location_id
+"#;
+
+/// A copy with its book and where it's kept.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LocatedCopy {
+    pub copy_id: i64,
+    pub volume_id: String,
+    pub title: String,
+    pub series: Option<String>,
+    pub number: Option<i64>,
+    pub location_id: Option<i64>,
+    /// Path of the location, None for copies that aren't shelved.
+    pub location: Option<String>,
+}
+
+const LOCATED_COPY_SELECT: &str = r#"
+    SELECT
+        c.copy_id, c.volume_id, b.title, b.series, b.number,
+        c.location_id, p.path AS location
+    FROM copies c
+    JOIN books b ON b.volume_id = c.volume_id
+    LEFT JOIN location_paths p ON p.location_id = c.location_id
+"#;
+
+/// Books and copies to move, a book stands for all its copies.
+#[derive(Debug, Default, Deserialize)]
+pub struct LocationItems {
+    #[serde(default)]
+    pub volume_ids: Vec<String>,
+    #[serde(default)]
+    pub copy_ids: Vec<i64>,
+}
+
+/// What a stocktake found on a location, compared to what should be there.
+#[derive(Debug, Default, Serialize)]
+pub struct StocktakeReport {
+    /// Expected and scanned.
+    pub found: Vec<LocatedCopy>,
+    /// Expected and not scanned.
+    pub missing: Vec<LocatedCopy>,
+    /// Scanned but recorded somewhere else, or nowhere.
+    pub misplaced: Vec<LocatedCopy>,
+    /// Scanned codes of books we don't have, or of more copies than we have.
+    pub unknown: Vec<String>,
 }
 
-#[derive(sqlx::FromRow)]
-struct BookRow {
+#[derive(Debug, Deserialize)]
+pub struct UpdateBookPayload {
     pub volume_id: String,
     pub title: String,
     pub series: Option<String>,
@@ -102,53 +409,88 @@ struct BookRow {
     pub published_date: Option<String>,
     pub description: Option<String>,
     pub page_count: Option<i64>,
-    pub print_type: Option<String>,
-    pub maturity_rating: Option<String>,
     pub language: Option<String>,
-    pub preview_link: Option<String>,
-    pub info_link: Option<String>,
-    pub canonical_link: Option<String>,
-    pub small_thumbnail: Option<String>,
-    pub thumbnail: Option<String>,
-    pub country: Option<String>,
-    pub saleability: Option<String>,
-    pub is_ebook: Option<i64>,
-    pub viewability: Option<String>,
-    pub embeddable: Option<i64>,
-    pub public_domain: Option<i64>,
-    pub text_to_speech_permission: Option<String>,
-    pub epub_available: Option<i64>,
-    pub pdf_available: Option<i64>,
-    pub web_reader_link: Option<String>,
-    pub access_view_status: Option<String>,
-    pub quote_sharing_allowed: Option<i64>,
+    pub authors: Vec<String>,
+    pub groups: Vec<String>,
+    pub custom_fields: HashMap<String, String>,
 }
 
-async fn load_custom_fields_for_book(
-    pool: &sqlx::SqlitePool,
-    volume_id: &str,
-) -> anyhow::Result<HashMap<String, String>> {
-    let fields = sqlx::query_as::<_, CustomFieldValue>(
-        r#"
-        SELECT cf.name, bcf.value
-        FROM book_custom_fields bcf
-        JOIN custom_fields cf ON bcf.field_id = cf.field_id
-        WHERE bcf.volume_id = ?
-        "#,
-    )
-    .bind(volume_id)
-    .fetch_all(pool)
-    .await?;
+/// Which books `load_books` returns, each one maps to a WHERE clause on `books`.
+pub enum BookSelection<'a> {
+    All,
+    VolumeId(&'a str),
+    VolumeIds(&'a [String]),
+    TitleContaining(&'a str),
+    /// Books in the group with this name.
+    Group(&'a str),
+}
+
+impl BookSelection<'_> {
+    fn where_clause(&self) -> String {
+        match self {
+            BookSelection::All => "1 = 1".to_string(),
+            BookSelection::VolumeId(_) => "volume_id = ?".to_string(),
+            BookSelection::VolumeIds(_) => {
+                "volume_id IN (SELECT value FROM json_each(?))".to_string()
+            }
+            BookSelection::TitleContaining(_) => "LOWER(title) LIKE ?".to_string(),
+            BookSelection::Group(_) => r#"volume_id IN (
+                SELECT bg.volume_id FROM book_groups bg
+                JOIN groups g ON g.group_id = bg.group_id
+                WHERE g.name = ?
+            )"#
+            .to_string(),
+        }
+    }
+
+    fn binds(&self) -> Vec<String> {
+        match self {
+            BookSelection::All => vec![],
+            BookSelection::VolumeId(volume_id) => vec![volume_id.to_string()],
+            BookSelection::VolumeIds(volume_ids) => {
+                vec![serde_json::to_string(volume_ids).unwrap_or_default()]
+            }
+            BookSelection::TitleContaining(title) => vec![format!("%{title}%")],
+            BookSelection::Group(name) => vec![name.to_string()],
+        }
+    }
+}
+
+// Runs a query returning (volume_id, value) rows and groups the values by
+// book, keeping the order of the rows.
+async fn fetch_grouped(
+    sqlite_pool: &sqlx::SqlitePool,
+    sql: &str,
+    binds: &[String],
+) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    let mut query = sqlx::query_as::<_, (String, String)>(sql);
+    for bind in binds {
+        query = query.bind(bind);
+    }
 
-    Ok(fields.into_iter().map(|f| (f.name, f.value)).collect())
+    let mut grouped: HashMap<String, Vec<String>> = HashMap::new();
+    for (volume_id, value) in query.fetch_all(sqlite_pool).await? {
+        grouped.entry(volume_id).or_default().push(value);
+    }
+    Ok(grouped)
 }
 
-pub async fn fetch_all_books(
-    pool: &tauri_plugin_sql::DbPool,
-    app_handle: &tauri::AppHandle,
+/// Loads the selected books together with all their relations.
+///
+/// Relations are fetched with one query each for the whole selection and
+/// stitched together here, so the number of queries doesn't grow with the
+/// library. When `books_dir` is given `thumbnail` is replaced with the path of
+/// the downloaded cover, or None if there's no cover on disk.
+pub async fn load_books(
+    sqlite_pool: &sqlx::SqlitePool,
+    selection: BookSelection<'_>,
+    books_dir: Option<&std::path::Path>,
 ) -> anyhow::Result<Vec<Book>> {
-    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
-    let books = sqlx::query_as::<_, Book>(
+    let where_clause = selection.where_clause();
+    let binds = selection.binds();
+    let selected = format!("SELECT volume_id FROM books WHERE {where_clause}");
+
+    let sql = format!(
         r#"
         SELECT
             volume_id, title, series, number, publisher, published_date, description,
@@ -157,73 +499,183 @@ pub async fn fetch_all_books(
             thumbnail, country, saleability, is_ebook, viewability,
             embeddable, public_domain, text_to_speech_permission,
             epub_available, pdf_available, web_reader_link,
-            access_view_status, quote_sharing_allowed
+            access_view_status, quote_sharing_allowed, metadata_source
         FROM books
-        "#,
-    )
-    .fetch_all(sqlite_pool)
-    .await?;
-
-    let app_data_dir = app_handle.path().app_data_dir()?;
-    let books_dir = app_data_dir.join("books");
+        WHERE {where_clause}
+        ORDER BY title, volume_id
+        "#
+    );
+    let mut query = sqlx::query_as::<_, Book>(&sql);
+    for bind in &binds {
+        query = query.bind(bind);
+    }
+    let mut books = query.fetch_all(sqlite_pool).await?;
 
-    let mut result = Vec::new();
-    for mut book in books {
-        let author_rows = sqlx::query_as::<_, Author>(
+    let mut authors = fetch_grouped(
+        sqlite_pool,
+        &format!(
             r#"
-            SELECT a.name
+            SELECT ba.volume_id, a.name
             FROM authors a
             JOIN book_authors ba ON a.author_id = ba.author_id
-            WHERE ba.volume_id = ?
+            WHERE ba.volume_id IN ({selected})
             ORDER BY ba.position
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-        book.authors = author_rows.into_iter().map(|a| a.name).collect();
+            "#
+        ),
+        &binds,
+    )
+    .await?;
+
+    let mut categories = fetch_grouped(
+        sqlite_pool,
+        &format!(
+            r#"
+            SELECT bc.volume_id, c.name
+            FROM categories c
+            JOIN book_categories bc ON c.category_id = bc.category_id
+            WHERE bc.volume_id IN ({selected})
+            ORDER BY c.name
+            "#
+        ),
+        &binds,
+    )
+    .await?;
 
-        let groups = sqlx::query_scalar::<_, String>(
+    let mut groups = fetch_grouped(
+        sqlite_pool,
+        &format!(
             r#"
-            SELECT g.name
+            SELECT bg.volume_id, g.name
             FROM groups g
             JOIN book_groups bg ON g.group_id = bg.group_id
-            WHERE bg.volume_id = ?
+            WHERE bg.volume_id IN ({selected})
             ORDER BY g.name
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-        book.groups = groups;
+            "#
+        ),
+        &binds,
+    )
+    .await?;
 
-        let isbns = sqlx::query_scalar::<_, String>(
-            r#"
-            SELECT identifier
-            FROM book_identifiers
-            WHERE volume_id = ?
-            ORDER BY type DESC
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-        book.isbns = isbns;
+    let identifiers_sql = format!(
+        r#"
+        SELECT volume_id, type, identifier
+        FROM book_identifiers
+        WHERE volume_id IN ({selected})
+        ORDER BY type DESC, identifier
+        "#
+    );
+    let mut query = sqlx::query_as::<_, (String, String, String)>(&identifiers_sql);
+    for bind in &binds {
+        query = query.bind(bind);
+    }
+    let mut identifiers: HashMap<String, Vec<BookIdentifier>> = HashMap::new();
+    for (volume_id, type_, identifier) in query.fetch_all(sqlite_pool).await? {
+        identifiers
+            .entry(volume_id)
+            .or_default()
+            .push(BookIdentifier { type_, identifier });
+    }
 
-        let custom_fields = load_custom_fields_for_book(sqlite_pool, &book.volume_id).await?;
-        book.custom_fields = custom_fields;
+    let custom_fields_sql = format!(
+        r#"
+        SELECT bcf.volume_id, cf.name, bcf.value
+        FROM book_custom_fields bcf
+        JOIN custom_fields cf ON bcf.field_id = cf.field_id
+        WHERE bcf.volume_id IN ({selected})
+        "#
+    );
+    let mut query = sqlx::query_as::<_, (String, String, String)>(&custom_fields_sql);
+    for bind in &binds {
+        query = query.bind(bind);
+    }
+    let mut custom_fields: HashMap<String, HashMap<String, String>> = HashMap::new();
+    for (volume_id, name, value) in query.fetch_all(sqlite_pool).await? {
+        custom_fields
+            .entry(volume_id)
+            .or_default()
+            .insert(name, value);
+    }
 
-        let thumbnail_path = books_dir.join(format!("{}.jpg", book.volume_id));
-        book.thumbnail = if thumbnail_path.exists() {
-            Some(thumbnail_path.to_string_lossy().to_string())
-        } else {
-            None
-        };
+    let loans_sql =
+        format!("{LOAN_SELECT} WHERE l.returned_on IS NULL AND l.volume_id IN ({selected})");
+    let mut query = sqlx::query_as::<_, Loan>(&loans_sql);
+    for bind in &binds {
+        query = query.bind(bind);
+    }
+    let mut loans: HashMap<String, Loan> = query
+        .fetch_all(sqlite_pool)
+        .await?
+        .into_iter()
+        .map(|loan| (loan.volume_id.clone(), loan))
+        .collect();
+
+    let readings_sql = format!(
+        "{READING_STATE_SELECT} WHERE b.volume_id IN ({selected})
+            AND (r.reading_id IS NOT NULL OR br.volume_id IS NOT NULL)"
+    );
+    let mut query = sqlx::query_as::<_, ReadingState>(&readings_sql);
+    for bind in &binds {
+        query = query.bind(bind);
+    }
+    let mut readings: HashMap<String, ReadingState> = query
+        .fetch_all(sqlite_pool)
+        .await?
+        .into_iter()
+        .map(|reading| (reading.volume_id.clone(), reading))
+        .collect();
+
+    let copies_sql = format!("{COPY_SELECT} WHERE volume_id IN ({selected}) ORDER BY copy_id");
+    let mut query = sqlx::query_as::<_, BookCopy>(&copies_sql);
+    for bind in &binds {
+        query = query.bind(bind);
+    }
+    let mut copies: HashMap<String, Vec<BookCopy>> = HashMap::new();
+    for copy in query.fetch_all(sqlite_pool).await? {
+        copies.entry(copy.volume_id.clone()).or_default().push(copy);
+    }
+
+    // One directory listing instead of an exists() call per book
+    let thumbnails: Option<HashSet<std::ffi::OsString>> = books_dir.map(|dir| {
+        std::fs::read_dir(dir)
+            .map(|entries| entries.flatten().map(|e| e.file_name()).collect())
+            .unwrap_or_default()
+    });
 
-        result.push(book);
+    for book in &mut books {
+        book.authors = authors.remove(&book.volume_id).unwrap_or_default();
+        book.categories = categories.remove(&book.volume_id).unwrap_or_default();
+        book.groups = groups.remove(&book.volume_id).unwrap_or_default();
+        book.identifiers = identifiers.remove(&book.volume_id).unwrap_or_default();
+        book.isbns = book
+            .identifiers
+            .iter()
+            .map(|i| i.identifier.clone())
+            .collect();
+        book.custom_fields = custom_fields.remove(&book.volume_id).unwrap_or_default();
+        book.loan = loans.remove(&book.volume_id);
+        book.reading = readings.remove(&book.volume_id);
+        book.copies = copies.remove(&book.volume_id).unwrap_or_default();
+
+        if let (Some(dir), Some(thumbnails)) = (books_dir, &thumbnails) {
+            let file_name = format!("{}.jpg", book.volume_id);
+            book.thumbnail = if thumbnails.contains(std::ffi::OsStr::new(&file_name)) {
+                Some(dir.join(file_name).to_string_lossy().to_string())
+            } else {
+                None
+            };
+        }
     }
 
-    Ok(result)
+    Ok(books)
+}
+
+pub async fn fetch_all_books(
+    pool: &tauri_plugin_sql::DbPool,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<Vec<Book>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let books_dir = app_handle.path().app_data_dir()?.join("books");
+    load_books(sqlite_pool, BookSelection::All, Some(&books_dir)).await
 }
 
 pub async fn get_book(
@@ -232,78 +684,15 @@ pub async fn get_book(
     volume_id: &str,
 ) -> anyhow::Result<Book> {
     let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
-    let mut book = sqlx::query_as::<_, Book>(
-        r#"
-        SELECT
-            volume_id, title, series, number, publisher, published_date, description,
-            page_count, print_type, maturity_rating, language,
-            preview_link, info_link, canonical_link, small_thumbnail,
-            thumbnail, country, saleability, is_ebook, viewability,
-            embeddable, public_domain, text_to_speech_permission,
-            epub_available, pdf_available, web_reader_link,
-            access_view_status, quote_sharing_allowed
-        FROM books
-        WHERE volume_id = ?
-        "#,
-    )
-    .bind(volume_id)
-    .fetch_one(sqlite_pool)
-    .await?;
-
-    let author_rows = sqlx::query_as::<_, Author>(
-        r#"
-        SELECT a.name
-        FROM authors a
-        JOIN book_authors ba ON a.author_id = ba.author_id
-        WHERE ba.volume_id = ?
-        ORDER BY ba.position
-        "#,
-    )
-    .bind(volume_id)
-    .fetch_all(sqlite_pool)
-    .await?;
-    book.authors = author_rows.into_iter().map(|a| a.name).collect();
-
-    let groups = sqlx::query_scalar::<_, String>(
-        r#"
-        SELECT g.name
-        FROM groups g
-        JOIN book_groups bg ON g.group_id = bg.group_id
-        WHERE bg.volume_id = ?
-        ORDER BY g.name
-        "#,
-    )
-    .bind(volume_id)
-    .fetch_all(sqlite_pool)
-    .await?;
-    book.groups = groups;
-
-    let custom_fields = load_custom_fields_for_book(sqlite_pool, volume_id).await?;
-    book.custom_fields = custom_fields;
-
-    let isbns = sqlx::query_scalar::<_, String>(
-        r#"
-        SELECT identifier
-        FROM book_identifiers
-        WHERE volume_id = ?
-        ORDER BY type DESC
-        "#,
+    let books_dir = app_handle.path().app_data_dir()?.join("books");
+    load_books(
+        sqlite_pool,
+        BookSelection::VolumeId(volume_id),
+        Some(&books_dir),
     )
-    .bind(volume_id)
-    .fetch_all(sqlite_pool)
-    .await?;
-    book.isbns = isbns;
-
-    let app_data_dir = app_handle.path().app_data_dir()?;
-    let books_dir = app_data_dir.join("books");
-    let thumbnail_path = books_dir.join(format!("{}.jpg", book.volume_id));
-    book.thumbnail = if thumbnail_path.exists() {
-        Some(thumbnail_path.to_string_lossy().to_string())
-    } else {
-        None
-    };
-
-    Ok(book)
+    .await?
+    .pop()
+    .ok_or_else(|| anyhow::anyhow!("Book {volume_id} not found"))
 }
 
 pub async fn find_books_containing_title(
@@ -311,71 +700,350 @@ pub async fn find_books_containing_title(
     title: &str,
 ) -> anyhow::Result<Vec<Book>> {
     let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
-    let mut books = sqlx::query_as::<_, Book>(
-        r#"
-        SELECT
-            volume_id, title, series, number, publisher, published_date, description,
-            page_count, print_type, maturity_rating, language,
-            preview_link, info_link, canonical_link, small_thumbnail,
-            thumbnail, country, saleability, is_ebook, viewability,
-            embeddable, public_domain, text_to_speech_permission,
-            epub_available, pdf_available, web_reader_link,
-            access_view_status, quote_sharing_allowed
-        FROM books
-        WHERE LOWER(title) LIKE ?
-        "#,
-    )
-    .bind(format!("%{title}%"))
-    .fetch_all(sqlite_pool)
-    .await?;
+    load_books(sqlite_pool, BookSelection::TitleContaining(title), None).await
+}
 
-    for book in &mut books {
-        let author_rows = sqlx::query_as::<_, Author>(
-            r#"
-            SELECT a.name
-            FROM authors a
-            JOIN book_authors ba ON a.author_id = ba.author_id
-            WHERE ba.volume_id = ?
-            ORDER BY ba.position
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-        book.authors = author_rows.into_iter().map(|a| a.name).collect();
+/// A value a book must have for a custom field.
+#[derive(Debug, Deserialize)]
+pub struct CustomFieldFilter {
+    pub name: String,
+    pub value: String,
+}
 
-        let groups = sqlx::query_scalar::<_, String>(
-            r#"
-            SELECT g.name
-            FROM groups g
-            JOIN book_groups bg ON g.group_id = bg.group_id
-            WHERE bg.volume_id = ?
-            ORDER BY g.name
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-        book.groups = groups;
+/// Filters for `query_books`, unset fields don't filter anything.
+#[derive(Debug, Default, Deserialize)]
+pub struct BookFilter {
+    /// Case-insensitive substring of the title, an author or the publisher.
+    pub text: Option<String>,
+    pub group: Option<String>,
+    pub series: Option<String>,
+    pub language: Option<String>,
+    pub custom_field: Option<CustomFieldFilter>,
+    pub has_number: Option<bool>,
+    pub has_thumbnail: Option<bool>,
+    /// Status of the latest reading, "currently reading" is `reading`.
+    pub reading_status: Option<ReadingStatus>,
+    /// Books finished reading in this year.
+    pub read_in: Option<i32>,
+}
 
-        let custom_fields = load_custom_fields_for_book(sqlite_pool, &book.volume_id).await?;
-        book.custom_fields = custom_fields;
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookSort {
+    #[default]
+    Title,
+    Series,
+    Publisher,
+    PublishedDate,
+}
 
-        let isbns = sqlx::query_scalar::<_, String>(
-            r#"
-            SELECT identifier
-            FROM book_identifiers
-            WHERE volume_id = ?
-            ORDER BY type DESC
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-        book.isbns = isbns;
+impl BookSort {
+    fn columns(self) -> &'static [&'static str] {
+        match self {
+            BookSort::Title => &["title"],
+            BookSort::Series => &["series IS NULL", "series", "number", "title"],
+            BookSort::Publisher => &["publisher IS NULL", "publisher", "title"],
+            BookSort::PublishedDate => &["published_date IS NULL", "published_date", "title"],
+        }
     }
+}
 
-    Ok(books)
+#[derive(Debug, Default, Deserialize)]
+pub struct BookQuery {
+    #[serde(default)]
+    pub filter: BookFilter,
+    #[serde(default)]
+    pub sort: BookSort,
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default)]
+    pub offset: i64,
+    /// None returns every book from `offset` on.
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookPage {
+    pub books: Vec<Book>,
+    /// Number of books matching the filter, regardless of offset and limit.
+    pub total: i64,
+}
+
+impl BookQuery {
+    /// Builds the WHERE clause and its binds. `thumbnails` are the volume_ids
+    /// with a cover on disk, only used by the has_thumbnail filter.
+    fn where_clause(&self, thumbnails: &[String]) -> anyhow::Result<(String, Vec<String>)> {
+        let filter = &self.filter;
+        let mut conditions = vec!["1 = 1".to_string()];
+        let mut binds = Vec::new();
+
+        if let Some(text) = filter.text.as_deref().filter(|t| !t.trim().is_empty()) {
+            conditions.push(
+                r#"(
+                    LOWER(title) LIKE ?
+                    OR LOWER(publisher) LIKE ?
+                    OR EXISTS (
+                        SELECT 1 FROM book_authors ba
+                        JOIN authors a ON a.author_id = ba.author_id
+                        WHERE ba.volume_id = books.volume_id AND LOWER(a.name) LIKE ?
+                    )
+                )"#
+                .to_string(),
+            );
+            let pattern = format!("%{}%", text.trim().to_lowercase());
+            binds.extend([pattern.clone(), pattern.clone(), pattern]);
+        }
+
+        if let Some(group) = &filter.group {
+            conditions.push(
+                r#"EXISTS (
+                    SELECT 1 FROM book_groups bg
+                    JOIN groups g ON g.group_id = bg.group_id
+                    WHERE bg.volume_id = books.volume_id AND g.name = ?
+                )"#
+                .to_string(),
+            );
+            binds.push(group.clone());
+        }
+
+        if let Some(series) = &filter.series {
+            conditions.push("series = ?".to_string());
+            binds.push(series.clone());
+        }
+
+        if let Some(language) = &filter.language {
+            conditions.push("language = ?".to_string());
+            binds.push(language.clone());
+        }
+
+        if let Some(custom_field) = &filter.custom_field {
+            conditions.push(
+                r#"EXISTS (
+                    SELECT 1 FROM book_custom_fields bcf
+                    JOIN custom_fields cf ON cf.field_id = bcf.field_id
+                    WHERE bcf.volume_id = books.volume_id AND cf.name = ? AND bcf.value = ?
+                )"#
+                .to_string(),
+            );
+            binds.push(custom_field.name.clone());
+            binds.push(custom_field.value.clone());
+        }
+
+        match filter.has_number {
+            Some(true) => conditions.push("number IS NOT NULL".to_string()),
+            Some(false) => conditions.push("number IS NULL".to_string()),
+            None => {}
+        }
+
+        if let Some(status) = filter.reading_status {
+            conditions.push(
+                r#"(
+                    SELECT status FROM readings
+                    WHERE volume_id = books.volume_id
+                    ORDER BY reading_id DESC LIMIT 1
+                ) = ?"#
+                    .to_string(),
+            );
+            binds.push(status.as_str().to_string());
+        }
+
+        if let Some(year) = filter.read_in {
+            conditions.push(
+                r#"EXISTS (
+                    SELECT 1 FROM readings
+                    WHERE volume_id = books.volume_id AND status = 'read'
+                      AND substr(finished_on, 1, 4) = ?
+                )"#
+                .to_string(),
+            );
+            binds.push(format!("{year:04}"));
+        }
+
+        // Covers only exist on disk, so the ids are passed in as a JSON array
+        if let Some(has_thumbnail) = filter.has_thumbnail {
+            let not = if has_thumbnail { "" } else { "NOT " };
+            conditions.push(format!(
+                "volume_id {not}IN (SELECT value FROM json_each(?))"
+            ));
+            binds.push(serde_json::to_string(thumbnails)?);
+        }
+
+        Ok((conditions.join(" AND "), binds))
+    }
+
+    fn order_by(&self) -> String {
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        let mut columns: Vec<String> = self
+            .sort
+            .columns()
+            .iter()
+            .map(|c| format!("{c} {direction}"))
+            .collect();
+        // Keeps pages stable when the sort columns tie
+        columns.push("volume_id".to_string());
+        columns.join(", ")
+    }
+}
+
+/// Returns one page of the books matching `query` plus the total number of
+/// matches, so the frontend doesn't have to load the whole library.
+pub async fn query_books(
+    pool: &tauri_plugin_sql::DbPool,
+    app_handle: &tauri::AppHandle,
+    query: &BookQuery,
+) -> anyhow::Result<BookPage> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let books_dir = app_handle.path().app_data_dir()?.join("books");
+
+    let thumbnails: Vec<String> = if query.filter.has_thumbnail.is_some() {
+        std::fs::read_dir(&books_dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|e| {
+                        e.file_name()
+                            .to_str()
+                            .and_then(|name| name.strip_suffix(".jpg"))
+                            .map(str::to_string)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let (where_clause, binds) = query.where_clause(&thumbnails)?;
+
+    let count_sql = format!("SELECT COUNT(*) FROM books WHERE {where_clause}");
+    let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+    for bind in &binds {
+        count_query = count_query.bind(bind);
+    }
+    let total = count_query.fetch_one(sqlite_pool).await?;
+
+    // SQLite treats a negative LIMIT as no limit
+    let page_sql = format!(
+        "SELECT volume_id FROM books WHERE {where_clause} ORDER BY {} LIMIT {} OFFSET {}",
+        query.order_by(),
+        query.limit.unwrap_or(-1),
+        query.offset.max(0)
+    );
+    let mut page_query = sqlx::query_scalar::<_, String>(&page_sql);
+    for bind in &binds {
+        page_query = page_query.bind(bind);
+    }
+    let volume_ids = page_query.fetch_all(sqlite_pool).await?;
+
+    let mut books = load_books(
+        sqlite_pool,
+        BookSelection::VolumeIds(&volume_ids),
+        Some(&books_dir),
+    )
+    .await?;
+
+    // load_books orders by title, put the page back in the requested order
+    let positions: HashMap<&str, usize> = volume_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect();
+    books.sort_by_key(|b| positions.get(b.volume_id.as_str()).copied());
+
+    Ok(BookPage { books, total })
+}
+
+/// A `search_books` hit, best matches come first.
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    pub book: Book,
+    /// Excerpt of the best matching column with matches wrapped in
+    /// `<mark></mark>`. The rest of the text isn't escaped, so the frontend
+    /// should split on the markers rather than render it as HTML.
+    pub snippet: String,
+    /// bm25 score, lower is better.
+    pub rank: f64,
+}
+
+// Turns what the user typed into an FTS5 query: every word must match, as a
+// prefix so half-remembered names still hit, and quoted so FTS5 syntax
+// characters in the input are taken literally.
+fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{word}\"*"))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// The volume_id, snippet and rank of the best matches, best first.
+async fn search_hits(
+    sqlite_pool: &sqlx::SqlitePool,
+    input: &str,
+    limit: i64,
+) -> anyhow::Result<Vec<(String, String, f64)>> {
+    let Some(query) = fts_query(input) else {
+        return Ok(Vec::new());
+    };
+
+    // Column weights: title, authors, description, custom_fields
+    let hits = sqlx::query_as::<_, (String, String, f64)>(
+        r#"
+        SELECT
+            b.volume_id,
+            snippet(books_fts, -1, '<mark>', '</mark>', '…', 12),
+            bm25(books_fts, 10.0, 5.0, 1.0, 2.0) AS rank
+        FROM books_fts
+        JOIN books b ON b.rowid = books_fts.rowid
+        WHERE books_fts MATCH ?
+        ORDER BY rank
+        LIMIT ?
+        "#,
+    )
+    .bind(&query)
+    .bind(limit)
+    .fetch_all(sqlite_pool)
+    .await?;
+    Ok(hits)
+}
+
+pub async fn search_books(
+    pool: &tauri_plugin_sql::DbPool,
+    app_handle: &tauri::AppHandle,
+    input: &str,
+    limit: i64,
+) -> anyhow::Result<Vec<SearchResult>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let hits = search_hits(sqlite_pool, input, limit).await?;
+
+    let volume_ids: Vec<String> = hits.iter().map(|(id, _, _)| id.clone()).collect();
+    let books_dir = app_handle.path().app_data_dir()?.join("books");
+    let mut books: HashMap<String, Book> = load_books(
+        sqlite_pool,
+        BookSelection::VolumeIds(&volume_ids),
+        Some(&books_dir),
+    )
+    .await?
+    .into_iter()
+    .map(|b| (b.volume_id.clone(), b))
+    .collect();
+
+    Ok(hits
+        .into_iter()
+        .filter_map(|(volume_id, snippet, rank)| {
+            books.remove(&volume_id).map(|book| SearchResult {
+                book,
+                snippet,
+                rank,
+            })
+        })
+        .collect())
 }
 
 pub async fn set_book_number(
@@ -399,6 +1067,187 @@ pub async fn set_book_number(
     Ok(())
 }
 
+/// Saves a book found by a metadata provider, updating it in place if it was
+/// already stored under the same volume_id.
+pub async fn upsert_normalized_book(
+    pool: &tauri_plugin_sql::DbPool,
+    book: &NormalizedBook,
+) -> anyhow::Result<()> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+    upsert_normalized_book_in_tx(&mut tx, book).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn upsert_normalized_book_in_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    book: &NormalizedBook,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO books (
+          volume_id, title, publisher, published_date, description,
+          page_count, print_type, maturity_rating, language,
+          preview_link, info_link, canonical_link, small_thumbnail,
+          thumbnail, country, saleability, is_ebook, viewability,
+          embeddable, public_domain, text_to_speech_permission,
+          epub_available, pdf_available, web_reader_link,
+          access_view_status, quote_sharing_allowed, metadata_source
+        ) VALUES (
+          ?, ?, ?, ?, ?,
+          ?, ?, ?, ?,
+          ?, ?, ?, ?,
+          ?, ?, ?, ?, ?,
+          ?, ?, ?,
+          ?, ?, ?,
+          ?, ?, ?
+        )
+        ON CONFLICT(volume_id) DO UPDATE SET
+          title=excluded.title,
+          publisher=excluded.publisher,
+          published_date=excluded.published_date,
+          description=excluded.description,
+          page_count=excluded.page_count,
+          print_type=excluded.print_type,
+          maturity_rating=excluded.maturity_rating,
+          language=excluded.language,
+          preview_link=excluded.preview_link,
+          info_link=excluded.info_link,
+          canonical_link=excluded.canonical_link,
+          small_thumbnail=excluded.small_thumbnail,
+          thumbnail=excluded.thumbnail,
+          country=excluded.country,
+          saleability=excluded.saleability,
+          is_ebook=excluded.is_ebook,
+          viewability=excluded.viewability,
+          embeddable=excluded.embeddable,
+          public_domain=excluded.public_domain,
+          text_to_speech_permission=excluded.text_to_speech_permission,
+          epub_available=excluded.epub_available,
+          pdf_available=excluded.pdf_available,
+          web_reader_link=excluded.web_reader_link,
+          access_view_status=excluded.access_view_status,
+          quote_sharing_allowed=excluded.quote_sharing_allowed,
+          metadata_source=excluded.metadata_source
+        "#,
+    )
+    .bind(&book.volume_id)
+    .bind(&book.title)
+    .bind(&book.publisher)
+    .bind(&book.published_date)
+    .bind(&book.description)
+    .bind(book.page_count)
+    .bind(&book.print_type)
+    .bind(&book.maturity_rating)
+    .bind(&book.language)
+    .bind(&book.preview_link)
+    .bind(&book.info_link)
+    .bind(&book.canonical_link)
+    .bind(&book.small_thumbnail)
+    .bind(&book.thumbnail)
+    .bind(&book.country)
+    .bind(&book.saleability)
+    .bind(book.is_ebook)
+    .bind(&book.viewability)
+    .bind(book.embeddable)
+    .bind(book.public_domain)
+    .bind(&book.text_to_speech_permission)
+    .bind(book.epub_available)
+    .bind(book.pdf_available)
+    .bind(&book.web_reader_link)
+    .bind(&book.access_view_status)
+    .bind(book.quote_sharing_allowed)
+    .bind(&book.metadata_source)
+    .execute(&mut **tx)
+    .await?;
+
+    ensure_copy(&mut **tx, &book.volume_id).await?;
+
+    // Stored like the ones entered by hand. Barcodes the provider got wrong
+    // are dropped, types we can't check (e.g. Google's OTHER) are kept as
+    // they are. Fetching the book again doesn't add its identifiers twice.
+    for ii in &book.identifiers {
+        let (id_type, identifier) = match normalize_identifier(&ii.identifier) {
+            Ok((t, normalized)) => (t.to_string(), normalized),
+            Err(_) if matches!(ii.type_.as_str(), "ISBN_10" | "ISBN_13" | "EAN_13") => continue,
+            Err(_) => (ii.type_.clone(), ii.identifier.trim().to_string()),
+        };
+        if identifier.is_empty() {
+            continue;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO book_identifiers (volume_id, type, identifier, canonical_isbn13)
+            SELECT ?1, ?2, ?3, ?4
+            WHERE NOT EXISTS (
+              SELECT 1 FROM book_identifiers WHERE volume_id = ?1 AND identifier = ?3
+            )
+            "#,
+        )
+        .bind(&book.volume_id)
+        .bind(&id_type)
+        .bind(&identifier)
+        .bind(canonical_isbn13(&identifier))
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    // Authors (preserve order)
+    if !book.authors.is_empty() {
+        sqlx::query("DELETE FROM book_authors WHERE volume_id = ?")
+            .bind(&book.volume_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for (pos, name) in book.authors.iter().enumerate() {
+            sqlx::query(r#"INSERT INTO authors (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
+                .bind(name)
+                .execute(&mut **tx)
+                .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO book_authors (volume_id, author_id, position)
+                SELECT ?, author_id, ?
+                FROM authors WHERE name = ?
+                ON CONFLICT(volume_id, author_id)
+                DO UPDATE SET position = excluded.position
+                "#,
+            )
+            .bind(&book.volume_id)
+            .bind(pos as i64)
+            .bind(name)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    for name in &book.categories {
+        sqlx::query(r#"INSERT INTO categories (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
+            .bind(name)
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO book_categories (volume_id, category_id)
+            SELECT ?, category_id
+            FROM categories WHERE name = ?
+            ON CONFLICT(volume_id, category_id) DO NOTHING
+            "#,
+        )
+        .bind(&book.volume_id)
+        .bind(name)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn insert_book(
     pool: &tauri_plugin_sql::DbPool,
     title: &str,
@@ -446,6 +1295,8 @@ pub async fn insert_book(
     .execute(&mut *tx)
     .await?;
 
+    ensure_copy(&mut *tx, &volume_id).await?;
+
     // Authors (optional)
     if !authors.is_empty() {
         sqlx::query("DELETE FROM book_authors WHERE volume_id = ?")
@@ -500,16 +1351,17 @@ pub async fn insert_book(
 
     // Save ISBN/EAN-13 if present
     if let Some(id_value) = identifier.filter(|s| !s.is_empty()) {
-        let id_type = get_identifier_type(id_value)?;
+        let (id_type, id_value) = normalize_identifier(id_value)?;
         sqlx::query(
             r#"
-            INSERT INTO book_identifiers (volume_id, type, identifier)
-            VALUES (?, ?, ?)
+            INSERT INTO book_identifiers (volume_id, type, identifier, canonical_isbn13)
+            VALUES (?, ?, ?, ?)
             "#,
         )
         .bind(&volume_id)
         .bind(id_type)
-        .bind(id_value)
+        .bind(&id_value)
+        .bind(canonical_isbn13(&id_value))
         .execute(&mut *tx)
         .await?;
     }
@@ -540,17 +1392,65 @@ pub async fn insert_book(
 
 pub async fn isbn_exists(pool: &tauri_plugin_sql::DbPool, isbn: &str) -> anyhow::Result<bool> {
     let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
-    let row = sqlx::query(
-        r#"
-        SELECT 1
-        FROM book_identifiers
-        WHERE identifier = ?
-        LIMIT 1
-        "#,
+    identifier_exists(sqlite_pool, isbn).await
+}
+
+/// The book we own with `isbn`, matched like `isbn_exists`.
+pub async fn find_book_by_isbn(
+    pool: &tauri_plugin_sql::DbPool,
+    isbn: &str,
+) -> anyhow::Result<Option<Book>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let volume_id = match canonical_isbn13(isbn) {
+        Some(canonical) => {
+            sqlx::query_scalar::<_, String>(
+                "SELECT volume_id FROM book_identifiers WHERE canonical_isbn13 = ? LIMIT 1",
+            )
+            .bind(canonical)
+            .fetch_optional(sqlite_pool)
+            .await?
+        }
+        None => {
+            sqlx::query_scalar::<_, String>(
+                "SELECT volume_id FROM book_identifiers WHERE identifier = ? LIMIT 1",
+            )
+            .bind(isbn)
+            .fetch_optional(sqlite_pool)
+            .await?
+        }
+    };
+
+    let Some(volume_id) = volume_id else {
+        return Ok(None);
+    };
+    Ok(
+        load_books(sqlite_pool, BookSelection::VolumeId(&volume_id), None)
+            .await?
+            .pop(),
     )
-    .bind(isbn)
-    .fetch_optional(sqlite_pool)
-    .await?;
+}
+
+// ISBN-10s, ISBN-13s and EANs are matched on their canonical form, so the
+// ISBN-10 of a book we stored through its ISBN-13 is found too. Anything we
+// can't validate is looked up as is.
+async fn identifier_exists<'e, E>(executor: E, identifier: &str) -> anyhow::Result<bool>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let row = match canonical_isbn13(identifier) {
+        Some(canonical) => {
+            sqlx::query("SELECT 1 FROM book_identifiers WHERE canonical_isbn13 = ? LIMIT 1")
+                .bind(canonical)
+                .fetch_optional(executor)
+                .await?
+        }
+        None => {
+            sqlx::query("SELECT 1 FROM book_identifiers WHERE identifier = ? LIMIT 1")
+                .bind(identifier)
+                .fetch_optional(executor)
+                .await?
+        }
+    };
 
     Ok(row.is_some())
 }
@@ -664,10 +1564,54 @@ pub async fn update_book(
     Ok(())
 }
 
-pub async fn delete_book(pool: &tauri_plugin_sql::DbPool, volume_id: &str) -> anyhow::Result<()> {
+/// Deletes a book with its copies, loan history, readings and review. A book
+/// that's lent out is only deleted with `force`, so it isn't forgotten by
+/// accident.
+pub async fn delete_book(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: &str,
+    force: bool,
+) -> anyhow::Result<()> {
     let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
     let mut tx = sqlite_pool.begin().await?;
 
+    if !force {
+        let loan = sqlx::query_as::<_, Loan>(&format!(
+            "{LOAN_SELECT} WHERE l.returned_on IS NULL AND l.volume_id = ?"
+        ))
+        .bind(volume_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(loan) = loan {
+            anyhow::bail!(
+                "\"{}\" is lent to {} since {}, return it before deleting it",
+                loan.title,
+                loan.borrower,
+                loan.lent_on
+            );
+        }
+    }
+
+    sqlx::query("DELETE FROM loans WHERE volume_id = ?")
+        .bind(volume_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM readings WHERE volume_id = ?")
+        .bind(volume_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM book_reviews WHERE volume_id = ?")
+        .bind(volume_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM copies WHERE volume_id = ?")
+        .bind(volume_id)
+        .execute(&mut *tx)
+        .await?;
+
     sqlx::query("DELETE FROM book_authors WHERE volume_id = ?")
         .bind(volume_id)
         .execute(&mut *tx)
@@ -692,170 +1636,94 @@ pub async fn delete_book(pool: &tauri_plugin_sql::DbPool, volume_id: &str) -> an
     Ok(())
 }
 
-pub async fn export_books_to_csv(
-    pool: &tauri_plugin_sql::DbPool,
-    save_path: &std::path::Path,
-    app_handle: &tauri::AppHandle,
-) -> anyhow::Result<()> {
-    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
-
+// Columns every CSV export starts with, each custom field follows as a column
+// of its own.
+const CSV_COLUMNS: &[&str] = &[
+    "volume_id",
+    "title",
+    "series",
+    "number",
+    "authors",
+    "categories",
+    "identifiers",
+    "groups",
+    "publisher",
+    "published_date",
+    "description",
+    "page_count",
+    "print_type",
+    "maturity_rating",
+    "language",
+    "preview_link",
+    "info_link",
+    "canonical_link",
+    "small_thumbnail",
+    "thumbnail",
+    "country",
+    "saleability",
+    "is_ebook",
+    "viewability",
+    "embeddable",
+    "public_domain",
+    "text_to_speech_permission",
+    "epub_available",
+    "pdf_available",
+    "web_reader_link",
+    "access_view_status",
+    "quote_sharing_allowed",
+    "metadata_source",
+    "reading_status",
+    "started_on",
+    "finished_on",
+    "current_page",
+    "times_read",
+    "rating",
+    "review",
+];
+
+// Separates the values of list columns (authors, categories, ...)
+pub const CSV_LIST_SEPARATOR: &str = "; ";
+
+/// Writes every book as CSV and returns the exported volume_ids.
+///
+/// Copies aren't exported, an import gives every new book a single copy.
+/// Library archives keep them.
+async fn write_books_csv<W: std::io::Write>(
+    sqlite_pool: &sqlx::SqlitePool,
+    writer: W,
+) -> anyhow::Result<Vec<String>> {
     // First, collect all unique custom field names
     let all_custom_field_names =
         sqlx::query_scalar::<_, String>("SELECT DISTINCT name FROM custom_fields ORDER BY name")
             .fetch_all(sqlite_pool)
             .await?;
 
-    let books = sqlx::query_as::<_, BookRow>(
-        r#"
-        SELECT
-            volume_id, title, series, number, publisher, published_date, description,
-            page_count, print_type, maturity_rating, language,
-            preview_link, info_link, canonical_link, small_thumbnail,
-            thumbnail, country, saleability, is_ebook, viewability,
-            embeddable, public_domain, text_to_speech_permission,
-            epub_available, pdf_available, web_reader_link,
-            access_view_status, quote_sharing_allowed
-        FROM books
-        ORDER BY title
-        "#,
-    )
-    .fetch_all(sqlite_pool)
-    .await?;
+    // Keep the original thumbnail URLs, covers are copied separately
+    let books = load_books(sqlite_pool, BookSelection::All, None).await?;
 
-    let file = std::fs::File::create(save_path)?;
-    let mut wtr = csv::Writer::from_writer(file);
-
-    // Create images directory next to CSV file
-    let csv_parent = save_path.parent().unwrap_or(std::path::Path::new("."));
-    let images_dir = csv_parent.join("images");
-    std::fs::create_dir_all(&images_dir)?;
-
-    // Get app books directory
-    let app_data_dir = app_handle.path().app_data_dir()?;
-    let books_dir = app_data_dir.join("books");
-
-    // Write header
-    let mut headers = vec![
-        "volume_id",
-        "title",
-        "series",
-        "number",
-        "authors",
-        "categories",
-        "identifiers",
-        "groups",
-        "publisher",
-        "published_date",
-        "description",
-        "page_count",
-        "print_type",
-        "maturity_rating",
-        "language",
-        "preview_link",
-        "info_link",
-        "canonical_link",
-        "small_thumbnail",
-        "thumbnail",
-        "country",
-        "saleability",
-        "is_ebook",
-        "viewability",
-        "embeddable",
-        "public_domain",
-        "text_to_speech_permission",
-        "epub_available",
-        "pdf_available",
-        "web_reader_link",
-        "access_view_status",
-        "quote_sharing_allowed",
-    ];
+    let mut wtr = csv::Writer::from_writer(writer);
 
+    let mut headers: Vec<&str> = CSV_COLUMNS.to_vec();
     for field_name in &all_custom_field_names {
         headers.push(field_name);
     }
-
     wtr.write_record(&headers)?;
 
-    // Write data rows
+    let mut volume_ids = Vec::with_capacity(books.len());
     for book in books {
-        let authors = sqlx::query_as::<_, Author>(
-            r#"
-            SELECT a.name
-            FROM authors a
-            JOIN book_authors ba ON a.author_id = ba.author_id
-            WHERE ba.volume_id = ?
-            ORDER BY ba.position
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-
-        let categories = sqlx::query_as::<_, Category>(
-            r#"
-            SELECT c.name
-            FROM categories c
-            JOIN book_categories bc ON c.category_id = bc.category_id
-            WHERE bc.volume_id = ?
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-
-        let identifiers = sqlx::query_as::<_, Identifier>(
-            r#"
-            SELECT type, identifier
-            FROM book_identifiers
-            WHERE volume_id = ?
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-
-        let groups = sqlx::query_scalar::<_, String>(
-            r#"
-            SELECT g.name
-            FROM groups g
-            JOIN book_groups bg ON g.group_id = bg.group_id
-            WHERE bg.volume_id = ?
-            "#,
-        )
-        .bind(&book.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
-
-        let custom_fields = load_custom_fields_for_book(sqlite_pool, &book.volume_id).await?;
-
-        // Copy thumbnail if it exists
-        let source_path = books_dir.join(format!("{}.jpg", book.volume_id));
-        if source_path.exists() {
-            let dest_path = images_dir.join(format!("{}.jpg", book.volume_id));
-            std::fs::copy(&source_path, &dest_path).ok();
-        }
-
         let mut record = vec![
             book.volume_id.clone(),
             book.title.clone(),
             book.series.clone().unwrap_or_default(),
             book.number.map(|n| n.to_string()).unwrap_or_default(),
-            authors
-                .iter()
-                .map(|a| a.name.clone())
-                .collect::<Vec<_>>()
-                .join("; "),
-            categories
-                .iter()
-                .map(|c| c.name.clone())
-                .collect::<Vec<_>>()
-                .join("; "),
-            identifiers
+            book.authors.join(CSV_LIST_SEPARATOR),
+            book.categories.join(CSV_LIST_SEPARATOR),
+            book.identifiers
                 .iter()
                 .map(|i| format!("{}:{}", i.type_, i.identifier))
                 .collect::<Vec<_>>()
-                .join("; "),
-            groups.join("; "),
+                .join(CSV_LIST_SEPARATOR),
+            book.groups.join(CSV_LIST_SEPARATOR),
             book.publisher.unwrap_or_default(),
             book.published_date.unwrap_or_default(),
             book.description.unwrap_or_default(),
@@ -888,67 +1756,192 @@ pub async fn export_books_to_csv(
             book.quote_sharing_allowed
                 .map(|n| n.to_string())
                 .unwrap_or_default(),
+            book.metadata_source.unwrap_or_default(),
         ];
 
+        let reading = book.reading.unwrap_or_default();
+        record.extend([
+            reading
+                .status
+                .map(|s| s.as_str().to_string())
+                .unwrap_or_default(),
+            reading.started_on.unwrap_or_default(),
+            reading.finished_on.unwrap_or_default(),
+            reading
+                .current_page
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+            // Empty rather than 0 for books without a reading or review
+            if reading.volume_id.is_empty() {
+                String::new()
+            } else {
+                reading.times_read.to_string()
+            },
+            reading.rating.map(|r| r.to_string()).unwrap_or_default(),
+            reading.review.unwrap_or_default(),
+        ]);
+
         // Add custom field values in the same order as headers
         for field_name in &all_custom_field_names {
-            record.push(custom_fields.get(field_name).cloned().unwrap_or_default());
+            record.push(
+                book.custom_fields
+                    .get(field_name)
+                    .cloned()
+                    .unwrap_or_default(),
+            );
         }
 
         wtr.write_record(&record)?;
+        volume_ids.push(book.volume_id);
     }
 
     wtr.flush()?;
+    Ok(volume_ids)
+}
+
+pub async fn export_books_to_csv(
+    pool: &tauri_plugin_sql::DbPool,
+    save_path: &std::path::Path,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<()> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+
+    let file = std::fs::File::create(save_path)?;
+    let volume_ids = write_books_csv(sqlite_pool, file).await?;
+
+    // Create images directory next to CSV file
+    let csv_parent = save_path.parent().unwrap_or(std::path::Path::new("."));
+    let images_dir = csv_parent.join("images");
+    std::fs::create_dir_all(&images_dir)?;
+
+    // Get app books directory
+    let app_data_dir = app_handle.path().app_data_dir()?;
+    let books_dir = app_data_dir.join("books");
+
+    for volume_id in volume_ids {
+        // Copy thumbnail if it exists
+        let source_path = books_dir.join(format!("{}.jpg", volume_id));
+        if source_path.exists() {
+            let dest_path = images_dir.join(format!("{}.jpg", volume_id));
+            std::fs::copy(&source_path, &dest_path).ok();
+        }
+    }
+
     Ok(())
 }
 
+/// The book a comic barcode was first scanned as. 978/979 codes are stored as
+/// ISBN-13s, so the match is on the canonical form rather than the type.
 pub async fn find_comic_by_ean(
     pool: &tauri_plugin_sql::DbPool,
     ean: &str,
 ) -> anyhow::Result<Option<Book>> {
     let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
-    let mut book = sqlx::query_as::<_, Book>(
+    let volume_id = sqlx::query_scalar::<_, String>(
         r#"
-        SELECT DISTINCT b.*
-        FROM books b
-        JOIN book_identifiers bi ON b.volume_id = bi.volume_id
-        WHERE bi.type = 'EAN_13' AND bi.identifier = ?
+        SELECT volume_id
+        FROM book_identifiers
+        WHERE canonical_isbn13 = ?
+        LIMIT 1
         "#,
     )
-    .bind(ean)
+    .bind(canonical_isbn13(ean).unwrap_or_else(|| ean.to_string()))
     .fetch_optional(sqlite_pool)
     .await?;
 
-    if let Some(ref mut b) = book {
-        let groups = sqlx::query_scalar::<_, String>(
-            r#"
-            SELECT g.name
-            FROM groups g
-            JOIN book_groups bg ON g.group_id = bg.group_id
-            WHERE bg.volume_id = ?
-            ORDER BY g.name
-            "#,
-        )
-        .bind(&b.volume_id)
-        .fetch_all(sqlite_pool)
-        .await?;
+    let Some(volume_id) = volume_id else {
+        return Ok(None);
+    };
+
+    Ok(
+        load_books(sqlite_pool, BookSelection::VolumeId(&volume_id), None)
+            .await?
+            .pop(),
+    )
+}
 
-        let custom_fields = load_custom_fields_for_book(sqlite_pool, &b.volume_id).await?;
+pub async fn clone_book(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: &str,
+) -> anyhow::Result<String> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
 
-        b.groups = groups;
-        b.custom_fields = custom_fields;
-    }
+    let new_volume_id = clone_book_in_tx(&mut tx, volume_id).await?;
 
-    Ok(book)
+    tx.commit().await?;
+    Ok(new_volume_id)
 }
 
-pub async fn clone_book(
+/// Create a new issue of the series `volume_id` belongs to, everything but the
+/// number is copied from it.
+pub async fn clone_book_as_issue(
     pool: &tauri_plugin_sql::DbPool,
     volume_id: &str,
+    number: i64,
 ) -> anyhow::Result<String> {
     let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
     let mut tx = sqlite_pool.begin().await?;
 
+    let new_volume_id = clone_issue_in_tx(&mut tx, volume_id, number).await?;
+
+    tx.commit().await?;
+    Ok(new_volume_id)
+}
+
+async fn clone_issue_in_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    volume_id: &str,
+    number: i64,
+) -> anyhow::Result<String> {
+    let new_volume_id = clone_book_in_tx(tx, volume_id).await?;
+
+    sqlx::query("UPDATE books SET number = ? WHERE volume_id = ?")
+        .bind(number)
+        .bind(&new_volume_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(new_volume_id)
+}
+
+/// Find the issue with the given number in the same series as `book`, books
+/// without a series are matched on their title.
+pub async fn find_comic_issue(
+    pool: &tauri_plugin_sql::DbPool,
+    book: &Book,
+    number: i64,
+) -> anyhow::Result<Option<String>> {
+    find_series_issue(pool, book.series.as_deref().unwrap_or(&book.title), number).await
+}
+
+/// The issue `number` of `series`, books without a series are their own.
+pub async fn find_series_issue(
+    pool: &tauri_plugin_sql::DbPool,
+    series: &str,
+    number: i64,
+) -> anyhow::Result<Option<String>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let volume_id = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT volume_id
+        FROM books
+        WHERE number = ? AND COALESCE(series, title) = ?
+        LIMIT 1
+        "#,
+    )
+    .bind(number)
+    .bind(series)
+    .fetch_optional(sqlite_pool)
+    .await?;
+
+    Ok(volume_id)
+}
+
+async fn clone_book_in_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    volume_id: &str,
+) -> anyhow::Result<String> {
     // Get the original book
     let book = sqlx::query_as::<_, Book>(
         r#"
@@ -959,13 +1952,13 @@ pub async fn clone_book(
             thumbnail, country, saleability, is_ebook, viewability,
             embeddable, public_domain, text_to_speech_permission,
             epub_available, pdf_available, web_reader_link,
-            access_view_status, quote_sharing_allowed
+            access_view_status, quote_sharing_allowed, metadata_source
         FROM books
         WHERE volume_id = ?
         "#,
     )
     .bind(volume_id)
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut **tx)
     .await?;
 
     let new_volume_id = Uuid::new_v4().to_string();
@@ -979,14 +1972,16 @@ pub async fn clone_book(
           language, preview_link, info_link, canonical_link,
           small_thumbnail, thumbnail, country, saleability, is_ebook,
           viewability, embeddable, public_domain, text_to_speech_permission,
-          epub_available, pdf_available, web_reader_link, access_view_status, quote_sharing_allowed
+          epub_available, pdf_available, web_reader_link, access_view_status, quote_sharing_allowed,
+          metadata_source
         ) VALUES (
           ?, ?, ?, ?, ?, ?,
           ?, ?, ?, ?,
           ?, ?, ?, ?,
           ?, ?, ?, ?, ?,
           ?, ?, ?, ?,
-          ?, ?, ?, ?, ?
+          ?, ?, ?, ?, ?,
+          ?
         )
         "#,
     )
@@ -1018,9 +2013,12 @@ pub async fn clone_book(
     .bind(&book.web_reader_link)
     .bind(&book.access_view_status)
     .bind(book.quote_sharing_allowed)
-    .execute(&mut *tx)
+    .bind(&book.metadata_source)
+    .execute(&mut **tx)
     .await?;
 
+    ensure_copy(&mut **tx, &new_volume_id).await?;
+
     // Clone authors
     sqlx::query(
         r#"
@@ -1032,7 +2030,7 @@ pub async fn clone_book(
     )
     .bind(&new_volume_id)
     .bind(volume_id)
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await?;
 
     // Clone groups
@@ -1046,21 +2044,21 @@ pub async fn clone_book(
     )
     .bind(&new_volume_id)
     .bind(volume_id)
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await?;
 
     // Clone identifiers
     sqlx::query(
         r#"
-        INSERT INTO book_identifiers (volume_id, type, identifier)
-        SELECT ?, type, identifier
+        INSERT INTO book_identifiers (volume_id, type, identifier, canonical_isbn13)
+        SELECT ?, type, identifier, canonical_isbn13
         FROM book_identifiers
         WHERE volume_id = ?
         "#,
     )
     .bind(&new_volume_id)
     .bind(volume_id)
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await?;
 
     sqlx::query(
@@ -1073,10 +2071,9 @@ pub async fn clone_book(
     )
     .bind(&new_volume_id)
     .bind(volume_id)
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await?;
 
-    tx.commit().await?;
     Ok(new_volume_id)
 }
 
@@ -1089,137 +2086,30 @@ pub async fn clone_book_with_number(
     let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
     let mut tx = sqlite_pool.begin().await?;
 
-    // Get the original book
-    let book = sqlx::query_as::<_, Book>(
-        r#"
-        SELECT
-            volume_id, title, series, number, publisher, published_date, description,
-            page_count, print_type, maturity_rating, language,
-            preview_link, info_link, canonical_link, small_thumbnail,
-            thumbnail, country, saleability, is_ebook, viewability,
-            embeddable, public_domain, text_to_speech_permission,
-            epub_available, pdf_available, web_reader_link,
-            access_view_status, quote_sharing_allowed
-        FROM books
-        WHERE volume_id = ?
-        "#,
-    )
-    .bind(volume_id)
-    .fetch_one(&mut *tx)
-    .await?;
+    let new_volume_id = clone_issue_in_tx(&mut tx, volume_id, number).await?;
 
-    let new_volume_id = Uuid::new_v4().to_string();
+    // The volume_id we receive here was already cloned by another function.
+    // I don't feel like fixing that as of now cause it would complicate things
+    // a bit and I just things to work, even if a bit janky.
+    //
+    // So here, after we cloned all the other numbers we delete the original clone.
+    // We'll get around to fix this.
+    sqlx::query("DELETE FROM book_authors WHERE volume_id = ?")
+        .bind(volume_id)
+        .execute(&mut *tx)
+        .await?;
 
-    // Insert cloned book with the specified number
-    sqlx::query(
-        r#"
-        INSERT INTO books (
-          volume_id, title, series, number, publisher, published_date,
-          description, page_count, print_type, maturity_rating,
-          language, preview_link, info_link, canonical_link,
-          small_thumbnail, thumbnail, country, saleability, is_ebook,
-          viewability, embeddable, public_domain, text_to_speech_permission,
-          epub_available, pdf_available, web_reader_link, access_view_status, quote_sharing_allowed
-        ) VALUES (
-          ?, ?, ?, ?, ?, ?,
-          ?, ?, ?, ?,
-          ?, ?, ?, ?,
-          ?, ?, ?, ?, ?,
-          ?, ?, ?, ?,
-          ?, ?, ?, ?, ?
-        )
-        "#,
-    )
-    .bind(&new_volume_id)
-    .bind(&book.title)
-    .bind(&book.series)
-    .bind(Some(number))
-    .bind(&book.publisher)
-    .bind(&book.published_date)
-    .bind(&book.description)
-    .bind(book.page_count)
-    .bind(&book.print_type)
-    .bind(&book.maturity_rating)
-    .bind(&book.language)
-    .bind(&book.preview_link)
-    .bind(&book.info_link)
-    .bind(&book.canonical_link)
-    .bind(&book.small_thumbnail)
-    .bind(&book.thumbnail)
-    .bind(&book.country)
-    .bind(&book.saleability)
-    .bind(book.is_ebook)
-    .bind(&book.viewability)
-    .bind(book.embeddable)
-    .bind(book.public_domain)
-    .bind(&book.text_to_speech_permission)
-    .bind(book.epub_available)
-    .bind(book.pdf_available)
-    .bind(&book.web_reader_link)
-    .bind(&book.access_view_status)
-    .bind(book.quote_sharing_allowed)
-    .execute(&mut *tx)
-    .await?;
+    sqlx::query("DELETE FROM book_groups WHERE volume_id = ?")
+        .bind(volume_id)
+        .execute(&mut *tx)
+        .await?;
 
-    // Clone authors
-    sqlx::query(
-        r#"
-        INSERT INTO book_authors (volume_id, author_id, position)
-        SELECT ?, author_id, position
-        FROM book_authors
-        WHERE volume_id = ?
-        "#,
-    )
-    .bind(&new_volume_id)
-    .bind(volume_id)
-    .execute(&mut *tx)
-    .await?;
+    sqlx::query("DELETE FROM book_custom_fields WHERE volume_id = ?")
+        .bind(volume_id)
+        .execute(&mut *tx)
+        .await?;
 
-    // Clone groups
-    sqlx::query(
-        r#"
-        INSERT INTO book_groups (volume_id, group_id)
-        SELECT ?, group_id
-        FROM book_groups
-        WHERE volume_id = ?
-        "#,
-    )
-    .bind(&new_volume_id)
-    .bind(volume_id)
-    .execute(&mut *tx)
-    .await?;
-
-    // Clone custom fields
-    sqlx::query(
-        r#"
-        INSERT INTO book_custom_fields (volume_id, field_id, value)
-        SELECT ?, field_id, value
-        FROM book_custom_fields
-        WHERE volume_id = ?
-        "#,
-    )
-    .bind(&new_volume_id)
-    .bind(volume_id)
-    .execute(&mut *tx)
-    .await?;
-
-    // The volume_id we receive here was already cloned by another function.
-    // I don't feel like fixing that as of now cause it would complicate things
-    // a bit and I just things to work, even if a bit janky.
-    //
-    // So here, after we cloned all the other numbers we delete the original clone.
-    // We'll get around to fix this.
-    sqlx::query("DELETE FROM book_authors WHERE volume_id = ?")
-        .bind(volume_id)
-        .execute(&mut *tx)
-        .await?;
-
-    sqlx::query("DELETE FROM book_groups WHERE volume_id = ?")
-        .bind(volume_id)
-        .execute(&mut *tx)
-        .await?;
-
-    sqlx::query("DELETE FROM book_custom_fields WHERE volume_id = ?")
+    sqlx::query("DELETE FROM copies WHERE volume_id = ?")
         .bind(volume_id)
         .execute(&mut *tx)
         .await?;
@@ -1287,155 +2177,2897 @@ pub async fn set_book_groups(
     Ok(())
 }
 
-pub async fn import_books_from_csv(
+/// A date as YYYY-MM-DD, None when it's empty. Anything SQLite can't read as
+/// a date is an error.
+async fn valid_date(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    date: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    let Some(date) = date.map(str::trim).filter(|d| !d.is_empty()) else {
+        return Ok(None);
+    };
+    sqlx::query_scalar::<_, Option<String>>("SELECT date(?)")
+        .bind(date)
+        .fetch_one(&mut **tx)
+        .await?
+        .map(Some)
+        .ok_or_else(|| anyhow::anyhow!("Invalid date {date}, expected YYYY-MM-DD"))
+}
+
+/// Like `valid_date`, today when there's no date.
+async fn date_or_today(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    date: Option<&str>,
+) -> anyhow::Result<String> {
+    match valid_date(tx, date).await? {
+        Some(date) => Ok(date),
+        None => Ok(sqlx::query_scalar("SELECT date('now', 'localtime')")
+            .fetch_one(&mut **tx)
+            .await?),
+    }
+}
+
+pub struct LendBook<'a> {
+    pub volume_id: &'a str,
+    pub borrower: &'a str,
+    /// Today when missing.
+    pub lent_on: Option<&'a str>,
+    pub due_on: Option<&'a str>,
+    pub notes: Option<&'a str>,
+}
+
+/// Lends a book, borrowers are created by name the first time they borrow
+/// something.
+pub async fn lend_book(
     pool: &tauri_plugin_sql::DbPool,
-    csv_path: &std::path::Path,
-    app_handle: &tauri::AppHandle,
-) -> anyhow::Result<usize> {
+    lend: LendBook<'_>,
+) -> anyhow::Result<Loan> {
     let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let borrower = lend.borrower.trim();
+    if borrower.is_empty() {
+        anyhow::bail!("A loan needs a borrower");
+    }
 
-    let file = std::fs::File::open(csv_path)?;
-    let mut rdr = csv::Reader::from_reader(file);
+    let current = sqlx::query_as::<_, Loan>(&format!(
+        "{LOAN_SELECT} WHERE l.returned_on IS NULL AND l.volume_id = ?"
+    ))
+    .bind(lend.volume_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(current) = current {
+        anyhow::bail!(
+            "\"{}\" is already lent to {} since {}",
+            current.title,
+            current.borrower,
+            current.lent_on
+        );
+    }
 
-    let headers = rdr.headers()?.clone();
-    let mut count = 0;
+    let lent_on = date_or_today(&mut tx, lend.lent_on).await?;
+    let due_on = valid_date(&mut tx, lend.due_on).await?;
+    if due_on.as_ref().is_some_and(|due_on| *due_on < lent_on) {
+        anyhow::bail!("The due date is before the loan starts");
+    }
 
-    let csv_parent = csv_path.parent().unwrap_or(std::path::Path::new("."));
-    let images_dir = csv_parent.join("images");
+    sqlx::query(r#"INSERT INTO borrowers (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
+        .bind(borrower)
+        .execute(&mut *tx)
+        .await?;
 
-    let app_data_dir = app_handle.path().app_data_dir()?;
-    let books_dir = app_data_dir.join("books");
-    std::fs::create_dir_all(&books_dir)?;
+    let loan_id = sqlx::query(
+        r#"
+        INSERT INTO loans (volume_id, borrower_id, lent_on, due_on, notes)
+        SELECT ?, borrower_id, ?, ?, ?
+        FROM borrowers WHERE name = ?
+        "#,
+    )
+    .bind(lend.volume_id)
+    .bind(&lent_on)
+    .bind(&due_on)
+    .bind(lend.notes.map(str::trim).filter(|n| !n.is_empty()))
+    .bind(borrower)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
 
-    for result in rdr.records() {
-        let record = result?;
+    let loan = sqlx::query_as::<_, Loan>(&format!("{LOAN_SELECT} WHERE l.loan_id = ?"))
+        .bind(loan_id)
+        .fetch_one(&mut *tx)
+        .await?;
 
-        let get_field = |name: &str| -> Option<String> {
-            headers.iter().position(|h| h == name).and_then(|i| record.get(i).map(|s| s.to_string()))
-        };
+    tx.commit().await?;
+    Ok(loan)
+}
 
-        let title = get_field("title").unwrap_or_default();
-        if title.is_empty() {
-            continue;
-        }
+/// Ends the loan a book is out on, today when there's no `returned_on`.
+pub async fn return_book(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: &str,
+    returned_on: Option<&str>,
+) -> anyhow::Result<Loan> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let returned_on = date_or_today(&mut tx, returned_on).await?;
+    let loan_id = sqlx::query_scalar::<_, i64>(
+        r#"
+        UPDATE loans SET returned_on = MAX(?, lent_on)
+        WHERE volume_id = ? AND returned_on IS NULL
+        RETURNING loan_id
+        "#,
+    )
+    .bind(&returned_on)
+    .bind(volume_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("Book {volume_id} isn't lent"))?;
 
-        let series = get_field("series");
-        let number: Option<i64> = get_field("number").and_then(|n| n.parse().ok());
-        let publisher = get_field("publisher");
-        let published_date = get_field("published_date");
-        let description = get_field("description");
-        let page_count: Option<i64> = get_field("page_count").and_then(|n| n.parse().ok());
-        let language = get_field("language");
+    let loan = sqlx::query_as::<_, Loan>(&format!("{LOAN_SELECT} WHERE l.loan_id = ?"))
+        .bind(loan_id)
+        .fetch_one(&mut *tx)
+        .await?;
 
-        let authors: Vec<String> = get_field("authors")
-            .map(|s| s.split("; ").map(|a| a.to_string()).collect())
-            .unwrap_or_default();
+    tx.commit().await?;
+    Ok(loan)
+}
 
-        let groups: Vec<String> = get_field("groups")
-            .map(|s| s.split("; ").map(|g| g.to_string()).collect())
-            .unwrap_or_default();
+/// Books that are out, the ones due first at the top.
+pub async fn list_active_loans(pool: &tauri_plugin_sql::DbPool) -> anyhow::Result<Vec<Loan>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let loans = sqlx::query_as::<_, Loan>(&format!(
+        "{LOAN_SELECT} WHERE l.returned_on IS NULL ORDER BY l.due_on IS NULL, l.due_on, l.lent_on"
+    ))
+    .fetch_all(sqlite_pool)
+    .await?;
+    Ok(loans)
+}
 
-        let identifiers: Vec<(String, String)> = get_field("identifiers")
-            .map(|s| {
-                s.split("; ")
-                    .filter_map(|id| {
-                        let parts: Vec<&str> = id.split(':').collect();
-                        if parts.len() == 2 {
-                            Some((parts[0].to_string(), parts[1].to_string()))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
+/// Books still out after their due date, the longest overdue first.
+pub async fn list_overdue_loans(pool: &tauri_plugin_sql::DbPool) -> anyhow::Result<Vec<Loan>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let loans = sqlx::query_as::<_, Loan>(&format!(
+        r#"
+        {LOAN_SELECT}
+        WHERE l.returned_on IS NULL AND l.due_on < date('now', 'localtime')
+        ORDER BY l.due_on, l.lent_on
+        "#
+    ))
+    .fetch_all(sqlite_pool)
+    .await?;
+    Ok(loans)
+}
 
-        let volume_id = Uuid::new_v4().to_string();
+/// Every loan, returned or not, of a book and/or a borrower, the latest first.
+pub async fn loan_history(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: Option<&str>,
+    borrower: Option<&str>,
+) -> anyhow::Result<Vec<Loan>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let loans = sqlx::query_as::<_, Loan>(&format!(
+        r#"
+        {LOAN_SELECT}
+        WHERE (?1 IS NULL OR l.volume_id = ?1) AND (?2 IS NULL OR br.name = ?2)
+        ORDER BY l.lent_on DESC, l.loan_id DESC
+        "#
+    ))
+    .bind(volume_id)
+    .bind(borrower)
+    .fetch_all(sqlite_pool)
+    .await?;
+    Ok(loans)
+}
 
-        sqlx::query(
-            r#"
-            INSERT INTO books (
-                volume_id, title, series, number, publisher, published_date,
-                description, page_count, language
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&volume_id)
-        .bind(&title)
-        .bind(series.as_deref())
-        .bind(number)
-        .bind(publisher.as_deref())
-        .bind(published_date.as_deref())
-        .bind(description.as_deref())
-        .bind(page_count)
-        .bind(language.as_deref())
-        .execute(sqlite_pool)
+pub async fn get_all_borrowers(pool: &tauri_plugin_sql::DbPool) -> anyhow::Result<Vec<String>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let borrowers = sqlx::query_scalar::<_, String>("SELECT name FROM borrowers ORDER BY name")
+        .fetch_all(sqlite_pool)
         .await?;
+    Ok(borrowers)
+}
 
-        for author in &authors {
-            sqlx::query(r#"INSERT INTO authors (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
-                .bind(author)
-                .execute(sqlite_pool)
-                .await?;
+async fn reading_state(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    volume_id: &str,
+) -> anyhow::Result<ReadingState> {
+    sqlx::query_as::<_, ReadingState>(&format!("{READING_STATE_SELECT} WHERE b.volume_id = ?"))
+        .bind(volume_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Book {volume_id} not found"))
+}
+
+async fn latest_reading(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    volume_id: &str,
+) -> anyhow::Result<Option<Reading>> {
+    Ok(sqlx::query_as::<_, Reading>(
+        r#"
+        SELECT reading_id, volume_id, status, started_on, finished_on, current_page
+        FROM readings WHERE volume_id = ?
+        ORDER BY reading_id DESC LIMIT 1
+        "#,
+    )
+    .bind(volume_id)
+    .fetch_optional(&mut **tx)
+    .await?)
+}
 
+/// Moves a book to `status` on `date`, today when there's none.
+///
+/// Starting or finishing updates the latest reading. Once that's read or
+/// abandoned, shelving or starting the book again begins a re-read, and
+/// marking it read logs another time through.
+pub async fn set_reading_status(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: &str,
+    status: ReadingStatus,
+    date: Option<&str>,
+) -> anyhow::Result<ReadingState> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let date = date_or_today(&mut tx, date).await?;
+    let latest = latest_reading(&mut tx, volume_id)
+        .await?
+        .filter(|r| !r.status.is_finished());
+
+    match (latest, status) {
+        (Some(latest), status) if latest.status == status => {}
+        (Some(latest), ReadingStatus::WantToRead) => {
             sqlx::query(
                 r#"
-                INSERT INTO book_authors (volume_id, author_id, position)
-                SELECT ?, author_id, 0
-                FROM authors WHERE name = ?
+                UPDATE readings SET status = ?, started_on = NULL, current_page = NULL
+                WHERE reading_id = ?
                 "#,
             )
-            .bind(&volume_id)
-            .bind(author)
-            .execute(sqlite_pool)
+            .bind(status)
+            .bind(latest.reading_id)
+            .execute(&mut *tx)
             .await?;
         }
-
-        for group in &groups {
-            sqlx::query(r#"INSERT INTO groups (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
-                .bind(group)
-                .execute(sqlite_pool)
+        (Some(latest), ReadingStatus::Reading) => {
+            sqlx::query("UPDATE readings SET status = ?, started_on = ? WHERE reading_id = ?")
+                .bind(status)
+                .bind(&date)
+                .bind(latest.reading_id)
+                .execute(&mut *tx)
                 .await?;
-
+        }
+        (Some(latest), status) => {
+            if latest.started_on.as_ref().is_some_and(|s| *s > date) {
+                anyhow::bail!("The reading started after {date}");
+            }
+            // A finished book was read to the last page
             sqlx::query(
                 r#"
-                INSERT INTO book_groups (volume_id, group_id)
-                SELECT ?, group_id
-                FROM groups WHERE name = ?
+                UPDATE readings SET
+                    status = ?1,
+                    finished_on = ?2,
+                    current_page = CASE WHEN ?1 = 'read'
+                        THEN COALESCE(
+                            (SELECT page_count FROM books WHERE volume_id = readings.volume_id),
+                            current_page
+                        )
+                        ELSE current_page END
+                WHERE reading_id = ?3
                 "#,
             )
-            .bind(&volume_id)
-            .bind(group)
-            .execute(sqlite_pool)
+            .bind(status)
+            .bind(&date)
+            .bind(latest.reading_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        (None, ReadingStatus::Abandoned) => {
+            anyhow::bail!("Only a book that's being read or wanted can be abandoned");
+        }
+        (None, status) => {
+            let (started_on, finished_on) = match status {
+                ReadingStatus::Reading => (Some(&date), None),
+                ReadingStatus::Read => (None, Some(&date)),
+                _ => (None, None),
+            };
+            sqlx::query(
+                r#"
+                INSERT INTO readings (volume_id, status, started_on, finished_on, current_page)
+                SELECT volume_id, ?, ?, ?, CASE WHEN ? = 'read' THEN page_count END
+                FROM books WHERE volume_id = ?
+                "#,
+            )
+            .bind(status)
+            .bind(started_on)
+            .bind(finished_on)
+            .bind(status)
+            .bind(volume_id)
+            .execute(&mut *tx)
             .await?;
         }
+    }
 
-        for (id_type, identifier) in &identifiers {
-            if !identifier.is_empty() {
-                sqlx::query(
-                    r#"
-                    INSERT INTO book_identifiers (volume_id, type, identifier)
-                    VALUES (?, ?, ?)
-                    "#,
-                )
-                .bind(&volume_id)
-                .bind(id_type)
-                .bind(identifier)
-                .execute(sqlite_pool)
-                .await?;
-            }
+    let state = reading_state(&mut tx, volume_id).await?;
+    tx.commit().await?;
+    Ok(state)
+}
+
+/// Records how far into the book we are. A book that isn't being read is
+/// started today.
+pub async fn set_reading_progress(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: &str,
+    current_page: i64,
+) -> anyhow::Result<ReadingState> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let page_count =
+        sqlx::query_scalar::<_, Option<i64>>("SELECT page_count FROM books WHERE volume_id = ?")
+            .bind(volume_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Book {volume_id} not found"))?;
+    if current_page < 0 {
+        anyhow::bail!("The page can't be negative");
+    }
+    if let Some(page_count) = page_count.filter(|p| *p > 0 && current_page > *p) {
+        anyhow::bail!("Page {current_page} is past the end, the book has {page_count} pages");
+    }
+
+    let today = date_or_today(&mut tx, None).await?;
+    match latest_reading(&mut tx, volume_id).await? {
+        Some(latest) if !latest.status.is_finished() => {
+            sqlx::query(
+                r#"
+                UPDATE readings SET
+                    status = 'reading',
+                    started_on = COALESCE(started_on, ?),
+                    current_page = ?
+                WHERE reading_id = ?
+                "#,
+            )
+            .bind(&today)
+            .bind(current_page)
+            .bind(latest.reading_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        _ => {
+            sqlx::query(
+                r#"
+                INSERT INTO readings (volume_id, status, started_on, current_page)
+                VALUES (?, 'reading', ?, ?)
+                "#,
+            )
+            .bind(volume_id)
+            .bind(&today)
+            .bind(current_page)
+            .execute(&mut *tx)
+            .await?;
         }
+    }
 
-        if let Some(orig_volume_id) = get_field("volume_id") {
-            let source_image = images_dir.join(format!("{}.jpg", orig_volume_id));
-            if source_image.exists() {
-                let dest_image = books_dir.join(format!("{}.jpg", volume_id));
-                std::fs::copy(&source_image, &dest_image).ok();
-            }
+    let state = reading_state(&mut tx, volume_id).await?;
+    tx.commit().await?;
+    Ok(state)
+}
+
+/// Checks a rating is between 0 and 5 in half stars.
+fn check_rating(rating: Option<f64>) -> anyhow::Result<()> {
+    match rating {
+        Some(rating) if !(0.0..=5.0).contains(&rating) || (rating * 2.0).fract() != 0.0 => {
+            anyhow::bail!("Ratings go from 0 to 5 in half stars, not {rating}")
         }
+        _ => Ok(()),
+    }
+}
+
+/// Sets the rating and review of a book, without either the review is
+/// removed.
+pub async fn rate_book(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: &str,
+    rating: Option<f64>,
+    review: Option<&str>,
+) -> anyhow::Result<ReadingState> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    check_rating(rating)?;
+    let review = review.map(str::trim).filter(|r| !r.is_empty());
+    write_review(&mut tx, volume_id, rating, review).await?;
+
+    let state = reading_state(&mut tx, volume_id).await?;
+    tx.commit().await?;
+    Ok(state)
+}
+
+async fn write_review(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    volume_id: &str,
+    rating: Option<f64>,
+    review: Option<&str>,
+) -> anyhow::Result<()> {
+    if rating.is_none() && review.is_none() {
+        sqlx::query("DELETE FROM book_reviews WHERE volume_id = ?")
+            .bind(volume_id)
+            .execute(&mut **tx)
+            .await?;
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO book_reviews (volume_id, rating, review) VALUES (?, ?, ?)
+        ON CONFLICT(volume_id) DO UPDATE SET rating = excluded.rating, review = excluded.review
+        "#,
+    )
+    .bind(volume_id)
+    .bind(rating)
+    .bind(review)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Every reading of a book, the latest first.
+pub async fn reading_history(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: &str,
+) -> anyhow::Result<Vec<Reading>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let readings = sqlx::query_as::<_, Reading>(
+        r#"
+        SELECT reading_id, volume_id, status, started_on, finished_on, current_page
+        FROM readings WHERE volume_id = ?
+        ORDER BY reading_id DESC
+        "#,
+    )
+    .bind(volume_id)
+    .fetch_all(sqlite_pool)
+    .await?;
+    Ok(readings)
+}
+
+/// A reading with its dates corrected, for logging past reads.
+#[derive(Debug, Deserialize)]
+pub struct UpdateReadingPayload {
+    pub reading_id: i64,
+    pub status: ReadingStatus,
+    pub started_on: Option<String>,
+    pub finished_on: Option<String>,
+    pub current_page: Option<i64>,
+}
+
+pub async fn update_reading(
+    pool: &tauri_plugin_sql::DbPool,
+    payload: &UpdateReadingPayload,
+) -> anyhow::Result<ReadingState> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
 
-        count += 1;
+    let started_on = valid_date(&mut tx, payload.started_on.as_deref()).await?;
+    let finished_on = valid_date(&mut tx, payload.finished_on.as_deref()).await?;
+    if let (Some(started_on), Some(finished_on)) = (&started_on, &finished_on) {
+        if finished_on < started_on {
+            anyhow::bail!("The reading finishes before it starts");
+        }
     }
+    if payload.current_page.is_some_and(|p| p < 0) {
+        anyhow::bail!("The page can't be negative");
+    }
+
+    let volume_id = sqlx::query_scalar::<_, String>(
+        r#"
+        UPDATE readings SET status = ?, started_on = ?, finished_on = ?, current_page = ?
+        WHERE reading_id = ?
+        RETURNING volume_id
+        "#,
+    )
+    .bind(payload.status)
+    .bind(&started_on)
+    // Only a finished reading has a finish date
+    .bind(finished_on.filter(|_| payload.status.is_finished()))
+    .bind(payload.current_page)
+    .bind(payload.reading_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("Reading {} not found", payload.reading_id))?;
+
+    let state = reading_state(&mut tx, &volume_id).await?;
+    tx.commit().await?;
+    Ok(state)
+}
+
+pub async fn delete_reading(
+    pool: &tauri_plugin_sql::DbPool,
+    reading_id: i64,
+) -> anyhow::Result<ReadingState> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let volume_id = sqlx::query_scalar::<_, String>(
+        "DELETE FROM readings WHERE reading_id = ? RETURNING volume_id",
+    )
+    .bind(reading_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("Reading {reading_id} not found"))?;
+
+    let state = reading_state(&mut tx, &volume_id).await?;
+    tx.commit().await?;
+    Ok(state)
+}
 
-    Ok(count)
+/// Records the copy a book stands for when it has none yet.
+async fn ensure_copy<'e, E>(executor: E, volume_id: &str) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO copies (volume_id)
+        SELECT ?1 WHERE NOT EXISTS (SELECT 1 FROM copies WHERE volume_id = ?1)
+        "#,
+    )
+    .bind(volume_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub fn condition_grades() -> Vec<ConditionGrade> {
+    [GradeScale::Book, GradeScale::Comic]
+        .into_iter()
+        .flat_map(|scale| {
+            scale
+                .grades()
+                .iter()
+                .map(move |(grade, label)| ConditionGrade {
+                    scale,
+                    grade,
+                    label,
+                })
+        })
+        .collect()
+}
+
+/// Checks the details of a copy and tidies them up: text trimmed, empty
+/// values dropped, the currency uppercase and the grade as it's stored.
+async fn clean_copy_details(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    details: &CopyDetails,
+) -> anyhow::Result<CopyDetails> {
+    let text = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    if let Some(price) = details.price.filter(|p| !p.is_finite() || *p < 0.0) {
+        anyhow::bail!("Invalid price {price}");
+    }
+    let currency = text(&details.currency).map(|c| c.to_ascii_uppercase());
+    if let Some(currency) = &currency {
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            anyhow::bail!("Currency {currency} isn't a three letter code like EUR");
+        }
+    }
+
+    let grade = match (details.grade_scale, text(&details.grade)) {
+        (_, None) => None,
+        (None, Some(_)) => anyhow::bail!("A grade needs its scale, book or comic"),
+        (Some(scale), Some(grade)) => Some(
+            scale
+                .grade(&grade)
+                .ok_or_else(|| anyhow::anyhow!("{grade} isn't a grade on the {scale:?} scale"))?
+                .to_string(),
+        ),
+    };
+
+    if let Some(location_id) = details.location_id {
+        location_kind(tx, location_id).await?;
+    }
+
+    Ok(CopyDetails {
+        acquired_on: valid_date(tx, details.acquired_on.as_deref()).await?,
+        source: text(&details.source),
+        price: details.price,
+        currency,
+        grade_scale: details.grade_scale.filter(|_| grade.is_some()),
+        grade,
+        signed: details.signed,
+        first_edition: details.first_edition,
+        notes: text(&details.notes),
+        location_id: details.location_id,
+    })
+}
+
+/// Adds another copy of a book we own.
+pub async fn add_copy(
+    pool: &tauri_plugin_sql::DbPool,
+    volume_id: &str,
+    details: &CopyDetails,
+) -> anyhow::Result<BookCopy> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let details = clean_copy_details(&mut tx, details).await?;
+    let copy_id = sqlx::query(
+        r#"
+        INSERT INTO copies (
+          volume_id, acquired_on, source, price, currency,
+          grade_scale, grade, signed, first_edition, notes, location_id
+        )
+        SELECT volume_id, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+        FROM books WHERE volume_id = ?
+        "#,
+    )
+    .bind(&details.acquired_on)
+    .bind(&details.source)
+    .bind(details.price)
+    .bind(&details.currency)
+    .bind(details.grade_scale)
+    .bind(&details.grade)
+    .bind(details.signed)
+    .bind(details.first_edition)
+    .bind(&details.notes)
+    .bind(details.location_id)
+    .bind(volume_id)
+    .execute(&mut *tx)
+    .await?;
+    if copy_id.rows_affected() == 0 {
+        anyhow::bail!("Book {volume_id} not found");
+    }
+
+    let copy = sqlx::query_as::<_, BookCopy>(&format!("{COPY_SELECT} WHERE copy_id = ?"))
+        .bind(copy_id.last_insert_rowid())
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(copy)
+}
+
+pub async fn update_copy(
+    pool: &tauri_plugin_sql::DbPool,
+    copy_id: i64,
+    details: &CopyDetails,
+) -> anyhow::Result<BookCopy> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let details = clean_copy_details(&mut tx, details).await?;
+    let updated = sqlx::query(
+        r#"
+        UPDATE copies SET
+          acquired_on = ?, source = ?, price = ?, currency = ?,
+          grade_scale = ?, grade = ?, signed = ?, first_edition = ?, notes = ?,
+          location_id = ?
+        WHERE copy_id = ?
+        "#,
+    )
+    .bind(&details.acquired_on)
+    .bind(&details.source)
+    .bind(details.price)
+    .bind(&details.currency)
+    .bind(details.grade_scale)
+    .bind(&details.grade)
+    .bind(details.signed)
+    .bind(details.first_edition)
+    .bind(&details.notes)
+    .bind(details.location_id)
+    .bind(copy_id)
+    .execute(&mut *tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        anyhow::bail!("Copy {copy_id} not found");
+    }
+
+    let copy = sqlx::query_as::<_, BookCopy>(&format!("{COPY_SELECT} WHERE copy_id = ?"))
+        .bind(copy_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(copy)
+}
+
+/// Removes a copy we no longer have. The last copy goes with the book, so
+/// it's refused here.
+pub async fn delete_copy(pool: &tauri_plugin_sql::DbPool, copy_id: i64) -> anyhow::Result<()> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let others = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) - 1 FROM copies
+        WHERE volume_id = (SELECT volume_id FROM copies WHERE copy_id = ?1)
+        "#,
+    )
+    .bind(copy_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if others < 0 {
+        anyhow::bail!("Copy {copy_id} not found");
+    }
+    if others == 0 {
+        anyhow::bail!("This is the only copy, delete the book instead");
+    }
+
+    sqlx::query("DELETE FROM copies WHERE copy_id = ?")
+        .bind(copy_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn location_kind(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    location_id: i64,
+) -> anyhow::Result<LocationKind> {
+    sqlx::query_scalar::<_, LocationKind>("SELECT kind FROM locations WHERE location_id = ?")
+        .bind(location_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Location {location_id} not found"))
+}
+
+async fn fetch_location(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    location_id: i64,
+) -> anyhow::Result<Location> {
+    Ok(sqlx::query_as::<_, Location>(&format!(
+        "{LOCATION_PATHS} {LOCATION_SELECT} WHERE l.location_id = ?"
+    ))
+    .bind(location_id)
+    .fetch_one(&mut **tx)
+    .await?)
+}
+
+/// Checks a location fits where it's put and returns its name trimmed. Names
+/// are unique among the locations sharing a parent.
+async fn check_location(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    location_id: Option<i64>,
+    name: &str,
+    kind: LocationKind,
+    parent_id: Option<i64>,
+) -> anyhow::Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("A location needs a name");
+    }
+
+    let parent_kind = match parent_id {
+        Some(parent_id) => Some(location_kind(tx, parent_id).await?),
+        None => None,
+    };
+    kind.check_parent(parent_kind)?;
+
+    let taken = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM locations
+        WHERE parent_id IS ? AND name = ? AND location_id IS NOT ?
+        "#,
+    )
+    .bind(parent_id)
+    .bind(name)
+    .bind(location_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    if taken > 0 {
+        anyhow::bail!("There's already a location called {name} there");
+    }
+
+    Ok(name.to_string())
+}
+
+/// Every location, ordered by path so the ones inside follow their parent.
+pub async fn get_locations(pool: &tauri_plugin_sql::DbPool) -> anyhow::Result<Vec<Location>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let locations = sqlx::query_as::<_, Location>(&format!(
+        "{LOCATION_PATHS} {LOCATION_SELECT} ORDER BY p.path"
+    ))
+    .fetch_all(sqlite_pool)
+    .await?;
+    Ok(locations)
+}
+
+pub async fn add_location(
+    pool: &tauri_plugin_sql::DbPool,
+    name: &str,
+    kind: LocationKind,
+    parent_id: Option<i64>,
+) -> anyhow::Result<Location> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let name = check_location(&mut tx, None, name, kind, parent_id).await?;
+    let location_id = sqlx::query("INSERT INTO locations (parent_id, name, kind) VALUES (?, ?, ?)")
+        .bind(parent_id)
+        .bind(&name)
+        .bind(kind)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+    let location = fetch_location(&mut tx, location_id).await?;
+    tx.commit().await?;
+    Ok(location)
+}
+
+/// Renames a location and/or moves it, with everything in it, to another
+/// parent.
+pub async fn update_location(
+    pool: &tauri_plugin_sql::DbPool,
+    location_id: i64,
+    name: &str,
+    parent_id: Option<i64>,
+) -> anyhow::Result<Location> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let kind = location_kind(&mut tx, location_id).await?;
+    let name = check_location(&mut tx, Some(location_id), name, kind, parent_id).await?;
+    sqlx::query("UPDATE locations SET name = ?, parent_id = ? WHERE location_id = ?")
+        .bind(&name)
+        .bind(parent_id)
+        .bind(location_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let location = fetch_location(&mut tx, location_id).await?;
+    tx.commit().await?;
+    Ok(location)
+}
+
+/// Deletes an empty location. One with locations or copies in it is refused,
+/// so nothing loses track of where it is.
+pub async fn delete_location(
+    pool: &tauri_plugin_sql::DbPool,
+    location_id: i64,
+) -> anyhow::Result<()> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    let location = fetch_location(&mut tx, location_id).await?;
+    let inside = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM locations WHERE parent_id = ?")
+        .bind(location_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if inside > 0 || location.copy_count > 0 {
+        anyhow::bail!("{} isn't empty, move what's in it first", location.path);
+    }
+
+    sqlx::query("DELETE FROM locations WHERE location_id = ?")
+        .bind(location_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Puts books and copies in a location, or takes them off the shelves with
+/// None. Returns how many copies moved.
+pub async fn move_to_location(
+    pool: &tauri_plugin_sql::DbPool,
+    items: &LocationItems,
+    location_id: Option<i64>,
+) -> anyhow::Result<u64> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    if let Some(location_id) = location_id {
+        location_kind(&mut tx, location_id).await?;
+    }
+
+    let mut moved = 0;
+    for volume_id in &items.volume_ids {
+        moved += sqlx::query("UPDATE copies SET location_id = ? WHERE volume_id = ?")
+            .bind(location_id)
+            .bind(volume_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+    for copy_id in &items.copy_ids {
+        moved += sqlx::query("UPDATE copies SET location_id = ? WHERE copy_id = ?")
+            .bind(location_id)
+            .bind(copy_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+
+    tx.commit().await?;
+    Ok(moved)
+}
+
+async fn copies_in<'e, E>(executor: E, location_id: Option<i64>) -> anyhow::Result<Vec<LocatedCopy>>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let copies = sqlx::query_as::<_, LocatedCopy>(&format!(
+        r#"
+        {LOCATION_PATHS}, inside(location_id) AS (
+            SELECT ?1
+            UNION ALL
+            SELECT l.location_id FROM locations l JOIN inside i ON l.parent_id = i.location_id
+        )
+        {LOCATED_COPY_SELECT}
+        WHERE (?1 IS NULL AND c.location_id IS NULL)
+           OR c.location_id IN (SELECT location_id FROM inside)
+        ORDER BY p.path, b.title COLLATE NOCASE, b.number, c.copy_id
+        "#
+    ))
+    .bind(location_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(copies)
+}
+
+/// The copies in a location and in the locations inside it, or the ones that
+/// aren't shelved with None.
+pub async fn list_location_contents(
+    pool: &tauri_plugin_sql::DbPool,
+    location_id: Option<i64>,
+) -> anyhow::Result<Vec<LocatedCopy>> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    copies_in(sqlite_pool, location_id).await
+}
+
+/// A copy matching the code scanned at `scan`, the index of the code.
+#[derive(FromRow)]
+struct ScannedCopy {
+    scan: i64,
+    #[sqlx(flatten)]
+    copy: LocatedCopy,
+}
+
+/// Compares the codes scanned on a location with the copies recorded there.
+/// Scanning a code twice counts two copies, and copies of a book are matched
+/// with the ones expected here first.
+pub async fn stocktake(
+    pool: &tauri_plugin_sql::DbPool,
+    location_id: i64,
+    scanned: &[String],
+) -> anyhow::Result<StocktakeReport> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let mut tx = sqlite_pool.begin().await?;
+
+    location_kind(&mut tx, location_id).await?;
+    let mut expected = copies_in(&mut *tx, Some(location_id)).await?;
+    let mut counted: HashSet<i64> = expected.iter().map(|c| c.copy_id).collect();
+
+    // Matched like find_volume_ids_by_identifier. Comic barcodes carry the
+    // issue in their supplement, only that issue of the series matches
+    let codes: Vec<&str> = scanned
+        .iter()
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+        .collect();
+    let scans: Vec<serde_json::Value> = codes
+        .iter()
+        .map(|code| {
+            let (identifier, number) = match barcode::parse(code) {
+                Ok(barcode) => {
+                    let number = barcode.issue_number();
+                    (barcode.ean, number)
+                }
+                Err(_) => (code.to_string(), None),
+            };
+            serde_json::json!({
+                "canonical": canonical_isbn13(&identifier),
+                "identifier": identifier,
+                "number": number,
+            })
+        })
+        .collect();
+
+    // Every copy of the books each code matches, in one go
+    let matches = sqlx::query_as::<_, ScannedCopy>(&format!(
+        r#"
+        {LOCATION_PATHS}, scans AS (
+            SELECT
+                CAST(key AS INTEGER) AS scan,
+                json_extract(value, '$.canonical') AS canonical,
+                json_extract(value, '$.identifier') AS identifier,
+                json_extract(value, '$.number') AS number
+            FROM json_each(?)
+        )
+        SELECT DISTINCT
+            s.scan, c.copy_id, c.volume_id, b.title, b.series, b.number,
+            c.location_id, p.path AS location
+        FROM scans s
+        JOIN book_identifiers bi
+          ON CASE WHEN s.canonical IS NULL
+               THEN bi.identifier = s.identifier
+               ELSE bi.canonical_isbn13 = s.canonical
+             END
+        JOIN copies c ON c.volume_id = bi.volume_id
+        JOIN books b ON b.volume_id = c.volume_id
+        LEFT JOIN location_paths p ON p.location_id = c.location_id
+        WHERE s.number IS NULL OR b.number = s.number
+        ORDER BY s.scan, c.copy_id
+        "#
+    ))
+    .bind(serde_json::to_string(&scans)?)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut candidates: Vec<Vec<LocatedCopy>> = vec![Vec::new(); codes.len()];
+    for m in matches {
+        candidates[m.scan as usize].push(m.copy);
+    }
+
+    let mut report = StocktakeReport::default();
+    for (code, copies) in codes.into_iter().zip(candidates) {
+        if let Some(index) = expected
+            .iter()
+            .position(|e| copies.iter().any(|c| c.volume_id == e.volume_id))
+        {
+            report.found.push(expected.remove(index));
+            continue;
+        }
+
+        match copies.into_iter().find(|c| !counted.contains(&c.copy_id)) {
+            Some(copy) => {
+                counted.insert(copy.copy_id);
+                report.misplaced.push(copy);
+            }
+            None => report.unknown.push(code.to_string()),
+        }
+    }
+    report.missing = expected;
+
+    tx.commit().await?;
+    Ok(report)
+}
+
+/// A CSV row in the shape `write_books_csv` produces.
+struct CsvBook {
+    book: NormalizedBook,
+    series: Option<String>,
+    number: Option<i64>,
+    groups: Vec<String>,
+    custom_fields: Vec<(String, String)>,
+    reading: CsvReading,
+}
+
+/// The reading columns of a CSV row: the latest reading, how often the book
+/// was read and the review.
+#[derive(Default)]
+struct CsvReading {
+    status: Option<ReadingStatus>,
+    started_on: Option<String>,
+    finished_on: Option<String>,
+    current_page: Option<i64>,
+    times_read: Option<i64>,
+    rating: Option<f64>,
+    review: Option<String>,
+}
+
+// Errors are the reason the row is rejected.
+fn parse_csv_record(
+    headers: &csv::StringRecord,
+    record: &csv::StringRecord,
+) -> Result<CsvBook, String> {
+    let get_field = |name: &str| -> Option<String> {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .and_then(|i| record.get(i))
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+    };
+    let get_number = |name: &str| -> Result<Option<i64>, String> {
+        get_field(name)
+            .map(|n| {
+                n.trim()
+                    .parse()
+                    .map_err(|_| format!("{name} \"{n}\" is not a number"))
+            })
+            .transpose()
+    };
+    let get_list = |name: &str| -> Vec<String> {
+        get_field(name)
+            .map(|s| {
+                s.split(CSV_LIST_SEPARATOR)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let title = get_field("title").ok_or("Missing title")?;
+
+    let reading_status = get_field("reading_status")
+        .map(|s| {
+            ReadingStatus::parse(s.trim()).ok_or_else(|| {
+                format!("reading_status \"{s}\" is not want_to_read, reading, read or abandoned")
+            })
+        })
+        .transpose()?;
+    let rating = get_field("rating")
+        .map(|r| {
+            r.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("rating \"{r}\" is not a number"))
+        })
+        .transpose()?;
+    check_rating(rating).map_err(|e| e.to_string())?;
+
+    // Identifiers are written as TYPE:identifier, the identifier itself can
+    // contain colons (e.g. Google's "OTHER:UOM:39015...").
+    let mut identifiers = Vec::new();
+    for entry in get_list("identifiers") {
+        let Some((id_type, identifier)) = entry.split_once(':') else {
+            return Err(format!("Identifier \"{entry}\" has no type"));
+        };
+        if identifier.is_empty() {
+            continue;
+        }
+
+        // Barcodes are validated and normalized, identifier types we
+        // don't know how to check (e.g. Google's OTHER) are kept as they are.
+        let (id_type, identifier) = match normalize_identifier(identifier) {
+            Ok((t, normalized)) => (t.to_string(), normalized),
+            Err(e) if matches!(id_type, "ISBN_10" | "ISBN_13" | "EAN_13") => {
+                return Err(format!("Invalid {id_type} {identifier}: {e}"));
+            }
+            Err(_) => (id_type.to_string(), identifier.to_string()),
+        };
+        identifiers.push(NormalizedIdentifier {
+            type_: id_type,
+            identifier,
+        });
+    }
+
+    // Any column we don't know is a custom field
+    let custom_fields = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| !CSV_COLUMNS.contains(h))
+        .filter_map(|(i, h)| {
+            record
+                .get(i)
+                .filter(|v| !v.is_empty())
+                .map(|v| (h.to_string(), v.to_string()))
+        })
+        .collect();
+
+    Ok(CsvBook {
+        book: NormalizedBook {
+            volume_id: get_field("volume_id").unwrap_or_default(),
+            title,
+            publisher: get_field("publisher"),
+            published_date: get_field("published_date"),
+            description: get_field("description"),
+            page_count: get_number("page_count")?,
+            print_type: get_field("print_type"),
+            maturity_rating: get_field("maturity_rating"),
+            language: get_field("language"),
+            preview_link: get_field("preview_link"),
+            info_link: get_field("info_link"),
+            canonical_link: get_field("canonical_link"),
+            small_thumbnail: get_field("small_thumbnail"),
+            thumbnail: get_field("thumbnail"),
+            country: get_field("country"),
+            saleability: get_field("saleability"),
+            is_ebook: get_number("is_ebook")?,
+            viewability: get_field("viewability"),
+            embeddable: get_number("embeddable")?,
+            public_domain: get_number("public_domain")?,
+            text_to_speech_permission: get_field("text_to_speech_permission"),
+            epub_available: get_number("epub_available")?,
+            pdf_available: get_number("pdf_available")?,
+            web_reader_link: get_field("web_reader_link"),
+            access_view_status: get_field("access_view_status"),
+            quote_sharing_allowed: get_number("quote_sharing_allowed")?,
+            metadata_source: get_field("metadata_source"),
+            authors: get_list("authors"),
+            categories: get_list("categories"),
+            identifiers,
+        },
+        series: get_field("series"),
+        number: get_number("number")?,
+        groups: get_list("groups"),
+        custom_fields,
+        reading: CsvReading {
+            status: reading_status,
+            started_on: get_field("started_on"),
+            finished_on: get_field("finished_on"),
+            current_page: get_number("current_page")?,
+            times_read: get_number("times_read")?,
+            rating,
+            review: get_field("review"),
+        },
+    })
+}
+
+// Writes the reading columns the CSV has. The row's reading replaces the
+// book's latest one when that's still going or the same finished reading,
+// otherwise it's a re-read. Reads `times_read` counts beyond the ones we know
+// of are added before it, without dates.
+async fn write_csv_reading(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    volume_id: &str,
+    reading: &CsvReading,
+    has_column: impl Fn(&str) -> bool,
+) -> anyhow::Result<()> {
+    let started_on = valid_date(tx, reading.started_on.as_deref()).await?;
+    let finished_on = valid_date(tx, reading.finished_on.as_deref()).await?;
+
+    if let Some(status) = reading.status {
+        let same = latest_reading(tx, volume_id).await?.filter(|latest| {
+            !latest.status.is_finished()
+                || (status.is_finished() && latest.finished_on == finished_on)
+        });
+        if let Some(same) = same {
+            sqlx::query("DELETE FROM readings WHERE reading_id = ?")
+                .bind(same.reading_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+    }
+
+    let mut reads = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM readings WHERE volume_id = ? AND status = 'read'",
+    )
+    .bind(volume_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    if reading.status == Some(ReadingStatus::Read) {
+        reads += 1;
+    }
+    for _ in reads..reading.times_read.unwrap_or_default() {
+        sqlx::query("INSERT INTO readings (volume_id, status) VALUES (?, 'read')")
+            .bind(volume_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    if let Some(status) = reading.status {
+        sqlx::query(
+            r#"
+            INSERT INTO readings (volume_id, status, started_on, finished_on, current_page)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(volume_id)
+        .bind(status)
+        .bind(&started_on)
+        .bind(finished_on.filter(|_| status.is_finished()))
+        .bind(reading.current_page)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    if has_column("rating") || has_column("review") {
+        let existing = sqlx::query_as::<_, (Option<f64>, Option<String>)>(
+            "SELECT rating, review FROM book_reviews WHERE volume_id = ?",
+        )
+        .bind(volume_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .unwrap_or_default();
+        let rating = if has_column("rating") {
+            reading.rating
+        } else {
+            existing.0
+        };
+        let review = if has_column("review") {
+            reading.review.as_deref()
+        } else {
+            existing.1.as_deref()
+        };
+        write_review(tx, volume_id, rating, review).await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_csv_book(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    row: &CsvBook,
+) -> anyhow::Result<()> {
+    let volume_id = &row.book.volume_id;
+
+    upsert_normalized_book_in_tx(tx, &row.book).await?;
+
+    sqlx::query("UPDATE books SET series = ?, number = ? WHERE volume_id = ?")
+        .bind(row.series.as_deref())
+        .bind(row.number)
+        .bind(volume_id)
+        .execute(&mut **tx)
+        .await?;
+
+    for group in &row.groups {
+        sqlx::query(r#"INSERT INTO groups (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
+            .bind(group)
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO book_groups (volume_id, group_id)
+            SELECT ?, group_id
+            FROM groups WHERE name = ?
+            "#,
+        )
+        .bind(volume_id)
+        .bind(group)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    for (field_name, value) in &row.custom_fields {
+        sqlx::query(
+            r#"
+            INSERT INTO book_custom_fields (volume_id, field_id, value)
+            SELECT ?, field_id, ?
+            FROM custom_fields WHERE name = ?
+            "#,
+        )
+        .bind(volume_id)
+        .bind(value)
+        .bind(field_name)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    write_csv_reading(tx, volume_id, &row.reading, |_| true).await?;
+
+    Ok(())
+}
+
+// Plain `books` columns of a CSV export, the ones `update_csv_book` can
+// write straight from the record.
+const CSV_BOOK_COLUMNS: &[&str] = &[
+    "title",
+    "series",
+    "number",
+    "publisher",
+    "published_date",
+    "description",
+    "page_count",
+    "print_type",
+    "maturity_rating",
+    "language",
+    "preview_link",
+    "info_link",
+    "canonical_link",
+    "small_thumbnail",
+    "thumbnail",
+    "country",
+    "saleability",
+    "is_ebook",
+    "viewability",
+    "embeddable",
+    "public_domain",
+    "text_to_speech_permission",
+    "epub_available",
+    "pdf_available",
+    "web_reader_link",
+    "access_view_status",
+    "quote_sharing_allowed",
+    "metadata_source",
+];
+
+const CSV_INTEGER_COLUMNS: &[&str] = &[
+    "number",
+    "page_count",
+    "is_ebook",
+    "embeddable",
+    "public_domain",
+    "epub_available",
+    "pdf_available",
+    "quote_sharing_allowed",
+];
+
+// Overwrites an existing book with the row. Only the columns the CSV has are
+// touched, so a spreadsheet with some columns removed doesn't wipe them.
+async fn update_csv_book(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    volume_id: &str,
+    headers: &csv::StringRecord,
+    record: &csv::StringRecord,
+    row: &CsvBook,
+) -> anyhow::Result<()> {
+    let has_column = |name: &str| headers.iter().any(|h| h == name);
+
+    // The record was validated by parse_csv_record, SQLite only has to turn
+    // empty cells into NULLs and numbers into integers
+    let mut assignments = Vec::new();
+    let mut binds = Vec::new();
+    for (i, header) in headers.iter().enumerate() {
+        let Some(column) = CSV_BOOK_COLUMNS.iter().find(|c| **c == header) else {
+            continue;
+        };
+        if CSV_INTEGER_COLUMNS.contains(column) {
+            assignments.push(format!("{column} = CAST(NULLIF(TRIM(?), '') AS INTEGER)"));
+        } else {
+            assignments.push(format!("{column} = NULLIF(?, '')"));
+        }
+        binds.push(record.get(i).unwrap_or_default());
+    }
+    if !assignments.is_empty() {
+        let sql = format!(
+            "UPDATE books SET {} WHERE volume_id = ?",
+            assignments.join(", ")
+        );
+        let mut query = sqlx::query(&sql);
+        for bind in binds {
+            query = query.bind(bind);
+        }
+        query.bind(volume_id).execute(&mut **tx).await?;
+    }
+
+    if has_column("authors") {
+        sqlx::query("DELETE FROM book_authors WHERE volume_id = ?")
+            .bind(volume_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for (pos, name) in row.book.authors.iter().enumerate() {
+            sqlx::query(r#"INSERT INTO authors (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
+                .bind(name)
+                .execute(&mut **tx)
+                .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO book_authors (volume_id, author_id, position)
+                SELECT ?, author_id, ?
+                FROM authors WHERE name = ?
+                ON CONFLICT(volume_id, author_id)
+                DO UPDATE SET position = excluded.position
+                "#,
+            )
+            .bind(volume_id)
+            .bind(pos as i64)
+            .bind(name)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    if has_column("categories") {
+        sqlx::query("DELETE FROM book_categories WHERE volume_id = ?")
+            .bind(volume_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for name in &row.book.categories {
+            sqlx::query(r#"INSERT INTO categories (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
+                .bind(name)
+                .execute(&mut **tx)
+                .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO book_categories (volume_id, category_id)
+                SELECT ?, category_id
+                FROM categories WHERE name = ?
+                ON CONFLICT(volume_id, category_id) DO NOTHING
+                "#,
+            )
+            .bind(volume_id)
+            .bind(name)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    if has_column("identifiers") {
+        sqlx::query("DELETE FROM book_identifiers WHERE volume_id = ?")
+            .bind(volume_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for ii in &row.book.identifiers {
+            sqlx::query(
+                r#"
+                INSERT INTO book_identifiers (volume_id, type, identifier, canonical_isbn13)
+                VALUES (?, ?, ?, ?)
+                "#,
+            )
+            .bind(volume_id)
+            .bind(&ii.type_)
+            .bind(&ii.identifier)
+            .bind(canonical_isbn13(&ii.identifier))
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    if has_column("groups") {
+        sqlx::query("DELETE FROM book_groups WHERE volume_id = ?")
+            .bind(volume_id)
+            .execute(&mut **tx)
+            .await?;
+
+        for group in &row.groups {
+            sqlx::query(r#"INSERT INTO groups (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
+                .bind(group)
+                .execute(&mut **tx)
+                .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO book_groups (volume_id, group_id)
+                SELECT ?, group_id
+                FROM groups WHERE name = ?
+                "#,
+            )
+            .bind(volume_id)
+            .bind(group)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    // Every custom field column is replaced, an empty cell removes the value
+    for field_name in headers.iter().filter(|h| !CSV_COLUMNS.contains(h)) {
+        sqlx::query(
+            r#"
+            DELETE FROM book_custom_fields
+            WHERE volume_id = ?
+              AND field_id = (SELECT field_id FROM custom_fields WHERE name = ?)
+            "#,
+        )
+        .bind(volume_id)
+        .bind(field_name)
+        .execute(&mut **tx)
+        .await?;
+    }
+    for (field_name, value) in &row.custom_fields {
+        sqlx::query(
+            r#"
+            INSERT INTO book_custom_fields (volume_id, field_id, value)
+            SELECT ?, field_id, ?
+            FROM custom_fields WHERE name = ?
+            "#,
+        )
+        .bind(volume_id)
+        .bind(value)
+        .bind(field_name)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    write_csv_reading(tx, volume_id, &row.reading, has_column).await?;
+
+    Ok(())
+}
+
+/// What an import does with rows matching a book already in the library.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Leave the existing book alone and skip the row.
+    #[default]
+    Insert,
+    /// Update the existing book with the row.
+    Merge,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Inserted,
+    /// An existing book was updated with the row, only in merge mode.
+    Updated {
+        reason: String,
+    },
+    /// The book is already in the library.
+    Skipped {
+        reason: String,
+    },
+    /// The row can't be imported as it is.
+    Rejected {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportRowReport {
+    /// Line of the row in the CSV file, the header is line 1. Rows of a
+    /// Calibre library have the Calibre book id instead, rows of an XML file
+    /// their position in it.
+    pub line: u64,
+    pub title: Option<String>,
+    /// The volume_id the book was (or would be) saved under.
+    pub volume_id: Option<String>,
+    // Copied to the books dir once the import is done
+    #[serde(skip)]
+    cover: Option<std::path::PathBuf>,
+    #[serde(flatten)]
+    pub status: ImportRowStatus,
+}
+
+/// What a CSV import did, or would do when `dry_run` is set.
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub path: String,
+    pub format: ImportFormat,
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub rejected: usize,
+    pub rows: Vec<ImportRowReport>,
+}
+
+enum ExistingBook {
+    None,
+    /// The volume_id of the book and how it was matched.
+    Found(String, String),
+    /// The row matches more than one book.
+    Ambiguous(String),
+}
+
+// Every issue of a comic series shares the series' barcode, so with a
+// `number` only the book with that number matches.
+async fn find_volume_ids_by_identifier(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    identifier: &str,
+    number: Option<i64>,
+) -> anyhow::Result<Vec<String>> {
+    // Same matching as identifier_exists
+    let (column, value) = match canonical_isbn13(identifier) {
+        Some(canonical) => ("canonical_isbn13", canonical),
+        None => ("identifier", identifier.to_string()),
+    };
+    let volume_ids = sqlx::query_scalar::<_, String>(&format!(
+        r#"
+        SELECT DISTINCT volume_id FROM book_identifiers
+        WHERE {column} = ?
+          AND (? IS NULL OR volume_id IN (SELECT volume_id FROM books WHERE number = ?))
+        "#
+    ))
+    .bind(value)
+    .bind(number)
+    .bind(number)
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(volume_ids)
+}
+
+// Matches the row by volume_id first, then by any of its identifiers.
+async fn find_existing_csv_book(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    row: &CsvBook,
+) -> anyhow::Result<ExistingBook> {
+    let volume_id = &row.book.volume_id;
+    if !volume_id.is_empty()
+        && sqlx::query("SELECT 1 FROM books WHERE volume_id = ?")
+            .bind(volume_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .is_some()
+    {
+        return Ok(ExistingBook::Found(
+            volume_id.clone(),
+            format!("Volume {volume_id} already exists"),
+        ));
+    }
+
+    let mut found: Option<(String, String)> = None;
+    for identifier in &row.book.identifiers {
+        for matched in find_volume_ids_by_identifier(tx, &identifier.identifier, row.number).await?
+        {
+            match &found {
+                Some((existing, _)) if *existing != matched => {
+                    return Ok(ExistingBook::Ambiguous(format!(
+                        "Identifiers match both {existing} and {matched}"
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    found = Some((
+                        matched,
+                        format!(
+                            "{} {} already exists",
+                            identifier.type_, identifier.identifier
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(match found {
+        Some((volume_id, reason)) => ExistingBook::Found(volume_id, reason),
+        None => ExistingBook::None,
+    })
+}
+
+/// Imports rows in the shape `write_books_csv` produces and reports what
+/// happened to every row.
+///
+/// Rows are matched to existing books by volume_id, then by identifier. In
+/// insert mode matching rows are skipped, in merge mode they update the book
+/// they match. Volume ids of new books are kept unless the CSV has none.
+/// Everything runs in a single transaction, each row in a savepoint of its own
+/// so a rejected row doesn't undo the others. With `dry_run` the transaction
+/// is rolled back.
+async fn import_csv_rows(
+    sqlite_pool: &sqlx::SqlitePool,
+    csv_rows: Vec<Result<CsvRow, csv::Error>>,
+    mode: ImportMode,
+    dry_run: bool,
+) -> anyhow::Result<Vec<ImportRowReport>> {
+    use sqlx::Acquire;
+
+    let mut tx = sqlite_pool.begin().await?;
+
+    // Custom fields the file had a column for exist even if no book uses them
+    let field_names: HashSet<&str> = csv_rows
+        .iter()
+        .flatten()
+        .flat_map(|row| row.headers.iter())
+        .filter(|h| !CSV_COLUMNS.contains(h))
+        .collect();
+    for field_name in field_names {
+        sqlx::query(r#"INSERT INTO custom_fields (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#)
+            .bind(field_name)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let mut rows = Vec::new();
+    for result in csv_rows {
+        let CsvRow {
+            headers,
+            record,
+            cover,
+        } = match result {
+            Ok(row) => row,
+            Err(e) => {
+                rows.push(ImportRowReport {
+                    line: e.position().map(|p| p.line()).unwrap_or_default(),
+                    title: None,
+                    volume_id: None,
+                    cover: None,
+                    status: ImportRowStatus::Rejected {
+                        reason: e.to_string(),
+                    },
+                });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        let title = headers
+            .iter()
+            .position(|h| h == "title")
+            .and_then(|i| record.get(i))
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string());
+
+        let mut row = match parse_csv_record(&headers, &record) {
+            Ok(row) => row,
+            Err(reason) => {
+                rows.push(ImportRowReport {
+                    line,
+                    title,
+                    volume_id: None,
+                    cover: None,
+                    status: ImportRowStatus::Rejected { reason },
+                });
+                continue;
+            }
+        };
+
+        // Rows we don't write are reported right away, the others say which
+        // book they update, if any
+        let update = match (find_existing_csv_book(&mut tx, &row).await?, mode) {
+            (ExistingBook::None, _) => None,
+            (ExistingBook::Found(volume_id, reason), ImportMode::Merge) => {
+                Some((volume_id, reason))
+            }
+            (ExistingBook::Found(volume_id, reason), ImportMode::Insert) => {
+                rows.push(ImportRowReport {
+                    line,
+                    title,
+                    volume_id: Some(volume_id),
+                    cover,
+                    status: ImportRowStatus::Skipped { reason },
+                });
+                continue;
+            }
+            (ExistingBook::Ambiguous(reason), mode) => {
+                let status = match mode {
+                    ImportMode::Insert => ImportRowStatus::Skipped { reason },
+                    ImportMode::Merge => ImportRowStatus::Rejected { reason },
+                };
+                rows.push(ImportRowReport {
+                    line,
+                    title,
+                    volume_id: None,
+                    cover,
+                    status,
+                });
+                continue;
+            }
+        };
+
+        let mut savepoint = tx.begin().await?;
+        let (volume_id, result, success) = match update {
+            Some((volume_id, reason)) => {
+                let result =
+                    update_csv_book(&mut savepoint, &volume_id, &headers, &record, &row).await;
+                (volume_id, result, ImportRowStatus::Updated { reason })
+            }
+            None => {
+                if row.book.volume_id.is_empty() {
+                    row.book.volume_id = Uuid::new_v4().to_string();
+                }
+                let result = insert_csv_book(&mut savepoint, &row).await;
+                (row.book.volume_id, result, ImportRowStatus::Inserted)
+            }
+        };
+        let status = match result {
+            Ok(()) => {
+                savepoint.commit().await?;
+                success
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                ImportRowStatus::Rejected {
+                    reason: e.to_string(),
+                }
+            }
+        };
+        rows.push(ImportRowReport {
+            line,
+            title,
+            volume_id: Some(volume_id),
+            cover,
+            status,
+        });
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+
+    Ok(rows)
+}
+
+fn import_report(
+    path: &std::path::Path,
+    format: ImportFormat,
+    mode: ImportMode,
+    dry_run: bool,
+    rows: Vec<ImportRowReport>,
+) -> ImportReport {
+    let count = |f: fn(&ImportRowStatus) -> bool| rows.iter().filter(|r| f(&r.status)).count();
+    ImportReport {
+        path: path.to_string_lossy().to_string(),
+        format,
+        mode,
+        dry_run,
+        inserted: count(|s| matches!(s, ImportRowStatus::Inserted)),
+        updated: count(|s| matches!(s, ImportRowStatus::Updated { .. })),
+        skipped: count(|s| matches!(s, ImportRowStatus::Skipped { .. })),
+        rejected: count(|s| matches!(s, ImportRowStatus::Rejected { .. })),
+        rows,
+    }
+}
+
+/// Copies the covers of the imported rows to the books dir, relative cover
+/// paths are resolved against `base_dir`.
+fn copy_import_covers(
+    report: &ImportReport,
+    base_dir: &std::path::Path,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<()> {
+    let app_data_dir = app_handle.path().app_data_dir()?;
+    let books_dir = app_data_dir.join("books");
+    std::fs::create_dir_all(&books_dir)?;
+
+    for row in &report.rows {
+        if !matches!(
+            row.status,
+            ImportRowStatus::Inserted | ImportRowStatus::Updated { .. }
+        ) {
+            continue;
+        }
+        let (Some(cover), Some(volume_id)) = (&row.cover, &row.volume_id) else {
+            continue;
+        };
+        let source_image = base_dir.join(cover);
+        if source_image.exists() {
+            let dest_image = books_dir.join(format!("{}.jpg", volume_id));
+            std::fs::copy(&source_image, &dest_image).ok();
+        }
+    }
+    Ok(())
+}
+
+/// Imports a CSV export of this app or of one of the `ImportFormat`s, the
+/// format is detected when none is given. Calibre libraries are handed to
+/// `import_calibre_library`.
+pub async fn import_books_from_csv(
+    pool: &tauri_plugin_sql::DbPool,
+    csv_path: &std::path::Path,
+    format: Option<ImportFormat>,
+    mode: ImportMode,
+    dry_run: bool,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<ImportReport> {
+    // A Calibre library is a folder, not a file
+    if format == Some(ImportFormat::Calibre) {
+        return import_calibre_library(pool, csv_path, mode, dry_run, app_handle).await;
+    }
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+
+    let content = crate::importers::decode(&std::fs::read(csv_path)?)?;
+    let format = format.unwrap_or_else(|| ImportFormat::detect(&content));
+    let csv_rows = crate::importers::read_rows(format, &content)?;
+    let rows = import_csv_rows(sqlite_pool, csv_rows, mode, dry_run).await?;
+
+    let report = import_report(csv_path, format, mode, dry_run, rows);
+    if !dry_run {
+        let csv_parent = csv_path.parent().unwrap_or(std::path::Path::new("."));
+        copy_import_covers(&report, csv_parent, app_handle)?;
+    }
+    Ok(report)
+}
+
+/// Imports the books of the Calibre library in `library_dir`, the folder
+/// holding its `metadata.db`.
+pub async fn import_calibre_library(
+    pool: &tauri_plugin_sql::DbPool,
+    library_dir: &std::path::Path,
+    mode: ImportMode,
+    dry_run: bool,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<ImportReport> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+
+    let csv_rows = crate::importers::calibre::read(library_dir).await?;
+    let rows = import_csv_rows(sqlite_pool, csv_rows, mode, dry_run).await?;
+
+    let report = import_report(library_dir, ImportFormat::Calibre, mode, dry_run, rows);
+    if !dry_run {
+        copy_import_covers(&report, library_dir, app_handle)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn memory_pool() -> sqlx::SqlitePool {
+        // A single connection, every connection to :memory: is its own database
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for migration in crate::migrations::all() {
+            sqlx::raw_sql(migration.sql).execute(&pool).await.unwrap();
+        }
+        pool
+    }
+
+    async fn export(pool: &sqlx::SqlitePool) -> String {
+        let mut out = Vec::new();
+        write_books_csv(pool, &mut out).await.unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    async fn seed(pool: &sqlx::SqlitePool) {
+        let books = [
+            NormalizedBook {
+                volume_id: "vol-1".to_string(),
+                title: "The Hobbit".to_string(),
+                publisher: Some("Allen & Unwin".to_string()),
+                description: Some(
+                    "In a hole in the ground, \"there lived\"; a hobbit.".to_string(),
+                ),
+                page_count: Some(310),
+                language: Some("en".to_string()),
+                thumbnail: Some("https://example.com/hobbit.jpg".to_string()),
+                is_ebook: Some(0),
+                metadata_source: Some("google_books".to_string()),
+                // Not alphabetical, the order has to survive
+                authors: vec![
+                    "Tolkien, J. R. R.".to_string(),
+                    "Anderson, Douglas".to_string(),
+                ],
+                categories: vec!["Fantasy".to_string(), "Classics".to_string()],
+                identifiers: vec![
+                    NormalizedIdentifier {
+                        type_: "ISBN_13".to_string(),
+                        identifier: "9780261102217".to_string(),
+                    },
+                    NormalizedIdentifier {
+                        type_: "OTHER".to_string(),
+                        identifier: "UOM:39015012345678".to_string(),
+                    },
+                ],
+                ..Default::default()
+            },
+            NormalizedBook {
+                volume_id: "vol-2".to_string(),
+                title: "Dylan Dog".to_string(),
+                identifiers: vec![NormalizedIdentifier {
+                    type_: "EAN_13".to_string(),
+                    identifier: "9771121580009".to_string(),
+                }],
+                ..Default::default()
+            },
+        ];
+
+        let mut tx = pool.begin().await.unwrap();
+        for book in &books {
+            upsert_normalized_book_in_tx(&mut tx, book).await.unwrap();
+        }
+        tx.commit().await.unwrap();
+
+        sqlx::raw_sql(
+            r#"
+            UPDATE books SET series = 'Dylan Dog', number = 12 WHERE volume_id = 'vol-2';
+            INSERT INTO groups (name) VALUES ('Comics'), ('Living room');
+            INSERT INTO book_groups (volume_id, group_id)
+            SELECT 'vol-2', group_id FROM groups;
+            INSERT INTO custom_fields (name) VALUES ('Condition'), ('Signed'), ('Unused');
+            INSERT INTO book_custom_fields (volume_id, field_id, value)
+            SELECT 'vol-1', field_id, 'Very good' FROM custom_fields WHERE name = 'Condition';
+            INSERT INTO book_custom_fields (volume_id, field_id, value)
+            SELECT 'vol-2', field_id, 'yes' FROM custom_fields WHERE name = 'Signed';
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn csv_export_import_export_is_lossless() {
+        let source = memory_pool().await;
+        seed(&source).await;
+        let exported = export(&source).await;
+
+        let target = memory_pool().await;
+        let csv_rows = crate::importers::read_rows(ImportFormat::Native, &exported).unwrap();
+        let rows = import_csv_rows(&target, csv_rows, ImportMode::Insert, false)
+            .await
+            .unwrap();
+        assert!(rows
+            .iter()
+            .all(|r| matches!(r.status, ImportRowStatus::Inserted)));
+        assert_eq!(rows.len(), 2);
+
+        assert_eq!(export(&target).await, exported);
+    }
+
+    async fn import_native(
+        pool: &sqlx::SqlitePool,
+        csv: &str,
+        mode: ImportMode,
+        dry_run: bool,
+    ) -> ImportReport {
+        let csv_rows = crate::importers::read_rows(ImportFormat::Native, csv).unwrap();
+        let rows = import_csv_rows(pool, csv_rows, mode, dry_run)
+            .await
+            .unwrap();
+        import_report(
+            std::path::Path::new("books.csv"),
+            ImportFormat::Native,
+            mode,
+            dry_run,
+            rows,
+        )
+    }
+
+    /// Line and status of every row, as the frontend gets them.
+    fn statuses(report: &ImportReport) -> Vec<(u64, String)> {
+        report
+            .rows
+            .iter()
+            .map(|r| {
+                let status = serde_json::to_value(&r.status).unwrap()["status"].clone();
+                (r.line, status.as_str().unwrap().to_string())
+            })
+            .collect()
+    }
+
+    async fn count(pool: &sqlx::SqlitePool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    // A page count the readings table refuses fails the row after its book
+    // was written, a missing title before anything is
+    const IMPORT_CSV: &str = "\
+volume_id,title,authors,identifiers,reading_status,current_page,Shelf
+vol-a,The Hobbit,\"Tolkien, J. R. R.\",ISBN_13:9780261102217,read,,A1
+vol-b,Bad page,Nobody,,reading,-1,B2
+vol-c,,,,,,
+vol-d,Dylan Dog,Tiziano Sclavi,,,,C3
+";
+
+    #[tokio::test]
+    async fn csv_import_rejects_only_the_bad_rows() {
+        let pool = memory_pool().await;
+        let report = import_native(&pool, IMPORT_CSV, ImportMode::Insert, false).await;
+
+        assert_eq!(
+            statuses(&report),
+            [
+                (2, "inserted".to_string()),
+                (3, "rejected".to_string()),
+                (4, "rejected".to_string()),
+                (5, "inserted".to_string()),
+            ]
+        );
+        assert!(matches!(
+            &report.rows[2].status,
+            ImportRowStatus::Rejected { reason } if reason == "Missing title"
+        ));
+        assert_eq!(
+            (
+                report.inserted,
+                report.updated,
+                report.skipped,
+                report.rejected
+            ),
+            (2, 0, 0, 2)
+        );
+
+        // The rejected row's savepoint took its book and author along
+        let volume_ids: Vec<String> =
+            sqlx::query_scalar("SELECT volume_id FROM books ORDER BY volume_id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(volume_ids, ["vol-a", "vol-d"]);
+        assert_eq!(count(&pool, "book_authors").await, 2);
+        assert_eq!(count(&pool, "readings").await, 1);
+        assert_eq!(count(&pool, "book_custom_fields").await, 2);
+    }
+
+    #[tokio::test]
+    async fn csv_import_dry_run_writes_nothing() {
+        let pool = memory_pool().await;
+        let report = import_native(&pool, IMPORT_CSV, ImportMode::Insert, true).await;
+
+        assert!(report.dry_run);
+        assert_eq!((report.inserted, report.rejected), (2, 2));
+        for table in [
+            "books",
+            "authors",
+            "book_authors",
+            "book_identifiers",
+            "readings",
+            "custom_fields",
+            "book_custom_fields",
+        ] {
+            assert_eq!(count(&pool, table).await, 0, "{table}");
+        }
+    }
+
+    async fn load_book(pool: &sqlx::SqlitePool, volume_id: &str) -> Book {
+        load_books(pool, BookSelection::VolumeId(volume_id), None)
+            .await
+            .unwrap()
+            .pop()
+            .unwrap()
+    }
+
+    fn updated(report: &ImportReport) -> Vec<(String, String)> {
+        report
+            .rows
+            .iter()
+            .map(|r| match &r.status {
+                ImportRowStatus::Updated { reason } => {
+                    (r.volume_id.clone().unwrap(), reason.clone())
+                }
+                status => panic!("line {} wasn't updated: {status:?}", r.line),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn csv_merge_only_updates_the_columns_in_the_file() {
+        let pool = memory_pool().await;
+        seed(&pool).await;
+
+        let csv = "volume_id,title\nvol-1,The Hobbit (Second Edition)\n";
+        let report = import_native(&pool, csv, ImportMode::Merge, false).await;
+        assert_eq!(
+            updated(&report),
+            [(
+                "vol-1".to_string(),
+                "Volume vol-1 already exists".to_string()
+            )]
+        );
+        let book = load_book(&pool, "vol-1").await;
+        assert_eq!(book.title, "The Hobbit (Second Edition)");
+        assert_eq!(book.publisher.as_deref(), Some("Allen & Unwin"));
+        assert_eq!(book.page_count, Some(310));
+        assert_eq!(book.authors.len(), 2);
+        assert_eq!(book.identifiers.len(), 2);
+        assert_eq!(book.custom_fields["Condition"], "Very good");
+
+        // Empty cells of the columns it has clear them
+        let csv = "volume_id,title,publisher,Condition\nvol-1,The Hobbit,,\n";
+        import_native(&pool, csv, ImportMode::Merge, false).await;
+        let book = load_book(&pool, "vol-1").await;
+        assert_eq!(book.title, "The Hobbit");
+        assert_eq!(book.publisher, None);
+        assert!(!book.custom_fields.contains_key("Condition"));
+        assert_eq!(book.page_count, Some(310));
+        assert_eq!(book.authors.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn csv_merge_matches_by_identifier() {
+        let pool = memory_pool().await;
+        seed(&pool).await;
+
+        // The ISBN-10 matches the Hobbit's ISBN-13
+        let csv = "\
+title,publisher,identifiers
+The Hobbit,HarperCollins,ISBN_10:0261102214
+Dylan Dog,Sergio Bonelli Editore,EAN_13:9771121580009
+";
+        let report = import_native(&pool, csv, ImportMode::Merge, false).await;
+        assert_eq!(
+            updated(&report),
+            [
+                (
+                    "vol-1".to_string(),
+                    "ISBN_10 0261102214 already exists".to_string()
+                ),
+                (
+                    "vol-2".to_string(),
+                    "EAN_13 9771121580009 already exists".to_string()
+                ),
+            ]
+        );
+        assert_eq!(count(&pool, "books").await, 2);
+        let book = load_book(&pool, "vol-1").await;
+        assert_eq!(book.publisher.as_deref(), Some("HarperCollins"));
+        assert_eq!(book.page_count, Some(310));
+
+        // In insert mode the same rows are left alone
+        let report = import_native(&pool, csv, ImportMode::Insert, false).await;
+        assert_eq!((report.skipped, report.updated), (2, 0));
+    }
+
+    #[tokio::test]
+    async fn csv_rows_matching_two_books_are_left_alone() {
+        let pool = memory_pool().await;
+        seed(&pool).await;
+
+        let csv = "title,identifiers\nMixed up,ISBN_13:9780261102217; EAN_13:9771121580009\n";
+        let reason = "Identifiers match both vol-1 and vol-2";
+        let report = import_native(&pool, csv, ImportMode::Merge, false).await;
+        assert!(matches!(
+            &report.rows[0].status,
+            ImportRowStatus::Rejected { reason: r } if r == reason
+        ));
+        let report = import_native(&pool, csv, ImportMode::Insert, false).await;
+        assert!(matches!(
+            &report.rows[0].status,
+            ImportRowStatus::Skipped { reason: r } if r == reason
+        ));
+
+        assert_eq!(count(&pool, "books").await, 2);
+        assert_eq!(load_book(&pool, "vol-1").await.title, "The Hobbit");
+        assert_eq!(load_book(&pool, "vol-2").await.title, "Dylan Dog");
+    }
+
+    async fn copy_ids(pool: &sqlx::SqlitePool, volume_id: &str) -> Vec<i64> {
+        sqlx::query_scalar("SELECT copy_id FROM copies WHERE volume_id = ? ORDER BY copy_id")
+            .bind(volume_id)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn the_last_copy_of_a_book_cant_be_deleted() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+
+        let first = copy_ids(sqlite_pool, "vol-1").await;
+        assert_eq!(first.len(), 1);
+        let details = CopyDetails {
+            price: Some(12.5),
+            currency: Some("eur".to_string()),
+            ..Default::default()
+        };
+        let second = add_copy(&pool, "vol-1", &details).await.unwrap();
+        assert_eq!(second.currency.as_deref(), Some("EUR"));
+
+        delete_copy(&pool, first[0]).await.unwrap();
+        assert_eq!(copy_ids(sqlite_pool, "vol-1").await, [second.copy_id]);
+
+        let err = delete_copy(&pool, second.copy_id).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "This is the only copy, delete the book instead"
+        );
+        assert_eq!(copy_ids(sqlite_pool, "vol-1").await, [second.copy_id]);
+
+        let err = delete_copy(&pool, 999).await.unwrap_err();
+        assert_eq!(err.to_string(), "Copy 999 not found");
+    }
+
+    #[tokio::test]
+    async fn clone_with_number_gives_the_issue_a_copy_of_its_own() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+        let details = CopyDetails {
+            grade_scale: Some(GradeScale::Comic),
+            grade: Some("9.4".to_string()),
+            signed: true,
+            ..Default::default()
+        };
+        add_copy(&pool, "vol-2", &details).await.unwrap();
+
+        let issue = clone_book_with_number(&pool, "vol-2", 13).await.unwrap();
+
+        // The template and its copies are gone, the issue starts with a
+        // single copy without the template's details
+        assert!(copy_ids(sqlite_pool, "vol-2").await.is_empty());
+        assert_eq!(count(sqlite_pool, "books").await, 2);
+        let book = load_book(sqlite_pool, &issue).await;
+        assert_eq!(book.number, Some(13));
+        assert_eq!(book.copies.len(), 1);
+        assert_eq!(book.copies[0].grade, None);
+        assert!(!book.copies[0].signed);
+    }
+
+    async fn search(pool: &sqlx::SqlitePool, input: &str) -> Vec<String> {
+        search_hits(pool, input, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(volume_id, _, _)| volume_id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn search_follows_inserts_edits_and_deletes() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+
+        assert_eq!(search(sqlite_pool, "hobb").await, ["vol-1"]);
+        assert_eq!(search(sqlite_pool, "tolkien").await, ["vol-1"]);
+        assert_eq!(search(sqlite_pool, "very good").await, ["vol-1"]);
+        assert!(search(sqlite_pool, "silmarillion").await.is_empty());
+
+        update_book(
+            &pool,
+            UpdateBookPayload {
+                volume_id: "vol-1".to_string(),
+                title: "The Silmarillion".to_string(),
+                series: None,
+                number: None,
+                publisher: None,
+                published_date: None,
+                description: Some("Of the Ainur".to_string()),
+                page_count: None,
+                language: None,
+                authors: vec!["Tolkien, Christopher".to_string()],
+                groups: Vec::new(),
+                custom_fields: HashMap::from([("Condition".to_string(), "Worn".to_string())]),
+            },
+        )
+        .await
+        .unwrap();
+        assert!(search(sqlite_pool, "hobbit").await.is_empty());
+        assert!(search(sqlite_pool, "anderson").await.is_empty());
+        assert!(search(sqlite_pool, "very good").await.is_empty());
+        assert_eq!(search(sqlite_pool, "silmarillion").await, ["vol-1"]);
+        assert_eq!(search(sqlite_pool, "christopher").await, ["vol-1"]);
+        assert_eq!(search(sqlite_pool, "ainur worn").await, ["vol-1"]);
+
+        // Renaming an author renames them in the index, reordering them too
+        sqlx::query(
+            "UPDATE authors SET name = 'Tolkien, C. R.' WHERE name = 'Tolkien, Christopher'",
+        )
+        .execute(sqlite_pool)
+        .await
+        .unwrap();
+        assert!(search(sqlite_pool, "christopher").await.is_empty());
+        assert_eq!(search(sqlite_pool, "tolkien c").await, ["vol-1"]);
+        sqlx::query("UPDATE book_authors SET position = 1 WHERE volume_id = 'vol-1'")
+            .execute(sqlite_pool)
+            .await
+            .unwrap();
+        assert_eq!(search(sqlite_pool, "tolkien c").await, ["vol-1"]);
+
+        delete_book(&pool, "vol-1", false).await.unwrap();
+        assert!(search(sqlite_pool, "silmarillion").await.is_empty());
+        assert!(search(sqlite_pool, "tolkien").await.is_empty());
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM books_fts")
+            .fetch_one(sqlite_pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[tokio::test]
+    async fn reading_status_moves_through_readings() {
+        use ReadingStatus::*;
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+        let set = |status, date| set_reading_status(&pool, "vol-1", status, date);
+
+        let err = set(Abandoned, None).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Only a book that's being read or wanted can be abandoned"
+        );
+        let state = set(WantToRead, Some("2024-01-01")).await.unwrap();
+        assert_eq!(state.status, Some(WantToRead));
+        assert_eq!(state.started_on, None);
+
+        // Starting the wanted book is the same reading
+        let state = set(Reading, Some("2024-01-10")).await.unwrap();
+        assert_eq!(state.status, Some(Reading));
+        assert_eq!(state.started_on.as_deref(), Some("2024-01-10"));
+        assert_eq!(reading_history(&pool, "vol-1").await.unwrap().len(), 1);
+        let err = set(Read, Some("2024-01-05")).await.unwrap_err();
+        assert_eq!(err.to_string(), "The reading started after 2024-01-05");
+        assert!(set(Read, Some("2024-13-01")).await.is_err());
+
+        let state = set(Read, Some("2024-02-01")).await.unwrap();
+        assert_eq!(state.finished_on.as_deref(), Some("2024-02-01"));
+        assert_eq!(state.current_page, Some(310));
+        assert_eq!(state.times_read, 1);
+        // Marking it read again logs another time through
+        let state = set(Read, Some("2024-02-01")).await.unwrap();
+        assert_eq!(state.times_read, 2);
+        assert_eq!(reading_history(&pool, "vol-1").await.unwrap().len(), 2);
+
+        // A read book is started again as a re-read, shelving it forgets the start
+        let state = set(Reading, Some("2024-03-01")).await.unwrap();
+        assert_eq!(state.started_on.as_deref(), Some("2024-03-01"));
+        assert_eq!(state.finished_on, None);
+        assert_eq!(reading_history(&pool, "vol-1").await.unwrap().len(), 3);
+        set_reading_progress(&pool, "vol-1", 100).await.unwrap();
+        let state = set(WantToRead, None).await.unwrap();
+        assert_eq!(state.status, Some(WantToRead));
+        assert_eq!(state.started_on, None);
+        assert_eq!(state.current_page, None);
+
+        let state = set(Abandoned, Some("2024-04-01")).await.unwrap();
+        assert_eq!(state.status, Some(Abandoned));
+        assert_eq!(state.finished_on.as_deref(), Some("2024-04-01"));
+        assert_eq!(state.times_read, 2);
+        assert!(set(Abandoned, None).await.is_err());
+        assert_eq!(reading_history(&pool, "vol-1").await.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn ratings_are_half_stars_up_to_five() {
+        for rating in [None, Some(0.0), Some(0.5), Some(3.5), Some(5.0)] {
+            assert!(check_rating(rating).is_ok(), "{rating:?}");
+        }
+        for rating in [-0.5, 5.5, 3.2, 4.75, f64::NAN] {
+            assert!(check_rating(Some(rating)).is_err(), "{rating}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rating_a_book_keeps_or_removes_the_review() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+
+        let state = rate_book(&pool, "vol-1", Some(4.5), Some(" Lovely "))
+            .await
+            .unwrap();
+        assert_eq!(state.rating, Some(4.5));
+        assert_eq!(state.review.as_deref(), Some("Lovely"));
+        let err = rate_book(&pool, "vol-1", Some(4.2), None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Ratings go from 0 to 5 in half stars, not 4.2"
+        );
+        assert!(rate_book(&pool, "vol-1", Some(6.0), None).await.is_err());
+        let rating: f64 = sqlx::query_scalar("SELECT rating FROM book_reviews")
+            .fetch_one(sqlite_pool)
+            .await
+            .unwrap();
+        assert_eq!(rating, 4.5);
+
+        let state = rate_book(&pool, "vol-1", None, Some("  ")).await.unwrap();
+        assert_eq!(state.rating, None);
+        assert_eq!(state.review, None);
+        assert_eq!(count(sqlite_pool, "book_reviews").await, 0);
+    }
+
+    #[tokio::test]
+    async fn loans_are_overdue_after_their_due_date() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+
+        let late = lend_book(
+            &pool,
+            LendBook {
+                volume_id: "vol-1",
+                borrower: " Frodo ",
+                lent_on: Some("2020-01-01"),
+                due_on: Some("2020-02-01"),
+                notes: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(late.borrower, "Frodo");
+        assert!(late.overdue);
+        let on_time = lend_book(
+            &pool,
+            LendBook {
+                volume_id: "vol-2",
+                borrower: "Frodo",
+                lent_on: None,
+                due_on: Some("2999-01-01"),
+                notes: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!on_time.overdue);
+
+        let overdue = list_overdue_loans(&pool).await.unwrap();
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue[0].volume_id, "vol-1");
+        let err = lend_book(
+            &pool,
+            LendBook {
+                volume_id: "vol-1",
+                borrower: "Sam",
+                lent_on: None,
+                due_on: None,
+                notes: None,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "\"The Hobbit\" is already lent to Frodo since 2020-01-01"
+        );
+
+        // Once it's back it's no longer overdue, even past the due date
+        let returned = return_book(&pool, "vol-1", Some("2020-03-01"))
+            .await
+            .unwrap();
+        assert_eq!(returned.returned_on.as_deref(), Some("2020-03-01"));
+        assert!(!returned.overdue);
+        assert!(list_overdue_loans(&pool).await.unwrap().is_empty());
+        assert!(return_book(&pool, "vol-1", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn lent_books_are_only_deleted_with_force() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+        let lend = |volume_id| LendBook {
+            volume_id,
+            borrower: "Frodo",
+            lent_on: Some("2020-01-01"),
+            due_on: None,
+            notes: None,
+        };
+        lend_book(&pool, lend("vol-1")).await.unwrap();
+        lend_book(&pool, lend("vol-2")).await.unwrap();
+
+        let err = delete_book(&pool, "vol-1", false).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "\"The Hobbit\" is lent to Frodo since 2020-01-01, return it before deleting it"
+        );
+        assert_eq!(count(sqlite_pool, "books").await, 2);
+        assert_eq!(count(sqlite_pool, "loans").await, 2);
+
+        delete_book(&pool, "vol-1", true).await.unwrap();
+        assert_eq!(count(sqlite_pool, "books").await, 1);
+        assert_eq!(count(sqlite_pool, "loans").await, 1);
+
+        // A returned book has nothing to lose
+        return_book(&pool, "vol-2", None).await.unwrap();
+        delete_book(&pool, "vol-2", false).await.unwrap();
+        assert_eq!(count(sqlite_pool, "books").await, 0);
+        assert_eq!(count(sqlite_pool, "loans").await, 0);
+        assert_eq!(count(sqlite_pool, "copies").await, 0);
+    }
+
+    #[test]
+    fn location_kinds_nest_in_order() {
+        use LocationKind::*;
+        let allowed = [
+            (Room, None),
+            (Bookcase, Some(Room)),
+            (Shelf, Some(Bookcase)),
+            (Box, None),
+            (Box, Some(Room)),
+            (Box, Some(Shelf)),
+        ];
+        let kinds = [Room, Bookcase, Shelf, Box];
+        for kind in kinds {
+            for parent in std::iter::once(None).chain(kinds.map(Some)) {
+                assert_eq!(
+                    kind.check_parent(parent).is_ok(),
+                    allowed.contains(&(kind, parent)),
+                    "{kind:?} in {parent:?}"
+                );
+            }
+        }
+    }
+
+    /// Study › Bookcase › Top shelf and Study › Bookcase › Bottom shelf.
+    async fn shelves(pool: &tauri_plugin_sql::DbPool) -> (Location, Location, Location) {
+        let room = add_location(pool, "Study", LocationKind::Room, None)
+            .await
+            .unwrap();
+        let bookcase = add_location(
+            pool,
+            "Bookcase",
+            LocationKind::Bookcase,
+            Some(room.location_id),
+        )
+        .await
+        .unwrap();
+        let top = add_location(
+            pool,
+            " Top shelf ",
+            LocationKind::Shelf,
+            Some(bookcase.location_id),
+        )
+        .await
+        .unwrap();
+        let bottom = add_location(
+            pool,
+            "Bottom shelf",
+            LocationKind::Shelf,
+            Some(bookcase.location_id),
+        )
+        .await
+        .unwrap();
+        (room, top, bottom)
+    }
+
+    #[tokio::test]
+    async fn locations_are_checked_against_their_parent() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let (room, top, _) = shelves(&pool).await;
+        assert_eq!(top.path, "Study › Bookcase › Top shelf");
+
+        let err = add_location(&pool, "Loose", LocationKind::Shelf, Some(room.location_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "A shelf goes in a bookcase");
+        let err = add_location(
+            &pool,
+            "Top shelf",
+            LocationKind::Box,
+            Some(top.parent_id.unwrap()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "A box goes in a room, on a shelf or on its own"
+        );
+        let err = add_location(&pool, "Top shelf", LocationKind::Shelf, top.parent_id)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "There's already a location called Top shelf there"
+        );
+
+        let boxed = add_location(
+            &pool,
+            "Comics box",
+            LocationKind::Box,
+            Some(top.location_id),
+        )
+        .await
+        .unwrap();
+        assert_eq!(boxed.path, "Study › Bookcase › Top shelf › Comics box");
+        // Moving it keeps the kind rules
+        let err = update_location(&pool, boxed.location_id, "Comics box", top.parent_id)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "A box goes in a room, on a shelf or on its own"
+        );
+        let moved = update_location(&pool, boxed.location_id, "Comics box", None)
+            .await
+            .unwrap();
+        assert_eq!(moved.path, "Comics box");
+    }
+
+    #[tokio::test]
+    async fn books_and_copies_move_between_locations() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+        add_copy(&pool, "vol-1", &CopyDetails::default())
+            .await
+            .unwrap();
+        let (room, top, bottom) = shelves(&pool).await;
+
+        // A book stands for all its copies
+        let items = LocationItems {
+            volume_ids: vec!["vol-1".to_string()],
+            copy_ids: Vec::new(),
+        };
+        assert_eq!(
+            move_to_location(&pool, &items, Some(top.location_id))
+                .await
+                .unwrap(),
+            2
+        );
+        let vol_2_copy = copy_ids(sqlite_pool, "vol-2").await[0];
+        let items = LocationItems {
+            volume_ids: Vec::new(),
+            copy_ids: vec![vol_2_copy],
+        };
+        assert_eq!(
+            move_to_location(&pool, &items, Some(bottom.location_id))
+                .await
+                .unwrap(),
+            1
+        );
+
+        let on_top = copies_in(sqlite_pool, Some(top.location_id)).await.unwrap();
+        assert_eq!(on_top.len(), 2);
+        assert!(on_top.iter().all(|c| c.volume_id == "vol-1"));
+        // The room has everything on the shelves of its bookcase
+        let in_room = copies_in(sqlite_pool, Some(room.location_id))
+            .await
+            .unwrap();
+        assert_eq!(in_room.len(), 3);
+        assert!(copies_in(sqlite_pool, None).await.unwrap().is_empty());
+
+        let first = on_top[0].copy_id;
+        let items = LocationItems {
+            volume_ids: Vec::new(),
+            copy_ids: vec![first],
+        };
+        assert_eq!(move_to_location(&pool, &items, None).await.unwrap(), 1);
+        let unshelved = copies_in(sqlite_pool, None).await.unwrap();
+        assert_eq!(unshelved.len(), 1);
+        assert_eq!(unshelved[0].copy_id, first);
+        assert_eq!(unshelved[0].location, None);
+
+        assert!(move_to_location(&pool, &items, Some(999)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn stocktake_sorts_scans_into_found_misplaced_and_unknown() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        seed(sqlite_pool).await;
+        let (_, top, bottom) = shelves(&pool).await;
+        let extra = add_copy(
+            &pool,
+            "vol-1",
+            &CopyDetails {
+                location_id: Some(bottom.location_id),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let items = LocationItems {
+            volume_ids: Vec::new(),
+            copy_ids: vec![
+                copy_ids(sqlite_pool, "vol-1").await[0],
+                copy_ids(sqlite_pool, "vol-2").await[0],
+            ],
+        };
+        move_to_location(&pool, &items, Some(top.location_id))
+            .await
+            .unwrap();
+
+        // The ISBN-10 finds the Hobbit expected here, the next scan its copy on
+        // the other shelf and the third one a copy we don't have. Dylan Dog
+        // #13 isn't Dylan Dog #12.
+        let scanned = [
+            "0261102214",
+            " 978-0-261-10221-7 ",
+            "9780261102217",
+            "977112158000913",
+            "",
+            "not a code",
+        ]
+        .map(str::to_string);
+        let report = stocktake(&pool, top.location_id, &scanned).await.unwrap();
+        let ids = |copies: &[LocatedCopy]| copies.iter().map(|c| c.copy_id).collect::<Vec<_>>();
+        assert_eq!(ids(&report.found), [items.copy_ids[0]]);
+        assert_eq!(ids(&report.misplaced), [extra.copy_id]);
+        assert_eq!(
+            report.misplaced[0].location.as_deref(),
+            Some("Study › Bookcase › Bottom shelf")
+        );
+        assert_eq!(
+            report.unknown,
+            ["9780261102217", "977112158000913", "not a code"]
+        );
+        assert_eq!(ids(&report.missing), [items.copy_ids[1]]);
+
+        let scanned = ["977112158000912".to_string()];
+        let report = stocktake(&pool, top.location_id, &scanned).await.unwrap();
+        assert_eq!(ids(&report.found), [items.copy_ids[1]]);
+        assert_eq!(ids(&report.missing), [items.copy_ids[0]]);
+        assert!(report.misplaced.is_empty() && report.unknown.is_empty());
+    }
+
+    #[tokio::test]
+    async fn isbn_comic_barcode_adds_the_next_issue() {
+        let pool = tauri_plugin_sql::DbPool::Sqlite(memory_pool().await);
+        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = &pool;
+        // Scanned without its supplement first, it's stored as an ISBN-13
+        let base = NormalizedBook {
+            volume_id: "vol-1".to_string(),
+            title: "One Piece".to_string(),
+            identifiers: vec![NormalizedIdentifier {
+                type_: "EAN_13".to_string(),
+                identifier: "9788822611628".to_string(),
+            }],
+            ..Default::default()
+        };
+        let mut tx = sqlite_pool.begin().await.unwrap();
+        upsert_normalized_book_in_tx(&mut tx, &base).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let scanned = crate::utils::barcode::parse("978882261162807").unwrap();
+        let number = scanned.issue_number().unwrap();
+        assert_eq!(number, 7);
+        let series_book = find_comic_by_ean(&pool, &scanned.ean)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(series_book.volume_id, "vol-1");
+        assert_eq!(
+            find_comic_issue(&pool, &series_book, number).await.unwrap(),
+            None
+        );
+
+        let issue = clone_book_as_issue(&pool, &series_book.volume_id, number)
+            .await
+            .unwrap();
+        assert_ne!(issue, "vol-1");
+        assert_eq!(
+            find_comic_issue(&pool, &series_book, number).await.unwrap(),
+            Some(issue)
+        );
+    }
 }
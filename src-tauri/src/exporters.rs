@@ -0,0 +1,233 @@
+//! Exports for reference managers, typesetting and library catalogs.
+//!
+//! Every format writes the same [`Entry`]: the book and the cite key it's
+//! exported under, so a selection exported as BibTeX and as RIS cites the
+//! same keys.
+
+mod bibtex;
+mod csl_json;
+mod dublin_core;
+mod marc;
+mod ris;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::db::{Book, BookSelection};
+
+/// Formats the exporters write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Bibtex,
+    Ris,
+    CslJson,
+    /// MARC 21 records in ISO 2709, the binary format.
+    Marc21,
+    #[serde(rename = "marcxml")]
+    MarcXml,
+    DublinCore,
+}
+
+impl ExportFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Bibtex => "BibTeX",
+            ExportFormat::Ris => "RIS",
+            ExportFormat::CslJson => "CSL-JSON",
+            ExportFormat::Marc21 => "MARC 21",
+            ExportFormat::MarcXml => "MARCXML",
+            ExportFormat::DublinCore => "Dublin Core",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Bibtex => "bib",
+            ExportFormat::Ris => "ris",
+            ExportFormat::CslJson => "json",
+            ExportFormat::Marc21 => "mrc",
+            ExportFormat::MarcXml | ExportFormat::DublinCore => "xml",
+        }
+    }
+
+    fn write(&self, entries: &[Entry]) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            ExportFormat::Bibtex => bibtex::write(entries).into_bytes(),
+            ExportFormat::Ris => ris::write(entries).into_bytes(),
+            ExportFormat::CslJson => csl_json::write(entries)?.into_bytes(),
+            ExportFormat::Marc21 => marc::write_iso2709(entries)?,
+            ExportFormat::MarcXml => marc::write_marcxml(entries)?.into_bytes(),
+            ExportFormat::DublinCore => dublin_core::write(entries)?.into_bytes(),
+        })
+    }
+}
+
+/// Which books to export.
+#[derive(Debug, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExportSelection {
+    #[default]
+    All,
+    Books {
+        volume_ids: Vec<String>,
+    },
+    Group {
+        name: String,
+    },
+}
+
+/// A book and its cite key.
+struct Entry<'a> {
+    key: String,
+    book: &'a Book,
+}
+
+impl Entry<'_> {
+    /// Family and given name of every author, in order.
+    fn names(&self) -> Vec<(&str, Option<&str>)> {
+        self.book.authors.iter().map(|a| split_name(a)).collect()
+    }
+
+    /// Year, month and day, as far as the published_date goes.
+    fn date_parts(&self) -> Vec<u32> {
+        date_parts(self.book.published_date.as_deref().unwrap_or_default())
+    }
+
+    /// ISBNs, the ISBN-13s first. Comic barcodes aren't ISBNs.
+    fn isbns(&self) -> Vec<&str> {
+        self.book
+            .identifiers
+            .iter()
+            .filter(|i| matches!(i.type_.as_str(), "ISBN_13" | "ISBN_10"))
+            .map(|i| i.identifier.as_str())
+            .collect()
+    }
+}
+
+/// Splits "Tolkien, J. R. R." or "J. R. R. Tolkien" into the family and given
+/// names. Single names, like "Moebius", only have a family name.
+fn split_name(name: &str) -> (&str, Option<&str>) {
+    let name = name.trim();
+    let (family, given) = match name.split_once(',') {
+        Some((family, given)) => (family, given),
+        None => match name.rsplit_once(' ') {
+            Some((given, family)) => (family, given),
+            None => (name, ""),
+        },
+    };
+    let given = given.trim();
+    (family.trim(), Some(given).filter(|g| !g.is_empty()))
+}
+
+/// The leading numbers of a date like "1937-09-21" or "1937".
+fn date_parts(date: &str) -> Vec<u32> {
+    date.split(['-', 'T'])
+        .take(3)
+        .map_while(|part| part.trim().parse().ok())
+        .collect()
+}
+
+// Articles skipped when picking the title word of a cite key
+const ARTICLES: &[&str] = &[
+    "a", "an", "the", "il", "lo", "la", "i", "gli", "le", "un", "una", "der", "die", "das", "el",
+    "los", "las", "les",
+];
+
+/// Lowercase ASCII letters and digits, accents folded: "Müller" is "muller".
+fn key_part(text: &str) -> String {
+    text.nfkd()
+        .filter(char::is_ascii_alphanumeric)
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// `tolkien1937hobbit`: the family name of the first author, the year and the
+/// first word of the title that isn't an article, with the number of series
+/// issues at the end.
+fn base_key(book: &Book) -> String {
+    let author = book
+        .authors
+        .first()
+        .map(|a| key_part(split_name(a).0))
+        .unwrap_or_default();
+    let year = date_parts(book.published_date.as_deref().unwrap_or_default())
+        .first()
+        .map(|y| y.to_string())
+        .unwrap_or_default();
+    let word = book
+        .title
+        .split_whitespace()
+        .map(key_part)
+        .find(|w| !w.is_empty() && !ARTICLES.contains(&w.as_str()))
+        .unwrap_or_default();
+    let number = book.number.map(|n| n.to_string()).unwrap_or_default();
+
+    let key = format!("{author}{year}{word}{number}");
+    if key.is_empty() {
+        "book".to_string()
+    } else {
+        key
+    }
+}
+
+/// Cite keys for `books`, in the same order. Books that would share a key
+/// get a letter after it, b, c and so on, handed out in volume_id order so
+/// the same books keep the same keys whatever order they're exported in.
+fn cite_keys(books: &[Book]) -> Vec<String> {
+    let bases: Vec<String> = books.iter().map(base_key).collect();
+    let mut sharing: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (book, base) in books.iter().zip(&bases) {
+        sharing.entry(base).or_default().push(&book.volume_id);
+    }
+    for volume_ids in sharing.values_mut() {
+        volume_ids.sort();
+    }
+
+    books
+        .iter()
+        .zip(&bases)
+        .map(|(book, base)| {
+            let position = sharing[base.as_str()]
+                .iter()
+                .position(|id| *id == book.volume_id)
+                .unwrap_or_default();
+            match position {
+                0 => base.clone(),
+                1..=25 => format!("{base}{}", (b'a' + position as u8) as char),
+                _ => format!("{base}{}", position + 1),
+            }
+        })
+        .collect()
+}
+
+pub fn render(format: ExportFormat, books: &[Book]) -> anyhow::Result<Vec<u8>> {
+    let entries: Vec<Entry> = cite_keys(books)
+        .into_iter()
+        .zip(books)
+        .map(|(key, book)| Entry { key, book })
+        .collect();
+    format.write(&entries)
+}
+
+/// Writes the selected books to `save_path`, returns how many there were.
+pub async fn export_books(
+    pool: &tauri_plugin_sql::DbPool,
+    format: ExportFormat,
+    selection: &ExportSelection,
+    save_path: &Path,
+) -> anyhow::Result<usize> {
+    let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
+    let selection = match selection {
+        ExportSelection::All => BookSelection::All,
+        ExportSelection::Books { volume_ids } => BookSelection::VolumeIds(volume_ids),
+        ExportSelection::Group { name } => BookSelection::Group(name),
+    };
+    let books = crate::db::load_books(sqlite_pool, selection, None).await?;
+
+    std::fs::write(save_path, render(format, &books)?)?;
+    Ok(books.len())
+}
@@ -0,0 +1,83 @@
+//! BibTeX `@book` entries. Page counts go to biblatex's `pagetotal`, BibTeX
+//! itself ignores fields it doesn't know.
+
+use super::Entry;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Escapes the characters TeX gives a meaning to.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn write(entries: &[Entry]) -> String {
+    let mut out = String::new();
+
+    for entry in entries {
+        let book = entry.book;
+        let mut fields: Vec<(&str, String)> = Vec::new();
+
+        let authors: Vec<String> = entry
+            .names()
+            .into_iter()
+            .map(|(family, given)| match given {
+                Some(given) => format!("{}, {}", escape(family), escape(given)),
+                None => escape(family),
+            })
+            .collect();
+        if !authors.is_empty() {
+            fields.push(("author", format!("{{{}}}", authors.join(" and "))));
+        }
+        // Double braces keep the capitalization of the title
+        fields.push(("title", format!("{{{{{}}}}}", escape(&book.title))));
+        if let Some(series) = &book.series {
+            fields.push(("series", format!("{{{}}}", escape(series))));
+        }
+        if let Some(number) = book.number {
+            fields.push(("number", format!("{{{number}}}")));
+        }
+        if let Some(publisher) = &book.publisher {
+            fields.push(("publisher", format!("{{{}}}", escape(publisher))));
+        }
+        let date = entry.date_parts();
+        if let Some(year) = date.first() {
+            fields.push(("year", format!("{{{year}}}")));
+        }
+        // Month macros, so styles can print them in their language
+        if let Some(month) = date
+            .get(1)
+            .and_then(|m| MONTHS.get((*m as usize).wrapping_sub(1)))
+        {
+            fields.push(("month", month.to_string()));
+        }
+        if let Some(isbn) = entry.isbns().first() {
+            fields.push(("isbn", format!("{{{isbn}}}")));
+        }
+        if let Some(pages) = book.page_count.filter(|p| *p > 0) {
+            fields.push(("pagetotal", format!("{{{pages}}}")));
+        }
+
+        out.push_str(&format!("@book{{{},\n", entry.key));
+        for (name, value) in fields {
+            out.push_str(&format!("  {name} = {value},\n"));
+        }
+        out.push_str("}\n\n");
+    }
+
+    out
+}
@@ -0,0 +1,81 @@
+//! CSL-JSON, the citeproc input format, which Zotero and pandoc read
+//! directly.
+
+use serde::Serialize;
+
+use super::Entry;
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Name<'a> {
+    Person {
+        family: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        given: Option<&'a str>,
+    },
+    /// Single names, which CSL would otherwise print as a family name with
+    /// an empty initial.
+    Literal { literal: &'a str },
+}
+
+#[derive(Serialize)]
+struct DateVariable {
+    #[serde(rename = "date-parts")]
+    date_parts: Vec<Vec<u32>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Item<'a> {
+    id: &'a str,
+    #[serde(rename = "type")]
+    type_: &'static str,
+    title: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    author: Vec<Name<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection_title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection_number: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publisher: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issued: Option<DateVariable>,
+    #[serde(rename = "ISBN", skip_serializing_if = "Option::is_none")]
+    isbn: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number_of_pages: Option<i64>,
+}
+
+pub fn write(entries: &[Entry]) -> anyhow::Result<String> {
+    let items: Vec<Item> = entries
+        .iter()
+        .map(|entry| {
+            let book = entry.book;
+            let date = entry.date_parts();
+            Item {
+                id: &entry.key,
+                type_: "book",
+                title: &book.title,
+                author: entry
+                    .names()
+                    .into_iter()
+                    .map(|(family, given)| match given {
+                        Some(_) => Name::Person { family, given },
+                        None => Name::Literal { literal: family },
+                    })
+                    .collect(),
+                collection_title: book.series.as_deref(),
+                collection_number: book.number,
+                publisher: book.publisher.as_deref(),
+                issued: (!date.is_empty()).then(|| DateVariable {
+                    date_parts: vec![date],
+                }),
+                isbn: entry.isbns().first().copied(),
+                number_of_pages: book.page_count.filter(|p| *p > 0),
+            }
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&items)?)
+}
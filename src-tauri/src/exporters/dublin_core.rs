@@ -0,0 +1,79 @@
+//! Simple Dublin Core, one `oai_dc:dc` record per book in a `dcCollection`
+//! like the Library of Congress' MARCXML to Dublin Core stylesheet writes.
+
+use quick_xml::events::{BytesDecl, BytesText, Event};
+
+use super::Entry;
+
+const OAI_DC_NAMESPACE: &str = "http://www.openarchives.org/OAI/2.0/oai_dc/";
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+
+pub fn write(entries: &[Entry]) -> anyhow::Result<String> {
+    let mut writer = quick_xml::Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+    writer
+        .create_element("oai_dc:dcCollection")
+        .with_attributes([
+            ("xmlns:oai_dc", OAI_DC_NAMESPACE),
+            ("xmlns:dc", DC_NAMESPACE),
+        ])
+        .write_inner_content(|writer| {
+            for entry in entries {
+                let book = entry.book;
+                let mut elements: Vec<(&str, String)> = vec![("dc:title", book.title.clone())];
+                // Surname first, like the MARC headings
+                for (family, given) in entry.names() {
+                    let name = match given {
+                        Some(given) => format!("{family}, {given}"),
+                        None => family.to_string(),
+                    };
+                    elements.push(("dc:creator", name));
+                }
+                elements.push(("dc:type", "text".to_string()));
+                if let Some(publisher) = &book.publisher {
+                    elements.push(("dc:publisher", publisher.clone()));
+                }
+                if let Some(date) = &book.published_date {
+                    elements.push(("dc:date", date.clone()));
+                }
+                if let Some(language) = &book.language {
+                    elements.push(("dc:language", language.clone()));
+                }
+                if let Some(pages) = book.page_count.filter(|p| *p > 0) {
+                    elements.push(("dc:format", format!("{pages} pages")));
+                }
+                for isbn in entry.isbns() {
+                    elements.push(("dc:identifier", format!("URN:ISBN:{isbn}")));
+                }
+                if let Some(series) = &book.series {
+                    let relation = match book.number {
+                        Some(number) => format!("{series} ; {number}"),
+                        None => series.clone(),
+                    };
+                    elements.push(("dc:relation", relation));
+                }
+                for category in &book.categories {
+                    elements.push(("dc:subject", category.clone()));
+                }
+
+                writer
+                    .create_element("oai_dc:dc")
+                    .write_inner_content(|writer| {
+                        for (name, value) in &elements {
+                            let value = value.trim();
+                            if value.is_empty() {
+                                continue;
+                            }
+                            writer
+                                .create_element(*name)
+                                .write_text_content(BytesText::new(value))?;
+                        }
+                        Ok(())
+                    })?;
+            }
+            Ok(())
+        })?;
+
+    Ok(String::from_utf8(writer.into_inner())?)
+}
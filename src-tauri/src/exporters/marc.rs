@@ -0,0 +1,442 @@
+//! MARC 21 bibliographic records, as ISO 2709 binary and as MARCXML.
+//!
+//! Records are minimal level: what the catalog knows about a book, with names
+//! and subjects as they were entered rather than checked against an
+//! authority file. The leader says so, and that ISBD punctuation is omitted.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use quick_xml::events::{BytesDecl, BytesText, Event};
+
+use super::{split_name, Entry, ARTICLES};
+
+const SUBFIELD_DELIMITER: u8 = 0x1F;
+const FIELD_TERMINATOR: u8 = 0x1E;
+const RECORD_TERMINATOR: u8 = 0x1D;
+const LEADER_LENGTH: usize = 24;
+const MARCXML_NAMESPACE: &str = "http://www.loc.gov/MARC21/slim";
+
+#[derive(Debug, PartialEq)]
+pub struct DataField {
+    pub tag: String,
+    pub ind1: char,
+    pub ind2: char,
+    pub subfields: Vec<(char, String)>,
+}
+
+/// A record without its leader, which depends on the encoding and is made by
+/// [`to_iso2709`].
+#[derive(Debug, Default, PartialEq)]
+pub struct Record {
+    pub control_fields: Vec<(String, String)>,
+    pub data_fields: Vec<DataField>,
+}
+
+/// ISO 639-1 codes, as Google Books gives languages, to MARC language codes.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "ara"),
+    ("ca", "cat"),
+    ("cs", "cze"),
+    ("da", "dan"),
+    ("de", "ger"),
+    ("el", "gre"),
+    ("en", "eng"),
+    ("es", "spa"),
+    ("fi", "fin"),
+    ("fr", "fre"),
+    ("he", "heb"),
+    ("hu", "hun"),
+    ("it", "ita"),
+    ("ja", "jpn"),
+    ("ko", "kor"),
+    ("la", "lat"),
+    ("nl", "dut"),
+    ("no", "nor"),
+    ("pl", "pol"),
+    ("pt", "por"),
+    ("ru", "rus"),
+    ("sv", "swe"),
+    ("tr", "tur"),
+    ("zh", "chi"),
+];
+
+fn language_code(language: Option<&str>) -> String {
+    let language = language.unwrap_or_default().trim().to_lowercase();
+    // Regional variants like "pt-BR" share the code of the language
+    let base = language.split(['-', '_']).next().unwrap_or_default();
+    match LANGUAGES.iter().find(|(iso, _)| *iso == base) {
+        Some((_, marc)) => marc.to_string(),
+        None if base.len() == 3 && base.chars().all(|c| c.is_ascii_lowercase()) => base.to_string(),
+        None => "und".to_string(),
+    }
+}
+
+/// Characters to skip when filing a title: "The " in "The Hobbit", "L'" in
+/// "L'Étranger". MARC has a single digit for it.
+fn nonfiling_characters(title: &str) -> char {
+    let title = title.trim_start().to_lowercase();
+    let skip = if let Some(elided) = ["l'", "l’", "un'"].iter().find(|e| title.starts_with(**e)) {
+        elided.chars().count()
+    } else {
+        match title.split_once(' ') {
+            Some((first, _)) if ARTICLES.contains(&first) => first.chars().count() + 1,
+            _ => 0,
+        }
+    };
+    char::from_digit(skip.min(9) as u32, 10).unwrap_or('0')
+}
+
+/// Today as yymmdd, the date the record was created on.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or_default() as i64;
+    // Days since 1970-01-01 to a civil date, from Howard Hinnant's algorithms
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:02}{month:02}{day:02}", year % 100)
+}
+
+/// 008, the fixed-length data elements for books. What we don't know is
+/// filled with |, "no attempt to code".
+fn fixed_length_data(entry: &Entry, entered: &str) -> String {
+    let book = entry.book;
+    let date = match entry.date_parts().first() {
+        Some(year) => format!("s{year:04}    "),
+        None => "nuuuu    ".to_string(),
+    };
+    let form = if book.is_ebook == Some(1) { 'o' } else { ' ' };
+    format!(
+        "{entered}{date}xx |||||{form}|||||||| ||{}|d",
+        language_code(book.language.as_deref())
+    )
+}
+
+fn field(tag: &str, ind1: char, ind2: char, subfields: &[(char, &str)]) -> DataField {
+    DataField {
+        tag: tag.to_string(),
+        ind1,
+        ind2,
+        subfields: subfields
+            .iter()
+            .filter(|(_, value)| !value.trim().is_empty())
+            .map(|(code, value)| (*code, value.trim().to_string()))
+            .collect(),
+    }
+}
+
+/// A personal name heading, surname first.
+fn name_field(tag: &str, name: &str) -> DataField {
+    match split_name(name) {
+        (family, Some(given)) => field(tag, '1', ' ', &[('a', &format!("{family}, {given}"))]),
+        (forename, None) => field(tag, '0', ' ', &[('a', forename)]),
+    }
+}
+
+pub fn record(entry: &Entry, entered: &str) -> Record {
+    let book = entry.book;
+    let mut data_fields = Vec::new();
+
+    for isbn in entry.isbns() {
+        data_fields.push(field("020", ' ', ' ', &[('a', isbn)]));
+    }
+    // Comic barcodes, as EANs
+    for ean in book.identifiers.iter().filter(|i| i.type_ == "EAN_13") {
+        data_fields.push(field("024", '3', ' ', &[('a', &ean.identifier)]));
+    }
+
+    let mut authors = book.authors.iter();
+    let main_entry = authors.next();
+    if let Some(author) = main_entry {
+        data_fields.push(name_field("100", author));
+    }
+
+    data_fields.push(field(
+        "245",
+        if main_entry.is_some() { '1' } else { '0' },
+        nonfiling_characters(&book.title),
+        &[('a', &book.title)],
+    ));
+
+    let year = entry.date_parts().first().map(|y| y.to_string());
+    if book.publisher.is_some() || year.is_some() {
+        data_fields.push(field(
+            "264",
+            ' ',
+            '1',
+            &[
+                ('b', book.publisher.as_deref().unwrap_or_default()),
+                ('c', year.as_deref().unwrap_or_default()),
+            ],
+        ));
+    }
+
+    if let Some(pages) = book.page_count.filter(|p| *p > 0) {
+        data_fields.push(field("300", ' ', ' ', &[('a', &format!("{pages} pages"))]));
+    }
+
+    if let Some(series) = &book.series {
+        let number = book.number.map(|n| n.to_string()).unwrap_or_default();
+        data_fields.push(field("490", '0', ' ', &[('a', series), ('v', &number)]));
+    }
+
+    for category in &book.categories {
+        data_fields.push(field("650", ' ', '4', &[('a', category)]));
+    }
+
+    for author in authors {
+        data_fields.push(name_field("700", author));
+    }
+
+    Record {
+        control_fields: vec![
+            ("001".to_string(), book.volume_id.clone()),
+            ("008".to_string(), fixed_length_data(entry, entered)),
+        ],
+        data_fields,
+    }
+}
+
+/// The ISO 2709 record, leader and directory included.
+pub fn to_iso2709(record: &Record) -> anyhow::Result<Vec<u8>> {
+    let mut directory = Vec::new();
+    let mut data = Vec::new();
+    let mut push_field = |tag: &str, content: Vec<u8>| -> anyhow::Result<()> {
+        let length = content.len() + 1;
+        if length > 9_999 {
+            anyhow::bail!("Field {tag} is too long for a MARC record");
+        }
+        directory.extend_from_slice(format!("{tag}{length:04}{:05}", data.len()).as_bytes());
+        data.extend(content);
+        data.push(FIELD_TERMINATOR);
+        Ok(())
+    };
+
+    for (tag, value) in &record.control_fields {
+        push_field(tag, value.as_bytes().to_vec())?;
+    }
+    for field in &record.data_fields {
+        let mut content = format!("{}{}", field.ind1, field.ind2).into_bytes();
+        for (code, value) in &field.subfields {
+            content.push(SUBFIELD_DELIMITER);
+            content.extend_from_slice(code.to_string().as_bytes());
+            content.extend_from_slice(value.as_bytes());
+        }
+        push_field(&field.tag, content)?;
+    }
+
+    let base_address = LEADER_LENGTH + directory.len() + 1;
+    let record_length = base_address + data.len() + 1;
+    if record_length > 99_999 {
+        anyhow::bail!("Record is too long for MARC");
+    }
+
+    // New record, language material, monograph, Unicode, minimal level
+    let mut out = format!("{record_length:05}nam a22{base_address:05}7c 4500").into_bytes();
+    out.extend(directory);
+    out.push(FIELD_TERMINATOR);
+    out.extend(data);
+    out.push(RECORD_TERMINATOR);
+    Ok(out)
+}
+
+pub fn write_iso2709(entries: &[Entry]) -> anyhow::Result<Vec<u8>> {
+    let entered = today();
+    let mut out = Vec::new();
+    for entry in entries {
+        out.extend(to_iso2709(&record(entry, &entered))?);
+    }
+    Ok(out)
+}
+
+pub fn write_marcxml(entries: &[Entry]) -> anyhow::Result<String> {
+    let entered = today();
+    let mut writer = quick_xml::Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+    writer
+        .create_element("collection")
+        .with_attribute(("xmlns", MARCXML_NAMESPACE))
+        .write_inner_content(|writer| {
+            for entry in entries {
+                let record = record(entry, &entered);
+                // The leader of the binary record, lengths included
+                let binary = to_iso2709(&record).map_err(std::io::Error::other)?;
+                let leader = String::from_utf8_lossy(&binary[..LEADER_LENGTH]).to_string();
+
+                writer
+                    .create_element("record")
+                    .write_inner_content(|writer| {
+                        writer
+                            .create_element("leader")
+                            .write_text_content(BytesText::new(&leader))?;
+                        for (tag, value) in &record.control_fields {
+                            writer
+                                .create_element("controlfield")
+                                .with_attribute(("tag", tag.as_str()))
+                                .write_text_content(BytesText::new(value))?;
+                        }
+                        for field in &record.data_fields {
+                            writer
+                                .create_element("datafield")
+                                .with_attributes([
+                                    ("tag", field.tag.as_str()),
+                                    ("ind1", field.ind1.to_string().as_str()),
+                                    ("ind2", field.ind2.to_string().as_str()),
+                                ])
+                                .write_inner_content(|writer| {
+                                    for (code, value) in &field.subfields {
+                                        writer
+                                            .create_element("subfield")
+                                            .with_attribute(("code", code.to_string().as_str()))
+                                            .write_text_content(BytesText::new(value))?;
+                                    }
+                                    Ok(())
+                                })?;
+                        }
+                        Ok(())
+                    })?;
+            }
+            Ok(())
+        })?;
+
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Book, BookIdentifier};
+    use crate::exporters::{render, ExportFormat};
+
+    /// Reads the records of a MARCXML collection, with their leaders.
+    fn parse_marcxml(xml: &str) -> Vec<(String, Record)> {
+        let mut reader = quick_xml::Reader::from_str(xml);
+        let mut records = Vec::new();
+        let mut leader = String::new();
+        let mut record = Record::default();
+        let mut text = String::new();
+        let mut control_tag = String::new();
+        let mut code = ' ';
+
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(e) => {
+                    text.clear();
+                    let attribute = |name: &str| -> String {
+                        let value = e.try_get_attribute(name).unwrap().unwrap();
+                        value.unescape_value().unwrap().to_string()
+                    };
+                    let first_char = |name: &str| attribute(name).chars().next().unwrap();
+                    match e.local_name().as_ref() {
+                        b"controlfield" => control_tag = attribute("tag"),
+                        b"datafield" => record.data_fields.push(DataField {
+                            tag: attribute("tag"),
+                            ind1: first_char("ind1"),
+                            ind2: first_char("ind2"),
+                            subfields: Vec::new(),
+                        }),
+                        b"subfield" => code = first_char("code"),
+                        _ => {}
+                    }
+                }
+                Event::Text(e) => text.push_str(&e.unescape().unwrap()),
+                Event::End(e) => match e.local_name().as_ref() {
+                    b"leader" => leader = std::mem::take(&mut text),
+                    b"controlfield" => record
+                        .control_fields
+                        .push((std::mem::take(&mut control_tag), std::mem::take(&mut text))),
+                    b"subfield" => record
+                        .data_fields
+                        .last_mut()
+                        .unwrap()
+                        .subfields
+                        .push((code, std::mem::take(&mut text))),
+                    b"record" => {
+                        records.push((std::mem::take(&mut leader), std::mem::take(&mut record)))
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        records
+    }
+
+    fn books() -> Vec<Book> {
+        vec![
+            Book {
+                volume_id: "vol-1".to_string(),
+                title: "The Hobbit, or There & Back <Again>".to_string(),
+                publisher: Some("Allen & Unwin".to_string()),
+                published_date: Some("1937-09-21".to_string()),
+                page_count: Some(310),
+                language: Some("en".to_string()),
+                // The order has to survive
+                authors: vec![
+                    "J. R. R. Tolkien".to_string(),
+                    "Anderson, Douglas".to_string(),
+                ],
+                categories: vec!["Fantasy".to_string(), "Classics".to_string()],
+                identifiers: vec![
+                    BookIdentifier {
+                        type_: "ISBN_13".to_string(),
+                        identifier: "9780261102217".to_string(),
+                    },
+                    BookIdentifier {
+                        type_: "ISBN_10".to_string(),
+                        identifier: "0261102214".to_string(),
+                    },
+                ],
+                ..Default::default()
+            },
+            Book {
+                volume_id: "vol-2".to_string(),
+                title: "L'Étranger".to_string(),
+                series: Some("Dylan Dog".to_string()),
+                number: Some(12),
+                language: Some("it".to_string()),
+                authors: vec!["Moebius".to_string()],
+                identifiers: vec![BookIdentifier {
+                    type_: "EAN_13".to_string(),
+                    identifier: "9771121580009".to_string(),
+                }],
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn marcxml_round_trip() {
+        let books = books();
+        let xml = String::from_utf8(render(ExportFormat::MarcXml, &books).unwrap()).unwrap();
+        let parsed = parse_marcxml(&xml);
+        assert_eq!(parsed.len(), books.len());
+
+        for ((leader, parsed), book) in parsed.into_iter().zip(&books) {
+            let entry = Entry {
+                key: String::new(),
+                book,
+            };
+            let entered = &parsed.control_fields[1].1[..6];
+            let expected = record(&entry, entered);
+            assert_eq!(parsed, expected);
+            assert_eq!(expected.control_fields[1].1.chars().count(), 40);
+
+            // The leader is the one of the binary record
+            let binary = to_iso2709(&expected).unwrap();
+            assert_eq!(leader.as_bytes(), &binary[..LEADER_LENGTH]);
+            assert_eq!(leader[..5].parse::<usize>().unwrap(), binary.len());
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! RIS, the tagged format Zotero, EndNote and Mendeley all import.
+//!
+//! Lines end in CRLF as the format specifies, the page count goes to `SP`
+//! which importers read as the number of pages of a book.
+
+use super::Entry;
+
+fn tag(out: &mut String, tag: &str, value: &str) {
+    // A line break would start a new tag
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    out.push_str(&format!("{tag}  - {value}\r\n"));
+}
+
+pub fn write(entries: &[Entry]) -> String {
+    let mut out = String::new();
+
+    for entry in entries {
+        let book = entry.book;
+        tag(&mut out, "TY", "BOOK");
+        tag(&mut out, "ID", &entry.key);
+        tag(&mut out, "TI", &book.title);
+        for (family, given) in entry.names() {
+            match given {
+                Some(given) => tag(&mut out, "AU", &format!("{family}, {given}")),
+                None => tag(&mut out, "AU", family),
+            }
+        }
+        if let Some(publisher) = &book.publisher {
+            tag(&mut out, "PB", publisher);
+        }
+        let date = entry.date_parts();
+        if let Some(year) = date.first() {
+            tag(&mut out, "PY", &year.to_string());
+        }
+        if date.len() > 1 {
+            let date: Vec<String> = date.iter().map(|part| format!("{part:02}")).collect();
+            tag(&mut out, "DA", &format!("{}/", date.join("/")));
+        }
+        for isbn in entry.isbns() {
+            tag(&mut out, "SN", isbn);
+        }
+        if let Some(pages) = book.page_count.filter(|p| *p > 0) {
+            tag(&mut out, "SP", &pages.to_string());
+        }
+        // ER is the one tag without a value, it keeps the trailing space
+        out.push_str("ER  - \r\n\r\n");
+    }
+
+    out
+}
@@ -1,7 +1,12 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
 use reqwest::Client;
 use serde::Deserialize;
-use sqlx::{Sqlite, Transaction};
-use tauri::Manager;
+
+use crate::providers::{MetadataProvider, NormalizedBook, NormalizedIdentifier};
+use crate::settings::AppSettings;
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -27,6 +32,7 @@ struct VolumeInfo {
     title: String,
     authors: Option<Vec<String>>,
     publisher: Option<String>,
+    #[serde(rename = "publishedDate")]
     published_date: Option<String>,
     description: Option<String>,
     #[serde(rename = "pageCount")]
@@ -101,291 +107,357 @@ struct Availability {
     is_available: Option<bool>,
 }
 
+pub const DEFAULT_BASE_URL: &str = "https://www.googleapis.com/books/v1/volumes";
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
 pub struct GoogleBooksClient {
     http: Client,
+    base_url: String,
+    timeout: Duration,
+    user_agent: Option<String>,
+}
+
+pub struct GoogleBooksClientBuilder {
+    http: Client,
+    base_url: String,
+    timeout: Duration,
+    user_agent: Option<String>,
+}
+
+impl GoogleBooksClientBuilder {
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    pub fn build(self) -> GoogleBooksClient {
+        GoogleBooksClient {
+            http: self.http,
+            base_url: self.base_url,
+            timeout: self.timeout,
+            user_agent: self.user_agent,
+        }
+    }
 }
 
 impl GoogleBooksClient {
-    pub fn new() -> Self {
-        Self {
+    pub fn builder() -> GoogleBooksClientBuilder {
+        GoogleBooksClientBuilder {
             http: Client::new(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: None,
+        }
+    }
+
+    // Settings are read on every lookup, so they're layered on top of whatever
+    // the client was built with instead of rebuilding it on save. The HTTP
+    // client and its connections are shared.
+    fn with_settings(&self, settings: &AppSettings) -> Self {
+        let mut builder = GoogleBooksClientBuilder {
+            http: self.http.clone(),
+            base_url: self.base_url.clone(),
+            timeout: self.timeout,
+            user_agent: self.user_agent.clone(),
+        };
+        if let Some(base_url) = &settings.google_books_base_url {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(secs) = settings.google_books_timeout_secs {
+            builder = builder.timeout(Duration::from_secs(secs));
         }
+        if let Some(user_agent) = &settings.google_books_user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        builder.build()
     }
 
-    // Returns Some(volume_id) if inserted/updated, None if no items for ISBN
-    pub async fn fetch_and_store_by_isbn(
+    // Google often has several volumes for the same ISBN, they're returned in
+    // the order the API ranks them.
+    pub async fn fetch_candidates_by_isbn(
         &self,
-        pool: &tauri_plugin_sql::DbPool,
         isbn: &str,
-        app_handle: &tauri::AppHandle,
         api_key: &str,
-    ) -> anyhow::Result<Option<String>> {
-        let tauri_plugin_sql::DbPool::Sqlite(sqlite_pool) = pool;
-
-        // 1) Fetch using query params
-        let resp = self
+    ) -> anyhow::Result<Vec<NormalizedBook>> {
+        let mut req = self
             .http
-            .get("https://www.googleapis.com/books/v1/volumes")
-            .query(&[("q", format!("isbn:{isbn}")), ("key", api_key.to_string())])
+            .get(&self.base_url)
+            .timeout(self.timeout)
+            .query(&[("q", format!("isbn:{isbn}")), ("key", api_key.to_string())]);
+        if let Some(user_agent) = &self.user_agent {
+            req = req.header(USER_AGENT, user_agent);
+        }
+
+        let resp = req
             .send()
             .await?
             .error_for_status()?
             .json::<VolumesResponse>()
             .await?;
 
-        let Some(items) = resp.items else {
-            return Ok(None);
-        };
-        if items.is_empty() {
-            return Ok(None);
-        }
-        let v = &items[0];
-        let vi = &v.volume_info;
-
-        // 2) Begin transaction
-        let mut tx: Transaction<'_, Sqlite> = sqlite_pool.begin().await?;
-
-        // Helpers
-        let img = vi.image_links.as_ref();
-        let as_i64 = |b: Option<bool>| -> i64 {
-            if b.unwrap_or(false) {
-                1
-            } else {
-                0
-            }
-        };
+        Ok(resp
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| NormalizedBook {
+                metadata_source: Some(MetadataProvider::name(self).to_string()),
+                ..normalize_volume(v)
+            })
+            .collect())
+    }
+}
 
-        let (s_country, saleability, is_ebook) = if let Some(s) = &v.sale_info {
-            (
-                s.country.clone(),
-                s.saleability.clone(),
-                Some(as_i64(s.is_ebook)),
-            )
-        } else {
-            (None, None, None)
-        };
+#[async_trait]
+impl MetadataProvider for GoogleBooksClient {
+    fn name(&self) -> &'static str {
+        "google_books"
+    }
 
-        let (
-            a_country,
-            viewability,
-            embeddable,
-            public_domain,
-            tts_perm,
-            epub_avail,
-            pdf_avail,
-            web_reader_link,
-            access_view_status,
-            quote_sharing_allowed,
-        ) = if let Some(a) = &v.access_info {
-            (
-                a.country.clone(),
-                a.viewability.clone(),
-                Some(as_i64(a.embeddable)),
-                Some(as_i64(a.public_domain)),
-                a.text_to_speech_permission.clone(),
-                Some(as_i64(a.epub.as_ref().and_then(|e| e.is_available))),
-                Some(as_i64(a.pdf.as_ref().and_then(|p| p.is_available))),
-                a.web_reader_link.clone(),
-                a.access_view_status.clone(),
-                Some(as_i64(a.quote_sharing_allowed)),
-            )
-        } else {
-            (None, None, None, None, None, None, None, None, None, None)
+    async fn fetch_by_isbn(
+        &self,
+        isbn: &str,
+        settings: &AppSettings,
+    ) -> anyhow::Result<Option<NormalizedBook>> {
+        let candidates = MetadataProvider::fetch_candidates_by_isbn(self, isbn, settings).await?;
+        Ok(candidates.into_iter().next())
+    }
+
+    async fn fetch_candidates_by_isbn(
+        &self,
+        isbn: &str,
+        settings: &AppSettings,
+    ) -> anyhow::Result<Vec<NormalizedBook>> {
+        let Some(api_key) = settings.google_books_api_key.as_deref() else {
+            anyhow::bail!("Google Books API key not configured");
         };
 
-        let country = s_country.or(a_country);
-
-        // 3) Upsert book
-        sqlx::query(
-            r#"
-            INSERT INTO books (
-              volume_id, title, publisher, published_date, description,
-              page_count, print_type, maturity_rating, language,
-              preview_link, info_link, canonical_link, small_thumbnail,
-              thumbnail, country, saleability, is_ebook, viewability,
-              embeddable, public_domain, text_to_speech_permission,
-              epub_available, pdf_available, web_reader_link,
-              access_view_status, quote_sharing_allowed
-            ) VALUES (
-              ?, ?, ?, ?, ?,
-              ?, ?, ?, ?,
-              ?, ?, ?, ?,
-              ?, ?, ?, ?, ?,
-              ?, ?, ?,
-              ?, ?, ?,
-              ?, ?
-            )
-            ON CONFLICT(volume_id) DO UPDATE SET
-              title=excluded.title,
-              publisher=excluded.publisher,
-              published_date=excluded.published_date,
-              description=excluded.description,
-              page_count=excluded.page_count,
-              print_type=excluded.print_type,
-              maturity_rating=excluded.maturity_rating,
-              language=excluded.language,
-              preview_link=excluded.preview_link,
-              info_link=excluded.info_link,
-              canonical_link=excluded.canonical_link,
-              small_thumbnail=excluded.small_thumbnail,
-              thumbnail=excluded.thumbnail,
-              country=excluded.country,
-              saleability=excluded.saleability,
-              is_ebook=excluded.is_ebook,
-              viewability=excluded.viewability,
-              embeddable=excluded.embeddable,
-              public_domain=excluded.public_domain,
-              text_to_speech_permission=excluded.text_to_speech_permission,
-              epub_available=excluded.epub_available,
-              pdf_available=excluded.pdf_available,
-              web_reader_link=excluded.web_reader_link,
-              access_view_status=excluded.access_view_status,
-              quote_sharing_allowed=excluded.quote_sharing_allowed
-            "#,
-        )
-        .bind(&v.id)
-        .bind(&vi.title)
-        .bind(&vi.publisher)
-        .bind(&vi.published_date)
-        .bind(&vi.description)
-        .bind(&vi.page_count)
-        .bind(&vi.print_type)
-        .bind(&vi.maturity_rating)
-        .bind(&vi.language)
-        .bind(&vi.preview_link)
-        .bind(&vi.info_link)
-        .bind(&vi.canonical_volume_link)
-        .bind(img.and_then(|i| i.small_thumbnail.clone()))
-        .bind(img.and_then(|i| i.thumbnail.clone()))
-        .bind(&country)
-        .bind(&saleability)
-        .bind(is_ebook)
-        .bind(&viewability)
-        .bind(embeddable)
-        .bind(public_domain)
-        .bind(&tts_perm)
-        .bind(epub_avail)
-        .bind(pdf_avail)
-        .bind(&web_reader_link)
-        .bind(&access_view_status)
-        .bind(quote_sharing_allowed)
-        .execute(&mut *tx)
-        .await?;
-
-        // 4) Upsert identifiers
-        if let Some(ids) = &vi.industry_identifiers {
-            for ii in ids {
-                sqlx::query(
-                    r#"
-                    INSERT INTO book_identifiers (volume_id, type, identifier)
-                    VALUES (?, ?, ?)
-                    "#,
-                )
-                .bind(&v.id)
-                .bind(&ii.type_)
-                .bind(&ii.identifier)
-                .execute(&mut *tx)
-                .await?;
-            }
-        }
+        self.with_settings(settings)
+            .fetch_candidates_by_isbn(isbn, api_key)
+            .await
+    }
+}
 
-        // 5) Upsert authors and join (preserve order)
-        if let Some(authors) = &vi.authors {
-            sqlx::query("DELETE FROM book_authors WHERE volume_id = ?")
-                .bind(&v.id)
-                .execute(&mut *tx)
-                .await?;
-
-            for (pos, name) in authors.iter().enumerate() {
-                sqlx::query(
-                    r#"INSERT INTO authors (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#,
-                )
-                .bind(name)
-                .execute(&mut *tx)
-                .await?;
-
-                sqlx::query(
-                    r#"
-                    INSERT INTO book_authors (volume_id, author_id, position)
-                    SELECT ?, author_id, ?
-                    FROM authors WHERE name = ?
-                    ON CONFLICT(volume_id, author_id)
-                    DO UPDATE SET position=excluded.position
-                    "#,
-                )
-                .bind(&v.id)
-                .bind(pos as i64)
-                .bind(name)
-                .execute(&mut *tx)
-                .await?;
-            }
-        }
+/// Maps a Google Books volume to our `books` columns, flattening sale and
+/// access info and turning booleans into SQLite integers.
+fn normalize_volume(v: Volume) -> NormalizedBook {
+    let vi = v.volume_info;
 
-        // 6) Upsert categories and join
-        if let Some(cats) = &vi.categories {
-            for name in cats {
-                sqlx::query(
-                    r#"INSERT INTO categories (name) VALUES (?) ON CONFLICT(name) DO NOTHING"#,
-                )
-                .bind(name)
-                .execute(&mut *tx)
-                .await?;
-
-                sqlx::query(
-                    r#"
-                    INSERT INTO book_categories (volume_id, category_id)
-                    SELECT ?, category_id
-                    FROM categories WHERE name = ?
-                    ON CONFLICT(volume_id, category_id) DO NOTHING
-                    "#,
-                )
-                .bind(&v.id)
-                .bind(name)
-                .execute(&mut *tx)
-                .await?;
-            }
+    let as_i64 = |b: Option<bool>| -> i64 {
+        if b.unwrap_or(false) {
+            1
+        } else {
+            0
         }
+    };
+
+    let (s_country, saleability, is_ebook) = if let Some(s) = v.sale_info {
+        (s.country, s.saleability, Some(as_i64(s.is_ebook)))
+    } else {
+        (None, None, None)
+    };
+
+    let mut book = NormalizedBook {
+        volume_id: v.id,
+        title: vi.title,
+        publisher: vi.publisher,
+        published_date: vi.published_date,
+        description: vi.description,
+        page_count: vi.page_count,
+        print_type: vi.print_type,
+        maturity_rating: vi.maturity_rating,
+        language: vi.language,
+        preview_link: vi.preview_link,
+        info_link: vi.info_link,
+        canonical_link: vi.canonical_volume_link,
+        small_thumbnail: vi
+            .image_links
+            .as_ref()
+            .and_then(|i| i.small_thumbnail.clone()),
+        thumbnail: vi.image_links.and_then(|i| i.thumbnail),
+        saleability,
+        is_ebook,
+        authors: vi.authors.unwrap_or_default(),
+        categories: vi.categories.unwrap_or_default(),
+        identifiers: vi
+            .industry_identifiers
+            .unwrap_or_default()
+            .into_iter()
+            .map(|ii| NormalizedIdentifier {
+                type_: ii.type_,
+                identifier: ii.identifier,
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    if let Some(a) = v.access_info {
+        book.country = s_country.or(a.country);
+        book.viewability = a.viewability;
+        book.embeddable = Some(as_i64(a.embeddable));
+        book.public_domain = Some(as_i64(a.public_domain));
+        book.text_to_speech_permission = a.text_to_speech_permission;
+        book.epub_available = Some(as_i64(a.epub.and_then(|e| e.is_available)));
+        book.pdf_available = Some(as_i64(a.pdf.and_then(|p| p.is_available)));
+        book.web_reader_link = a.web_reader_link;
+        book.access_view_status = a.access_view_status;
+        book.quote_sharing_allowed = Some(as_i64(a.quote_sharing_allowed));
+    } else {
+        book.country = s_country;
+    }
 
-        tx.commit().await?;
+    book
+}
 
-        if let Some(img) = vi.image_links.as_ref() {
-            if let Some(thumb_url) = &img.thumbnail {
-                self.download_thumbnail(&v.id, thumb_url, &app_handle)
-                    .await
-                    .ok();
-            }
-        } else {
-            println!("[DEBUG] Failed downloading thumbnail")
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock_server;
+
+    // Trimmed from a volumes?q=isbn:9780261102217 response
+    const VOLUMES: &str = r#"{
+      "kind": "books#volumes",
+      "totalItems": 1,
+      "items": [
+        {
+          "id": "pD6arNyKyi8C",
+          "volumeInfo": {
+            "title": "The Hobbit",
+            "authors": ["J. R. R. Tolkien"],
+            "publisher": "HarperCollins UK",
+            "publishedDate": "2009-04-20",
+            "description": "In a hole in the ground there lived a hobbit.",
+            "industryIdentifiers": [
+              {"type": "ISBN_10", "identifier": "0261102214"},
+              {"type": "ISBN_13", "identifier": "9780261102217"}
+            ],
+            "pageCount": 310,
+            "printType": "BOOK",
+            "categories": ["Fiction"],
+            "maturityRating": "NOT_MATURE",
+            "imageLinks": {
+              "smallThumbnail": "http://books.google.com/small.jpg",
+              "thumbnail": "http://books.google.com/thumb.jpg"
+            },
+            "language": "en",
+            "previewLink": "http://books.google.it/preview",
+            "infoLink": "http://books.google.it/info",
+            "canonicalVolumeLink": "https://books.google.com/canonical"
+          },
+          "saleInfo": {"country": "IT", "saleability": "NOT_FOR_SALE", "isEbook": false},
+          "accessInfo": {
+            "country": "IT",
+            "viewability": "PARTIAL",
+            "embeddable": true,
+            "publicDomain": false,
+            "textToSpeechPermission": "ALLOWED_FOR_ACCESSIBILITY",
+            "epub": {"isAvailable": true},
+            "pdf": {"isAvailable": false},
+            "webReaderLink": "http://play.google.com/reader",
+            "accessViewStatus": "SAMPLE",
+            "quoteSharingAllowed": false
+          }
         }
+      ]
+    }"#;
+
+    #[test]
+    fn normalizes_volume() {
+        let resp: VolumesResponse = serde_json::from_str(VOLUMES).unwrap();
+        let book = normalize_volume(resp.items.unwrap().remove(0));
+
+        assert_eq!(book.volume_id, "pD6arNyKyi8C");
+        assert_eq!(book.title, "The Hobbit");
+        assert_eq!(book.authors, vec!["J. R. R. Tolkien"]);
+        assert_eq!(book.publisher.as_deref(), Some("HarperCollins UK"));
+        assert_eq!(book.published_date.as_deref(), Some("2009-04-20"));
+        assert_eq!(book.page_count, Some(310));
+        assert_eq!(book.categories, vec!["Fiction"]);
+        assert_eq!(
+            book.canonical_link.as_deref(),
+            Some("https://books.google.com/canonical")
+        );
+        assert_eq!(
+            book.small_thumbnail.as_deref(),
+            Some("http://books.google.com/small.jpg")
+        );
+        assert_eq!(
+            book.thumbnail.as_deref(),
+            Some("http://books.google.com/thumb.jpg")
+        );
+        let identifiers: Vec<(&str, &str)> = book
+            .identifiers
+            .iter()
+            .map(|i| (i.type_.as_str(), i.identifier.as_str()))
+            .collect();
+        assert_eq!(
+            identifiers,
+            vec![("ISBN_10", "0261102214"), ("ISBN_13", "9780261102217")]
+        );
 
-        Ok(Some(v.id.clone()))
+        // Sale and access info flattened, booleans as integers
+        assert_eq!(book.country.as_deref(), Some("IT"));
+        assert_eq!(book.saleability.as_deref(), Some("NOT_FOR_SALE"));
+        assert_eq!(book.is_ebook, Some(0));
+        assert_eq!(book.embeddable, Some(1));
+        assert_eq!(book.public_domain, Some(0));
+        assert_eq!(book.epub_available, Some(1));
+        assert_eq!(book.pdf_available, Some(0));
+        assert_eq!(book.access_view_status.as_deref(), Some("SAMPLE"));
+        assert_eq!(book.quote_sharing_allowed, Some(0));
+        // Set by the client, not the normalizer
+        assert_eq!(book.metadata_source, None);
     }
 
-    async fn download_thumbnail(
-        &self,
-        volume_id: &str,
-        url: &str,
-        app_handle: &tauri::AppHandle,
-    ) -> anyhow::Result<()> {
-        println!(
-            "[DEBUG] Downloading thumbnail for {} from {}",
-            volume_id, url
+    #[tokio::test]
+    async fn fetches_from_the_configured_base_url() {
+        let (base_url, server) = mock_server(VOLUMES, Duration::ZERO);
+        let client = GoogleBooksClient::builder()
+            .base_url(format!("{base_url}/books/v1/volumes"))
+            .user_agent("isbn-scanner-test")
+            .build();
+
+        let books = client
+            .fetch_candidates_by_isbn("9780261102217", "secret")
+            .await
+            .unwrap();
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].title, "The Hobbit");
+        assert_eq!(books[0].metadata_source.as_deref(), Some("google_books"));
+
+        let head = server.join().unwrap();
+        assert_eq!(
+            head[0],
+            "GET /books/v1/volumes?q=isbn%3A9780261102217&key=secret HTTP/1.1"
         );
+        assert!(head
+            .iter()
+            .any(|line| line.eq_ignore_ascii_case("user-agent: isbn-scanner-test")));
+    }
 
-        let img_data = self.http.get(url).send().await?.bytes().await?;
-        println!("[DEBUG] Downloaded {} bytes", img_data.len());
-
-        let app_data_dir = app_handle.path().app_data_dir()?;
-        let books_dir = app_data_dir.join("books");
-
-        std::fs::create_dir_all(&books_dir)?;
-
-        let file_path = books_dir.join(format!("{}.jpg", volume_id));
-        println!("[DEBUG] Saving thumbnail to: {}", file_path.display());
-
-        std::fs::write(&file_path, img_data)?;
-        println!("[DEBUG] Thumbnail saved successfully");
-
-        Ok(())
+    #[tokio::test]
+    async fn gives_up_after_the_timeout() {
+        let (base_url, server) = mock_server(VOLUMES, Duration::from_secs(1));
+        let client = GoogleBooksClient::builder()
+            .base_url(format!("{base_url}/books/v1/volumes"))
+            .timeout(Duration::from_millis(100))
+            .build();
+
+        let err = client
+            .fetch_candidates_by_isbn("9780261102217", "secret")
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<reqwest::Error>().unwrap().is_timeout());
+        server.join().unwrap();
     }
 }
@@ -0,0 +1,239 @@
+//! Imports from other cataloguing apps.
+//!
+//! Every format is converted to rows in the shape of the app's own CSV export
+//! and goes through the same import as that, so matching existing books,
+//! merging and the import report work the same whatever the source.
+
+pub mod calibre;
+mod clz;
+mod comicrack;
+mod goodreads;
+mod librarything;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::CSV_LIST_SEPARATOR;
+
+/// Sources the importers understand.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// The CSV written by `export_books_to_csv`.
+    #[default]
+    Native,
+    /// Goodreads' `goodreads_library_export.csv`.
+    Goodreads,
+    /// LibraryThing's tab-delimited export.
+    #[serde(rename = "librarything")]
+    LibraryThing,
+    /// CLZ Comics' CSV export.
+    Clz,
+    /// ComicRack's `ComicDb.xml`.
+    #[serde(rename = "comicrack")]
+    ComicRack,
+    /// A Calibre library folder, read by `import_calibre_library` rather than
+    /// as a file.
+    Calibre,
+}
+
+impl ImportFormat {
+    /// Guesses the format from the header line of the file.
+    pub fn detect(content: &str) -> Self {
+        let header = content.lines().next().unwrap_or_default();
+        if content.trim_start().starts_with('<') && content.contains("<ComicDatabase") {
+            ImportFormat::ComicRack
+        } else if header.contains('\t') && header.contains("Primary Author") {
+            ImportFormat::LibraryThing
+        } else if header.contains("Exclusive Shelf") {
+            ImportFormat::Goodreads
+        } else if header.contains("Series") && header.contains("Issue") {
+            // Our own export has lowercase column names
+            ImportFormat::Clz
+        } else {
+            ImportFormat::Native
+        }
+    }
+}
+
+/// A row in the shape `db::write_books_csv` produces.
+///
+/// Native rows have every column of the file, converted ones only the columns
+/// they have a value for, so merging them leaves the rest of the book alone.
+pub struct CsvRow {
+    pub headers: csv::StringRecord,
+    pub record: csv::StringRecord,
+    /// Cover image, relative to the folder of the imported file or library.
+    pub cover: Option<std::path::PathBuf>,
+}
+
+/// Collects the values of a converted row.
+#[derive(Default)]
+struct RowBuilder {
+    headers: csv::StringRecord,
+    record: csv::StringRecord,
+}
+
+impl RowBuilder {
+    fn set(&mut self, column: &str, value: Option<&str>) {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return;
+        };
+        self.headers.push_field(column);
+        self.record.push_field(value);
+    }
+
+    fn list(&mut self, column: &str, values: &[String]) {
+        if values.is_empty() {
+            return;
+        }
+        self.set(column, Some(&values.join(CSV_LIST_SEPARATOR)));
+    }
+
+    /// `line` is the line of the row in the source file, for the report.
+    fn build(mut self, line: u64) -> CsvRow {
+        let mut position = csv::Position::new();
+        position.set_line(line);
+        self.record.set_position(Some(position));
+        CsvRow {
+            headers: self.headers,
+            record: self.record,
+            cover: None,
+        }
+    }
+}
+
+/// Reads the text of an export. Some apps write UTF-16 with a byte order
+/// mark, the rest is expected to be UTF-8.
+pub fn decode(bytes: &[u8]) -> anyhow::Result<String> {
+    let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| from([c[0], c[1]])).collect();
+        String::from_utf16(&units)
+    };
+
+    Ok(match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8(rest.to_vec())?,
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes)?,
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes)?,
+        _ => String::from_utf8(bytes.to_vec())?,
+    })
+}
+
+/// Reads the rows of `content` and converts them to the native columns.
+/// Rows the CSV reader can't parse are kept as errors for the report, a file
+/// that can't be read at all is an error.
+pub fn read_rows(
+    format: ImportFormat,
+    content: &str,
+) -> anyhow::Result<Vec<Result<CsvRow, csv::Error>>> {
+    Ok(match format {
+        ImportFormat::Native => read_native(content),
+        ImportFormat::Goodreads => goodreads::read(content),
+        ImportFormat::LibraryThing => librarything::read(content),
+        ImportFormat::Clz => clz::read(content),
+        ImportFormat::ComicRack => comicrack::read(content)?,
+        // A folder, read by calibre::read
+        ImportFormat::Calibre => Vec::new(),
+    })
+}
+
+fn read_native(content: &str) -> Vec<Result<CsvRow, csv::Error>> {
+    let mut rdr = csv::Reader::from_reader(content.as_bytes());
+    let headers = match rdr.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return vec![Err(e)],
+    };
+    rdr.records()
+        .map(|record| {
+            record.map(|record| CsvRow {
+                // Covers are exported to images/, named after the volume_id
+                cover: field(&headers, &record, "volume_id")
+                    .map(|id| std::path::Path::new("images").join(format!("{id}.jpg"))),
+                headers: headers.clone(),
+                record,
+            })
+        })
+        .collect()
+}
+
+/// The value of the `name` column, trimmed, if there is one.
+fn field<'r>(
+    headers: &csv::StringRecord,
+    record: &'r csv::StringRecord,
+    name: &str,
+) -> Option<&'r str> {
+    headers
+        .iter()
+        .position(|h| h == name)
+        .and_then(|i| record.get(i))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Splits a list like "sci-fi, to-read" and drops the empty entries.
+fn split_list(value: Option<&str>, separator: char) -> Vec<String> {
+    value
+        .map(|v| {
+            v.split(separator)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Adds the values we don't have yet, keeping their order.
+fn extend_unique(list: &mut Vec<String>, values: Vec<String>) {
+    for value in values {
+        if !list.contains(&value) {
+            list.push(value);
+        }
+    }
+}
+
+/// An ISBN as spreadsheet-safe exports write it: `="0441172717"`, or
+/// `[0441172717]` for LibraryThing. Returns it as a native `TYPE:identifier`
+/// entry. Values that aren't a valid ISBN are dropped, so a typo doesn't
+/// reject the whole book.
+fn isbn_identifier(value: &str) -> Option<String> {
+    let isbn = value
+        .trim()
+        .trim_start_matches('=')
+        .trim_matches(|c| c == '"' || c == '[' || c == ']')
+        .trim();
+    let (id_type, isbn) = crate::utils::normalize_identifier(isbn).ok()?;
+    Some(format!("{id_type}:{isbn}"))
+}
+
+/// A comic barcode as collection apps store it: an EAN-13 or a 12 digit UPC-A,
+/// optionally followed by its EAN-2/EAN-5 supplement. Returns the
+/// `EAN_13:...` entry, which every issue of the series shares like in the
+/// scanning flow, and the issue number carried by the supplement. Invalid
+/// barcodes are dropped rather than failing the import, like
+/// `comicinfo::gtin_identifier` does.
+fn comic_barcode(value: &str) -> (Option<String>, Option<i64>) {
+    let digits = crate::utils::isbn::normalize(value);
+    // A UPC-A is an EAN-13 starting with 0
+    let digits = match digits.len() {
+        12 | 14 | 17 => format!("0{digits}"),
+        _ => digits,
+    };
+    match crate::utils::barcode::parse(&digits) {
+        Ok(barcode) => (
+            Some(format!("EAN_13:{}", barcode.ean)),
+            barcode.issue_number(),
+        ),
+        Err(_) => (None, None),
+    }
+}
+
+/// Issue numbers that fit our `number` column, "½" or "1.5" don't.
+fn whole_number(value: Option<&str>) -> Option<i64> {
+    value.and_then(|v| v.trim().parse().ok())
+}
+
+/// The columns of a converted row with their values, in the order they were set.
+#[cfg(test)]
+fn columns(row: &CsvRow) -> Vec<(&str, &str)> {
+    row.headers.iter().zip(row.record.iter()).collect()
+}
@@ -0,0 +1,302 @@
+//! Calibre libraries, read straight from the `metadata.db` in the library
+//! folder.
+//!
+//! Books keep Calibre's uuid as their volume_id, so importing the same library
+//! again matches the books imported the first time. Series and series_index
+//! become the series and number, tags become groups and the rating the book's
+//! rating. Covers are the `cover.jpg` in each book's folder.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use sqlx::Connection;
+
+use super::{isbn_identifier, CsvRow, RowBuilder};
+
+const METADATA_DB: &str = "metadata.db";
+
+/// (book id, value) rows grouped by book, in query order.
+async fn load_values(
+    conn: &mut sqlx::SqliteConnection,
+    sql: &str,
+) -> anyhow::Result<HashMap<i64, Vec<String>>> {
+    let mut values: HashMap<i64, Vec<String>> = HashMap::new();
+    for (book, value) in sqlx::query_as::<_, (i64, String)>(sql)
+        .fetch_all(conn)
+        .await?
+    {
+        values.entry(book).or_default().push(value);
+    }
+    Ok(values)
+}
+
+/// Calibre rates out of 10, shown as stars out of 5.
+fn stars(rating: i64) -> String {
+    if rating % 2 == 0 {
+        (rating / 2).to_string()
+    } else {
+        format!("{}.5", rating / 2)
+    }
+}
+
+pub async fn read(library_dir: &Path) -> anyhow::Result<Vec<Result<CsvRow, csv::Error>>> {
+    let db_path = library_dir.join(METADATA_DB);
+    if !db_path.exists() {
+        anyhow::bail!("Not a Calibre library, {METADATA_DB} is missing");
+    }
+    let options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(&db_path)
+        .read_only(true);
+    let mut conn = sqlx::SqliteConnection::connect_with(&options).await?;
+
+    // Calibre declares its dates as TIMESTAMP, cast so they decode as text
+    let books = sqlx::query_as::<_, (i64, String, String, String, bool, f64, Option<String>)>(
+        r#"
+        SELECT id, uuid, title, path, has_cover, series_index, CAST(pubdate AS TEXT)
+        FROM books
+        ORDER BY id
+        "#,
+    )
+    .fetch_all(&mut conn)
+    .await?;
+
+    let mut authors = load_values(
+        &mut conn,
+        r#"
+        SELECT l.book, a.name FROM books_authors_link l
+        JOIN authors a ON a.id = l.author
+        ORDER BY l.id
+        "#,
+    )
+    .await?;
+    let mut tags = load_values(
+        &mut conn,
+        r#"
+        SELECT l.book, t.name FROM books_tags_link l
+        JOIN tags t ON t.id = l.tag
+        ORDER BY t.name
+        "#,
+    )
+    .await?;
+    let mut series = load_values(
+        &mut conn,
+        r#"
+        SELECT l.book, s.name FROM books_series_link l
+        JOIN series s ON s.id = l.series
+        "#,
+    )
+    .await?;
+    let mut publishers = load_values(
+        &mut conn,
+        r#"
+        SELECT l.book, p.name FROM books_publishers_link l
+        JOIN publishers p ON p.id = l.publisher
+        "#,
+    )
+    .await?;
+    let mut comments = load_values(&mut conn, "SELECT book, text FROM comments").await?;
+    let mut ratings = load_values(
+        &mut conn,
+        r#"
+        SELECT l.book, CAST(r.rating AS TEXT) FROM books_ratings_link l
+        JOIN ratings r ON r.id = l.rating
+        WHERE r.rating > 0
+        "#,
+    )
+    .await?;
+    let mut identifiers: HashMap<i64, Vec<(String, String)>> = HashMap::new();
+    for (book, id_type, value) in sqlx::query_as::<_, (i64, String, String)>(
+        "SELECT book, type, val FROM identifiers ORDER BY id",
+    )
+    .fetch_all(&mut conn)
+    .await?
+    {
+        identifiers.entry(book).or_default().push((id_type, value));
+    }
+
+    conn.close().await?;
+
+    let mut rows = Vec::new();
+    for (id, uuid, title, path, has_cover, series_index, pubdate) in books {
+        let mut row = RowBuilder::default();
+        row.set("volume_id", Some(&uuid));
+        row.set("title", Some(&title));
+
+        if let Some(name) = series.remove(&id).and_then(|s| s.into_iter().next()) {
+            row.set("series", Some(&name));
+            // Fractional indexes (e.g. a novella at 2.5) have no number
+            if series_index.fract() == 0.0 {
+                row.set("number", Some(&(series_index as i64).to_string()));
+            }
+        }
+
+        // Calibre stores the commas in author names as |
+        let author_names: Vec<String> = authors
+            .remove(&id)
+            .unwrap_or_default()
+            .iter()
+            .map(|a| a.replace('|', ","))
+            .collect();
+        row.list("authors", &author_names);
+
+        // Amazon ids of printed books are often the ISBN-10 again. Calibre
+        // doesn't check ISBNs, invalid ones are dropped and the book kept
+        let mut seen = HashSet::new();
+        let book_identifiers: Vec<String> = identifiers
+            .remove(&id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, value)| seen.insert(crate::utils::isbn::normalize(value)))
+            .filter_map(|(id_type, value)| match id_type.as_str() {
+                "isbn" => isbn_identifier(&value),
+                _ => Some(format!("{}:{}", id_type.to_uppercase(), value.trim())),
+            })
+            .collect();
+        row.list("identifiers", &book_identifiers);
+
+        row.list("groups", &tags.remove(&id).unwrap_or_default());
+
+        let first = |values: Option<Vec<String>>| values.and_then(|v| v.into_iter().next());
+        row.set("publisher", first(publishers.remove(&id)).as_deref());
+        row.set("description", first(comments.remove(&id)).as_deref());
+        // Unknown dates are stored as the year 101
+        let published_date = pubdate
+            .filter(|d| !d.starts_with("0101"))
+            .map(|d| d.chars().take(10).collect::<String>());
+        row.set("published_date", published_date.as_deref());
+        row.set("metadata_source", Some("calibre"));
+
+        let rating = first(ratings.remove(&id)).and_then(|r| r.parse::<i64>().ok());
+        row.set("rating", rating.map(stars).as_deref());
+
+        let mut row = row.build(id as u64);
+        if has_cover {
+            row.cover = Some(Path::new(&path).join("cover.jpg"));
+        }
+        rows.push(Ok(row));
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::columns;
+
+    // The parts of Calibre's schema the importer reads
+    const METADATA: &str = r#"
+    CREATE TABLE books (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      title TEXT NOT NULL DEFAULT 'Unknown',
+      pubdate TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+      series_index REAL NOT NULL DEFAULT 1.0,
+      path TEXT NOT NULL DEFAULT '',
+      uuid TEXT,
+      has_cover BOOL DEFAULT 0
+    );
+    CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE books_authors_link (id INTEGER PRIMARY KEY, book INTEGER, author INTEGER);
+    CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE books_tags_link (id INTEGER PRIMARY KEY, book INTEGER, tag INTEGER);
+    CREATE TABLE series (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE books_series_link (id INTEGER PRIMARY KEY, book INTEGER, series INTEGER);
+    CREATE TABLE publishers (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE books_publishers_link (id INTEGER PRIMARY KEY, book INTEGER, publisher INTEGER);
+    CREATE TABLE comments (id INTEGER PRIMARY KEY, book INTEGER, text TEXT);
+    CREATE TABLE ratings (id INTEGER PRIMARY KEY, rating INTEGER);
+    CREATE TABLE books_ratings_link (id INTEGER PRIMARY KEY, book INTEGER, rating INTEGER);
+    CREATE TABLE identifiers (id INTEGER PRIMARY KEY, book INTEGER, type TEXT, val TEXT);
+
+    INSERT INTO books (id, title, pubdate, series_index, path, uuid, has_cover) VALUES
+      (1, 'The Way of Kings', '2010-08-31 00:00:00+00:00', 1.0,
+       'Brandon Sanderson/The Way of Kings (1)', 'uuid-1', 1),
+      (2, 'Why We Can''t Wait', '0101-01-01 00:00:00+00:00', 1.0,
+       'Martin Luther King, Jr/Why We Can''t Wait (2)', 'uuid-2', 0),
+      (3, 'Edgedancer', '2016-10-04 00:00:00+00:00', 2.5, 'Brandon Sanderson/Edgedancer (3)',
+       'uuid-3', 0);
+    INSERT INTO authors (id, name) VALUES
+      (1, 'Brandon Sanderson'), (2, 'Martin Luther King| Jr.');
+    INSERT INTO books_authors_link (book, author) VALUES (1, 1), (2, 2), (3, 1);
+    INSERT INTO tags (id, name) VALUES (1, 'fantasy'), (2, 'epic');
+    INSERT INTO books_tags_link (book, tag) VALUES (1, 1), (1, 2);
+    INSERT INTO series (id, name) VALUES (1, 'The Stormlight Archive');
+    INSERT INTO books_series_link (book, series) VALUES (1, 1), (3, 1);
+    INSERT INTO publishers (id, name) VALUES (1, 'Tor Books');
+    INSERT INTO books_publishers_link (book, publisher) VALUES (1, 1);
+    INSERT INTO comments (book, text) VALUES (1, '<p>Roshar.</p>');
+    INSERT INTO ratings (id, rating) VALUES (1, 9);
+    INSERT INTO books_ratings_link (book, rating) VALUES (1, 1);
+    INSERT INTO identifiers (book, type, val) VALUES
+      (1, 'isbn', '0765326353'),
+      (1, 'amazon', '0765326353'),
+      (1, 'goodreads', '7235533'),
+      (2, 'isbn', '12345'),
+      (2, 'amazon', 'B00BHMFA24');
+    "#;
+
+    #[tokio::test]
+    async fn reads_the_library() {
+        let library_dir = std::env::temp_dir().join(format!("calibre-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&library_dir).unwrap();
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(library_dir.join(METADATA_DB))
+            .create_if_missing(true);
+        let mut conn = sqlx::SqliteConnection::connect_with(&options)
+            .await
+            .unwrap();
+        sqlx::raw_sql(METADATA).execute(&mut conn).await.unwrap();
+        conn.close().await.unwrap();
+
+        let rows: Vec<CsvRow> = read(&library_dir)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        std::fs::remove_dir_all(&library_dir).unwrap();
+        assert_eq!(rows.len(), 3);
+
+        // The Amazon id repeating the ISBN-10 is dropped
+        assert_eq!(
+            columns(&rows[0]),
+            [
+                ("volume_id", "uuid-1"),
+                ("title", "The Way of Kings"),
+                ("series", "The Stormlight Archive"),
+                ("number", "1"),
+                ("authors", "Brandon Sanderson"),
+                ("identifiers", "ISBN_10:0765326353; GOODREADS:7235533"),
+                ("groups", "epic; fantasy"),
+                ("publisher", "Tor Books"),
+                ("description", "<p>Roshar.</p>"),
+                ("published_date", "2010-08-31"),
+                ("metadata_source", "calibre"),
+                ("rating", "4.5"),
+            ]
+        );
+        assert_eq!(
+            rows[0].cover.as_deref(),
+            Some(Path::new(
+                "Brandon Sanderson/The Way of Kings (1)/cover.jpg"
+            ))
+        );
+
+        // The junk ISBN goes, the book and its other identifier stay
+        assert_eq!(
+            columns(&rows[1]),
+            [
+                ("volume_id", "uuid-2"),
+                ("title", "Why We Can't Wait"),
+                ("authors", "Martin Luther King, Jr."),
+                ("identifiers", "AMAZON:B00BHMFA24"),
+                ("metadata_source", "calibre"),
+            ]
+        );
+        assert_eq!(rows[1].cover, None);
+
+        // A novella between two volumes has no number
+        assert!(!columns(&rows[2]).iter().any(|(c, _)| *c == "number"));
+        assert_eq!(rows[2].record.position().unwrap().line(), 3);
+    }
+}
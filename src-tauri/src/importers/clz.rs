@@ -0,0 +1,138 @@
+//! CLZ Comics' CSV export, from Tools → Export to CSV.
+//!
+//! Issues get the series as their title, like scanned comics, the story title
+//! goes to a custom field together with the variant and grade. Every creator
+//! column becomes an author.
+
+use super::{comic_barcode, extend_unique, field, split_list, whole_number, CsvRow, RowBuilder};
+
+// In the order their names end up in the authors
+const CREATOR_COLUMNS: &[&str] = &[
+    "Writer",
+    "Plotter",
+    "Scripter",
+    "Artist",
+    "Penciller",
+    "Inker",
+    "Colorist",
+    "Letterer",
+    "Cover Artist",
+    "Editor",
+    "Creators",
+];
+
+pub fn read(content: &str) -> Vec<Result<CsvRow, csv::Error>> {
+    let mut rdr = csv::Reader::from_reader(content.as_bytes());
+    let headers = match rdr.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return vec![Err(e)],
+    };
+
+    rdr.records()
+        .map(|record| record.map(|record| convert(&headers, &record)))
+        .collect()
+}
+
+fn convert(headers: &csv::StringRecord, record: &csv::StringRecord) -> CsvRow {
+    let get = |name: &str| field(headers, record, name);
+    let mut row = RowBuilder::default();
+
+    let series = get("Series");
+    row.set("title", series.or(get("Title")));
+    row.set("series", series);
+
+    let (barcode, barcode_issue) = get("Barcode").map(comic_barcode).unwrap_or_default();
+    let number = whole_number(get("Issue Nr"))
+        .or(whole_number(get("Issue")))
+        .or(barcode_issue);
+    row.set("number", number.map(|n| n.to_string()).as_deref());
+    row.list("identifiers", &barcode.into_iter().collect::<Vec<_>>());
+
+    let mut authors = Vec::new();
+    for column in CREATOR_COLUMNS {
+        extend_unique(&mut authors, split_list(get(column), ','));
+    }
+    row.list("authors", &authors);
+
+    row.set("publisher", get("Publisher"));
+    row.set("published_date", get("Release Date").or(get("Cover Date")));
+    row.set(
+        "page_count",
+        get("Pages").filter(|p| p.parse::<i64>().is_ok()),
+    );
+    row.set("description", get("Plot").or(get("Synopsis")));
+    row.set("metadata_source", Some("clz"));
+
+    if series.is_some() {
+        row.set("Story Title", get("Title"));
+    }
+    row.set("Variant", get("Variant"));
+    row.set("Grade", get("Grade"));
+    row.set("Notes", get("Notes"));
+
+    row.build(record.position().map(|p| p.line()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::columns;
+
+    // A UPC-A with an EAN-5, an EAN-13 with an EAN-2 and an invalid barcode
+    const EXPORT: &str = r#"Series,Issue,Issue Nr,Title,Barcode,Writer,Artist,Publisher,Release Date,Pages,Plot,Variant,Grade,Notes
+Amazing Spider-Man,#1,,Homecoming,76194133450900111,"Dan Slott, Humberto Ramos",Humberto Ramos,Marvel,2014-04-30,52,Peter is back.,Variant A,9.8,
+Dylan Dog,,,Killers,977112158000912,Tiziano Sclavi,Claudio Villa,Bonelli,,,,,,Newsstand
+Tex,5,5,,1234567890123,,,Bonelli,,,,,,
+"#;
+
+    #[test]
+    fn reads_the_export() {
+        let rows: Vec<CsvRow> = read(EXPORT).into_iter().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 3);
+
+        // "#1" isn't a number, the issue comes from the supplement
+        assert_eq!(
+            columns(&rows[0]),
+            [
+                ("title", "Amazing Spider-Man"),
+                ("series", "Amazing Spider-Man"),
+                ("number", "1"),
+                ("identifiers", "EAN_13:0761941334509"),
+                ("authors", "Dan Slott; Humberto Ramos"),
+                ("publisher", "Marvel"),
+                ("published_date", "2014-04-30"),
+                ("page_count", "52"),
+                ("description", "Peter is back."),
+                ("metadata_source", "clz"),
+                ("Story Title", "Homecoming"),
+                ("Variant", "Variant A"),
+                ("Grade", "9.8"),
+            ]
+        );
+        assert_eq!(
+            columns(&rows[1]),
+            [
+                ("title", "Dylan Dog"),
+                ("series", "Dylan Dog"),
+                ("number", "12"),
+                ("identifiers", "EAN_13:9771121580009"),
+                ("authors", "Tiziano Sclavi; Claudio Villa"),
+                ("publisher", "Bonelli"),
+                ("metadata_source", "clz"),
+                ("Story Title", "Killers"),
+                ("Notes", "Newsstand"),
+            ]
+        );
+        // The bad barcode is dropped, the issue is still imported
+        assert_eq!(
+            columns(&rows[2]),
+            [
+                ("title", "Tex"),
+                ("series", "Tex"),
+                ("number", "5"),
+                ("publisher", "Bonelli"),
+                ("metadata_source", "clz"),
+            ]
+        );
+    }
+}
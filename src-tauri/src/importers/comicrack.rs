@@ -0,0 +1,209 @@
+//! ComicRack's `ComicDb.xml`, the database it keeps in its application data
+//! folder.
+//!
+//! Each `<Book>` keeps its ComicRack id as volume_id, so importing the database
+//! again matches the issues imported the first time. Creators become authors
+//! and tags become groups.
+
+use std::collections::HashMap;
+
+use quick_xml::events::Event;
+
+use super::{comic_barcode, extend_unique, split_list, whole_number, CsvRow, RowBuilder};
+
+// In the order their names end up in the authors
+const CREATOR_ELEMENTS: &[&str] = &[
+    "Writer",
+    "Penciller",
+    "Inker",
+    "Colorist",
+    "Letterer",
+    "CoverArtist",
+    "Editor",
+];
+
+/// Reads every `<Book>` as the text of its child elements, nested lists like
+/// `<Pages>` are skipped.
+pub fn read(content: &str) -> anyhow::Result<Vec<Result<CsvRow, csv::Error>>> {
+    let mut reader = quick_xml::Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut rows = Vec::new();
+    let mut depth = 0;
+    // Depth of the <Book> we're in, its id and the text of its children
+    let mut book: Option<(usize, Option<String>, HashMap<String, String>)> = None;
+    let mut element = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                depth += 1;
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if book.is_none() && name == "Book" {
+                    let id = e
+                        .try_get_attribute("Id")?
+                        .map(|a| a.unescape_value().map(|v| v.to_string()))
+                        .transpose()?;
+                    book = Some((depth, id, HashMap::new()));
+                }
+                element = name;
+            }
+            Event::Text(e) => {
+                if let Some((book_depth, _, fields)) = &mut book {
+                    if depth == *book_depth + 1 {
+                        fields.insert(element.clone(), e.unescape()?.to_string());
+                    }
+                }
+            }
+            Event::End(_) => {
+                if matches!(&book, Some((book_depth, _, _)) if *book_depth == depth) {
+                    if let Some((_, id, fields)) = book.take() {
+                        rows.push(Ok(convert(id, &fields, rows.len() as u64 + 1)));
+                    }
+                }
+                depth -= 1;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(rows)
+}
+
+fn convert(id: Option<String>, fields: &HashMap<String, String>, position: u64) -> CsvRow {
+    let get = |name: &str| fields.get(name).map(|v| v.trim()).filter(|v| !v.is_empty());
+    let mut row = RowBuilder::default();
+
+    row.set("volume_id", id.as_deref());
+    let series = get("Series");
+    row.set("title", series.or(get("Title")));
+    row.set("series", series);
+
+    let (barcode, barcode_issue) = get("GTIN").map(comic_barcode).unwrap_or_default();
+    let number = whole_number(get("Number")).or(barcode_issue);
+    row.set("number", number.map(|n| n.to_string()).as_deref());
+    row.list("identifiers", &barcode.into_iter().collect::<Vec<_>>());
+
+    let mut authors = Vec::new();
+    for element in CREATOR_ELEMENTS {
+        extend_unique(&mut authors, split_list(get(element), ','));
+    }
+    row.list("authors", &authors);
+
+    row.list("categories", &split_list(get("Genre"), ','));
+    row.list("groups", &split_list(get("Tags"), ','));
+
+    row.set("publisher", get("Publisher"));
+    let published_date = get("Year").map(|year| {
+        [get("Month"), get("Day")]
+            .into_iter()
+            .map_while(|part| part.and_then(|p| p.parse::<u32>().ok()))
+            .fold(year.to_string(), |date, part| format!("{date}-{part:02}"))
+    });
+    row.set("published_date", published_date.as_deref());
+    row.set(
+        "page_count",
+        get("PageCount").filter(|p| p.parse::<i64>().is_ok() && *p != "0"),
+    );
+    row.set("description", get("Summary"));
+    row.set("language", get("LanguageISO"));
+    row.set("metadata_source", Some("comicrack"));
+
+    if series.is_some() {
+        row.set("Story Title", get("Title"));
+    }
+    row.set("Notes", get("Notes"));
+
+    row.build(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::columns;
+
+    const COMIC_DB: &str = r#"<?xml version="1.0"?>
+<ComicDatabase xmlns:xsd="http://www.w3.org/2001/XMLSchema">
+  <Books>
+    <Book Id="6a1f0e3c" File="C:\Comics\Saga 003.cbz">
+      <Series>Saga</Series>
+      <Number>3</Number>
+      <Title>Chapter Three</Title>
+      <Year>2012</Year>
+      <Month>5</Month>
+      <Writer>Brian K. Vaughan</Writer>
+      <Penciller>Fiona Staples</Penciller>
+      <CoverArtist>Fiona Staples</CoverArtist>
+      <Genre>Sci-Fi, Fantasy</Genre>
+      <Tags>Owned</Tags>
+      <PageCount>32</PageCount>
+      <GTIN>76194133450900311</GTIN>
+      <Pages>
+        <Page Image="0" Type="FrontCover" />
+        <Page Image="1">
+          <Bookmark>Not a field of the book</Bookmark>
+        </Page>
+      </Pages>
+      <Notes>Signed &amp; numbered</Notes>
+    </Book>
+    <Book Id="9b2c7d4e">
+      <Title>One-shot</Title>
+      <Number>½</Number>
+      <GTIN>1234567890123</GTIN>
+      <PageCount>0</PageCount>
+    </Book>
+  </Books>
+</ComicDatabase>
+"#;
+
+    #[test]
+    fn reads_the_database() {
+        let rows: Vec<CsvRow> = read(COMIC_DB)
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows.len(), 2);
+
+        assert_eq!(
+            columns(&rows[0]),
+            [
+                ("volume_id", "6a1f0e3c"),
+                ("title", "Saga"),
+                ("series", "Saga"),
+                ("number", "3"),
+                ("identifiers", "EAN_13:0761941334509"),
+                ("authors", "Brian K. Vaughan; Fiona Staples"),
+                ("categories", "Sci-Fi; Fantasy"),
+                ("groups", "Owned"),
+                ("published_date", "2012-05"),
+                ("page_count", "32"),
+                ("metadata_source", "comicrack"),
+                ("Story Title", "Chapter Three"),
+                ("Notes", "Signed & numbered"),
+            ]
+        );
+
+        // No whole number, an invalid barcode and no pages
+        assert_eq!(
+            columns(&rows[1]),
+            [
+                ("volume_id", "9b2c7d4e"),
+                ("title", "One-shot"),
+                ("metadata_source", "comicrack"),
+            ]
+        );
+        assert_eq!(rows[1].record.position().unwrap().line(), 2);
+    }
+
+    #[test]
+    fn number_falls_back_to_the_barcode_supplement() {
+        let content = r#"<ComicDatabase><Books><Book Id="1">
+            <Series>Dylan Dog</Series><GTIN>977112158000912</GTIN>
+        </Book></Books></ComicDatabase>"#;
+        let rows = read(content).unwrap();
+        let row = rows[0].as_ref().unwrap();
+        assert!(columns(row).contains(&("number", "12")));
+    }
+}
@@ -0,0 +1,163 @@
+//! Goodreads' `goodreads_library_export.csv`, from My Books → Import and
+//! export.
+//!
+//! Titles carry the series, "The Way of Kings (The Stormlight Archive, #1)",
+//! shelves become groups and the private notes a custom field. The exclusive
+//! shelf, read dates, rating and review become the reading status.
+
+use super::{extend_unique, field, isbn_identifier, split_list, CsvRow, RowBuilder};
+
+pub fn read(content: &str) -> Vec<Result<CsvRow, csv::Error>> {
+    let mut rdr = csv::Reader::from_reader(content.as_bytes());
+    let headers = match rdr.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return vec![Err(e)],
+    };
+
+    rdr.records()
+        .map(|record| record.map(|record| convert(&headers, &record)))
+        .collect()
+}
+
+/// Splits "Title (Series, #3)" into the title, the series and its number.
+/// Numbers that aren't whole, like "#2.5", only keep the series.
+fn split_series(title: &str) -> (&str, Option<&str>, Option<i64>) {
+    let Some((bare, series)) = title.strip_suffix(')').and_then(|t| t.rsplit_once(" (")) else {
+        return (title, None, None);
+    };
+    let Some((series, number)) = series.rsplit_once(", #") else {
+        return (title, None, None);
+    };
+    (bare.trim(), Some(series.trim()), number.trim().parse().ok())
+}
+
+fn convert(headers: &csv::StringRecord, record: &csv::StringRecord) -> CsvRow {
+    let get = |name: &str| field(headers, record, name);
+    let mut row = RowBuilder::default();
+
+    if let Some(title) = get("Title") {
+        let (title, series, number) = split_series(title);
+        row.set("title", Some(title));
+        row.set("series", series);
+        row.set("number", number.map(|n| n.to_string()).as_deref());
+    }
+
+    // Author is a single name, which can have a comma ("King, Jr.")
+    let mut authors: Vec<String> = get("Author").map(str::to_string).into_iter().collect();
+    extend_unique(&mut authors, split_list(get("Additional Authors"), ','));
+    row.list("authors", &authors);
+
+    let identifiers: Vec<String> = [get("ISBN"), get("ISBN13")]
+        .into_iter()
+        .flatten()
+        .filter_map(isbn_identifier)
+        .collect();
+    row.list("identifiers", &identifiers);
+
+    // The exclusive shelf (read, to-read, ...) isn't always repeated in the
+    // shelves
+    let mut groups = split_list(get("Exclusive Shelf"), ',');
+    extend_unique(&mut groups, split_list(get("Bookshelves"), ','));
+    row.list("groups", &groups);
+
+    row.set("publisher", get("Publisher"));
+    row.set(
+        "published_date",
+        get("Year Published").or(get("Original Publication Year")),
+    );
+    row.set("page_count", get("Number of Pages"));
+    row.set("metadata_source", Some("goodreads"));
+
+    let status = match get("Exclusive Shelf") {
+        Some("read") => Some("read"),
+        Some("currently-reading") => Some("reading"),
+        Some("to-read") => Some("want_to_read"),
+        _ => None,
+    };
+    row.set("reading_status", status);
+    // Dates are written 2023/01/15
+    row.set(
+        "finished_on",
+        get("Date Read").map(|d| d.replace('/', "-")).as_deref(),
+    );
+    row.set("times_read", get("Read Count").filter(|c| *c != "0"));
+
+    // Unrated books have a rating of 0
+    row.set("rating", get("My Rating").filter(|r| *r != "0"));
+    row.set("review", get("My Review"));
+    row.set("Notes", get("Private Notes"));
+
+    row.build(record.position().map(|p| p.line()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::columns;
+
+    const EXPORT: &str = r#"Book Id,Title,Author,Author l-f,Additional Authors,ISBN,ISBN13,My Rating,Average Rating,Publisher,Binding,Number of Pages,Year Published,Original Publication Year,Date Read,Date Added,Bookshelves,Bookshelves with positions,Exclusive Shelf,My Review,Spoiler,Private Notes,Read Count,Owned Copies
+7235533,"The Way of Kings (The Stormlight Archive, #1)",Brandon Sanderson,"Sanderson, Brandon",,"=""0765326353""","=""9780765326355""",5,4.65,Tor Books,Hardcover,1007,2010,2010,2023/01/15,2022/12/01,"fantasy, favorites","fantasy (#3), favorites (#1)",read,Loved it,,Signed copy,1,1
+1023,Why We Can't Wait,"Martin Luther King, Jr.","King, Martin Luther, Jr.","Jesse Jackson, Coretta Scott King","=""""","=""978045152753X""",0,4.40,,Paperback,,,1964,,2024/03/02,,,to-read,,,,0,0
+"#;
+
+    #[test]
+    fn reads_the_export() {
+        let rows: Vec<CsvRow> = read(EXPORT).into_iter().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+
+        assert_eq!(
+            columns(&rows[0]),
+            [
+                ("title", "The Way of Kings"),
+                ("series", "The Stormlight Archive"),
+                ("number", "1"),
+                ("authors", "Brandon Sanderson"),
+                ("identifiers", "ISBN_10:0765326353; ISBN_13:9780765326355"),
+                ("groups", "read; fantasy; favorites"),
+                ("publisher", "Tor Books"),
+                ("published_date", "2010"),
+                ("page_count", "1007"),
+                ("metadata_source", "goodreads"),
+                ("reading_status", "read"),
+                ("finished_on", "2023-01-15"),
+                ("times_read", "1"),
+                ("rating", "5"),
+                ("review", "Loved it"),
+                ("Notes", "Signed copy"),
+            ]
+        );
+        assert_eq!(rows[0].record.position().unwrap().line(), 2);
+
+        // The author keeps its comma, the malformed ISBN-13 is dropped
+        assert_eq!(
+            columns(&rows[1]),
+            [
+                ("title", "Why We Can't Wait"),
+                (
+                    "authors",
+                    "Martin Luther King, Jr.; Jesse Jackson; Coretta Scott King"
+                ),
+                ("groups", "to-read"),
+                ("published_date", "1964"),
+                ("metadata_source", "goodreads"),
+                ("reading_status", "want_to_read"),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_the_series_off_the_title() {
+        assert_eq!(
+            split_series("Dune Messiah (Dune, #2)"),
+            ("Dune Messiah", Some("Dune"), Some(2))
+        );
+        assert_eq!(
+            split_series("Edgedancer (The Stormlight Archive, #2.5)"),
+            ("Edgedancer", Some("The Stormlight Archive"), None)
+        );
+        assert_eq!(
+            split_series("Dune (Deluxe Edition)"),
+            ("Dune (Deluxe Edition)", None, None)
+        );
+    }
+}
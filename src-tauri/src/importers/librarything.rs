@@ -0,0 +1,152 @@
+//! LibraryThing's tab-delimited export, from Tools → Export.
+//!
+//! Authors are written "Last, First", tags and collections become groups and
+//! the comments custom fields. Reading dates, rating and review become the
+//! reading status.
+
+use super::{extend_unique, field, isbn_identifier, split_list, CsvRow, RowBuilder};
+
+// Every book is in it unless the user removed it, it says nothing about the book
+const DEFAULT_COLLECTION: &str = "Your library";
+
+pub fn read(content: &str) -> Vec<Result<CsvRow, csv::Error>> {
+    // Fields aren't quoted, quotes in titles are just quotes
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .quoting(false)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers = match rdr.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return vec![Err(e)],
+    };
+
+    rdr.records()
+        .map(|record| record.map(|record| convert(&headers, &record)))
+        .collect()
+}
+
+/// "Herbert, Frank" → "Frank Herbert", names without a single comma are kept.
+fn author_name(name: &str) -> String {
+    match name.split_once(", ") {
+        Some((last, first)) if !first.contains(',') => format!("{first} {last}"),
+        _ => name.to_string(),
+    }
+}
+
+/// The publisher of "Ace (1990), Edition: Reissue, 528 pages".
+fn publisher(publication: &str) -> Option<&str> {
+    let end = publication.find([',', '(']).unwrap_or(publication.len());
+    Some(publication[..end].trim()).filter(|p| !p.is_empty())
+}
+
+fn convert(headers: &csv::StringRecord, record: &csv::StringRecord) -> CsvRow {
+    let get = |name: &str| field(headers, record, name);
+    let mut row = RowBuilder::default();
+
+    row.set("title", get("Title"));
+
+    let mut authors: Vec<String> = get("Primary Author").map(author_name).into_iter().collect();
+    let secondary = split_list(get("Secondary Author"), '|')
+        .iter()
+        .map(|a| author_name(a))
+        .collect();
+    extend_unique(&mut authors, secondary);
+    row.list("authors", &authors);
+
+    // "ISBNs" lists the ISBN-10 and ISBN-13, older exports only have "ISBN"
+    let isbns = match get("ISBNs") {
+        Some(isbns) => split_list(Some(isbns), ','),
+        None => get("ISBN").map(str::to_string).into_iter().collect(),
+    };
+    let identifiers: Vec<String> = isbns.iter().filter_map(|i| isbn_identifier(i)).collect();
+    row.list("identifiers", &identifiers);
+
+    let mut groups = split_list(get("Tags"), ',');
+    let collections = split_list(get("Collections"), ',')
+        .into_iter()
+        .filter(|c| c != DEFAULT_COLLECTION)
+        .collect();
+    extend_unique(&mut groups, collections);
+    row.list("groups", &groups);
+
+    row.set("publisher", get("Publication").and_then(publisher));
+    row.set("published_date", get("Date"));
+    row.set(
+        "page_count",
+        get("Page Count").filter(|p| p.parse::<i64>().is_ok()),
+    );
+    row.set("metadata_source", Some("librarything"));
+
+    // Only the latest reading is exported
+    let status = if get("Date Read").is_some() {
+        Some("read")
+    } else if get("Date Started").is_some() {
+        Some("reading")
+    } else {
+        None
+    };
+    row.set("reading_status", status);
+    row.set("started_on", get("Date Started"));
+    row.set("finished_on", get("Date Read"));
+
+    row.set("rating", get("Rating"));
+    row.set("review", get("Review"));
+    row.set("Comment", get("Comment"));
+    row.set("Notes", get("Private Comment"));
+
+    row.build(record.position().map(|p| p.line()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::columns;
+
+    // No quoting, the quotes are part of the titles. The second row is short
+    // and its opening quote is never closed
+    const EXPORT: &str = concat!(
+        "Book Id\tTitle\tPrimary Author\tSecondary Author\tPublication\tDate\tReview\tRating\tComment\tPrivate Comment\tPage Count\tDate Started\tDate Read\tTags\tCollections\tISBN\tISBNs\n",
+        "1\tDune \"Deluxe\" Edition\tHerbert, Frank\t\tAce (1990), Edition: Reissue, 528 pages\t1990\tGreat\t4.5\tFirst read in school\tShelf 2\t528\t2023-01-01\t2023-02-01\tsci-fi, classics\tYour library, Favorites\t[0441172717]\t0441172717, 9780441172719\n",
+        "2\t\"Hello\tRoe, Richard\tDoe, Jane|Smith, John\t\t\t\t\t\t\t\t2024-05-01\t\t\tYour library\t[12345]\n",
+    );
+
+    #[test]
+    fn reads_the_export() {
+        let rows: Vec<CsvRow> = read(EXPORT).into_iter().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+
+        assert_eq!(
+            columns(&rows[0]),
+            [
+                ("title", "Dune \"Deluxe\" Edition"),
+                ("authors", "Frank Herbert"),
+                ("identifiers", "ISBN_10:0441172717; ISBN_13:9780441172719"),
+                ("groups", "sci-fi; classics; Favorites"),
+                ("publisher", "Ace"),
+                ("published_date", "1990"),
+                ("page_count", "528"),
+                ("metadata_source", "librarything"),
+                ("reading_status", "read"),
+                ("started_on", "2023-01-01"),
+                ("finished_on", "2023-02-01"),
+                ("rating", "4.5"),
+                ("review", "Great"),
+                ("Comment", "First read in school"),
+                ("Notes", "Shelf 2"),
+            ]
+        );
+
+        assert_eq!(
+            columns(&rows[1]),
+            [
+                ("title", "\"Hello"),
+                ("authors", "Richard Roe; Jane Doe; John Smith"),
+                ("metadata_source", "librarything"),
+                ("reading_status", "reading"),
+                ("started_on", "2024-05-01"),
+            ]
+        );
+        assert_eq!(rows[1].record.position().unwrap().line(), 3);
+    }
+}
@@ -1,34 +1,45 @@
+mod archive;
+mod backup;
+mod comicinfo;
 mod commands;
 mod db;
+mod exporters;
 mod google_books;
+mod importers;
 mod migrations;
+mod providers;
 mod settings;
 mod utils;
 
 use crate::google_books::client::GoogleBooksClient;
+use crate::providers::open_library::OpenLibraryClient;
+use crate::providers::MetadataProvider;
 
 pub struct AppConfig {
-    pub client: GoogleBooksClient,
+    // Metadata providers in the order they're queried
+    pub providers: Vec<Box<dyn MetadataProvider>>,
+    // Shared client for downloads that aren't tied to a provider, like thumbnails
+    pub http: reqwest::Client,
 }
 
 impl AppConfig {
     pub async fn from_env() -> Result<Self, String> {
+        let user_agent = concat!("isbn-scanner/", env!("CARGO_PKG_VERSION"));
+        // The endpoint and timeout are overridden from the settings on each
+        // lookup
+        let google_books = GoogleBooksClient::builder().user_agent(user_agent).build();
+        let open_library = OpenLibraryClient::builder().user_agent(user_agent).build();
+
         Ok(AppConfig {
-            client: GoogleBooksClient::new(),
+            providers: vec![Box::new(google_books), Box::new(open_library)],
+            http: reqwest::Client::new(),
         })
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let migrations = vec![
-        crate::migrations::MIGRATION001,
-        crate::migrations::MIGRATION002,
-        crate::migrations::MIGRATION003,
-        crate::migrations::MIGRATION004,
-        crate::migrations::MIGRATION005,
-        crate::migrations::MIGRATION006,
-    ];
+    let migrations = crate::migrations::all();
 
     let config =
         tauri::async_runtime::block_on(AppConfig::from_env()).expect("Failed to load config");
@@ -44,12 +55,19 @@ pub fn run() {
                 .build(),
         )
         .manage(config)
+        .manage(crate::backup::BackupState::default())
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             crate::commands::fetch_isbn,
+            crate::commands::preview_isbn,
+            crate::commands::commit_preview,
             crate::commands::get_all_books,
+            crate::commands::query_books,
+            crate::commands::search_books,
             crate::commands::get_single_book,
             crate::commands::isbn_exists,
+            crate::commands::find_book_by_isbn,
+            crate::commands::get_isbn_details,
             crate::commands::get_settings,
             crate::commands::set_settings,
             crate::commands::add_book,
@@ -57,14 +75,51 @@ pub fn run() {
             crate::commands::delete_book,
             crate::commands::set_book_number,
             crate::commands::export_books_csv,
+            crate::commands::export_books,
             crate::commands::import_books_csv,
+            crate::commands::import_calibre_library,
+            crate::commands::export_comicinfo,
+            crate::commands::import_comicinfo_folder,
+            crate::commands::export_library_archive,
+            crate::commands::restore_library_archive,
             crate::commands::find_comic_by_ean,
+            crate::commands::add_comic_issue_by_barcode,
             crate::commands::clone_book,
             crate::commands::clone_book_with_number,
             crate::commands::get_all_groups,
             crate::commands::get_all_custom_fields,
             crate::commands::set_book_groups,
+            crate::commands::lend_book,
+            crate::commands::return_book,
+            crate::commands::list_active_loans,
+            crate::commands::list_overdue_loans,
+            crate::commands::loan_history,
+            crate::commands::get_all_borrowers,
+            crate::commands::set_reading_status,
+            crate::commands::set_reading_progress,
+            crate::commands::rate_book,
+            crate::commands::reading_history,
+            crate::commands::update_reading,
+            crate::commands::delete_reading,
+            crate::commands::get_condition_grades,
+            crate::commands::add_copy,
+            crate::commands::update_copy,
+            crate::commands::delete_copy,
+            crate::commands::get_locations,
+            crate::commands::add_location,
+            crate::commands::update_location,
+            crate::commands::delete_location,
+            crate::commands::move_to_location,
+            crate::commands::list_location_contents,
+            crate::commands::stocktake,
+            crate::commands::list_backups,
+            crate::commands::restore_backup,
         ])
+        .setup(|app| {
+            // The sql plugin has opened and migrated the database by now
+            crate::backup::spawn_snapshot(app.handle());
+            Ok(())
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1,5 +1,74 @@
+use sqlx::Executor;
 use tauri_plugin_sql::{Migration, MigrationKind};
 
+/// Every migration, oldest first.
+pub fn all() -> Vec<Migration> {
+    vec![
+        MIGRATION001,
+        MIGRATION002,
+        MIGRATION003,
+        MIGRATION004,
+        MIGRATION005,
+        MIGRATION006,
+        MIGRATION007,
+        MIGRATION008,
+        MIGRATION009,
+        MIGRATION010,
+        MIGRATION011,
+        MIGRATION012,
+        MIGRATION013,
+    ]
+}
+
+/// Version of the newest migration, the schema every database has once the
+/// app started.
+pub fn latest_version() -> i64 {
+    all().iter().map(|m| m.version).max().unwrap_or_default()
+}
+
+/// Drops every table and rebuilds the schema as it was at `version` by running
+/// the migrations up to it. Meant to run inside a transaction that then fills
+/// the tables and calls `migrate_after` with the same version.
+///
+/// `_sqlx_migrations` is left alone, the migrator keeps seeing every migration
+/// as applied.
+pub async fn reset_schema(conn: &mut sqlx::SqliteConnection, version: i64) -> anyhow::Result<()> {
+    // Children are dropped before their parents, rows are checked at commit
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+
+    // Virtual tables first, dropping them takes their shadow tables along
+    let tables = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT name FROM main.sqlite_master
+        WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != '_sqlx_migrations'
+        ORDER BY sql LIKE 'CREATE VIRTUAL TABLE%' DESC, rowid DESC
+        "#,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for table in tables {
+        sqlx::query(&format!("DROP TABLE IF EXISTS main.\"{table}\""))
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    for migration in all().into_iter().filter(|m| m.version <= version) {
+        conn.execute(migration.sql).await?;
+    }
+    Ok(())
+}
+
+/// Runs the migrations newer than `version`, so their backfills apply to rows
+/// restored into a schema built by `reset_schema`.
+pub async fn migrate_after(conn: &mut sqlx::SqliteConnection, version: i64) -> anyhow::Result<()> {
+    for migration in all().into_iter().filter(|m| m.version > version) {
+        conn.execute(migration.sql).await?;
+    }
+    Ok(())
+}
+
 pub const MIGRATION001: Migration = Migration {
     version: 1,
     description: "create_initial_tables",
@@ -166,3 +235,316 @@ pub const MIGRATION006: Migration = Migration {
     sql: "ALTER TABLE books ADD COLUMN series TEXT;",
     kind: MigrationKind::Up,
 };
+
+pub const MIGRATION007: Migration = Migration {
+    version: 7,
+    description: "add_metadata_source",
+    sql: "ALTER TABLE books ADD COLUMN metadata_source TEXT;",
+    kind: MigrationKind::Up,
+};
+
+pub const MIGRATION008: Migration = Migration {
+    version: 8,
+    description: "add_canonical_isbn13",
+    sql: "
+    ALTER TABLE book_identifiers ADD COLUMN canonical_isbn13 TEXT;
+
+    UPDATE book_identifiers
+    SET canonical_isbn13 = UPPER(REPLACE(REPLACE(identifier, '-', ''), ' ', ''));
+
+    -- ISBN-10 to ISBN-13: prefix 978, drop the old check digit and compute the
+    -- EAN one. The 978 prefix always contributes 9*1 + 7*3 + 8*1 = 38. Only
+    -- ISBN-10s with a valid check digit are converted, like utils::isbn does
+    UPDATE book_identifiers
+    SET canonical_isbn13 = '978' || SUBSTR(canonical_isbn13, 1, 9) || (
+      (10 - (
+        38
+        + 3 * (
+          CAST(SUBSTR(canonical_isbn13, 1, 1) AS INTEGER)
+          + CAST(SUBSTR(canonical_isbn13, 3, 1) AS INTEGER)
+          + CAST(SUBSTR(canonical_isbn13, 5, 1) AS INTEGER)
+          + CAST(SUBSTR(canonical_isbn13, 7, 1) AS INTEGER)
+          + CAST(SUBSTR(canonical_isbn13, 9, 1) AS INTEGER)
+        )
+        + CAST(SUBSTR(canonical_isbn13, 2, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 4, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 6, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 8, 1) AS INTEGER)
+      ) % 10) % 10
+    )
+    WHERE LENGTH(canonical_isbn13) = 10
+      AND SUBSTR(canonical_isbn13, 1, 9) NOT GLOB '*[^0-9]*'
+      AND SUBSTR(canonical_isbn13, 10, 1) GLOB '[0-9X]'
+      AND (
+        10 * CAST(SUBSTR(canonical_isbn13, 1, 1) AS INTEGER)
+        + 9 * CAST(SUBSTR(canonical_isbn13, 2, 1) AS INTEGER)
+        + 8 * CAST(SUBSTR(canonical_isbn13, 3, 1) AS INTEGER)
+        + 7 * CAST(SUBSTR(canonical_isbn13, 4, 1) AS INTEGER)
+        + 6 * CAST(SUBSTR(canonical_isbn13, 5, 1) AS INTEGER)
+        + 5 * CAST(SUBSTR(canonical_isbn13, 6, 1) AS INTEGER)
+        + 4 * CAST(SUBSTR(canonical_isbn13, 7, 1) AS INTEGER)
+        + 3 * CAST(SUBSTR(canonical_isbn13, 8, 1) AS INTEGER)
+        + 2 * CAST(SUBSTR(canonical_isbn13, 9, 1) AS INTEGER)
+        + CASE SUBSTR(canonical_isbn13, 10, 1)
+            WHEN 'X' THEN 10
+            ELSE CAST(SUBSTR(canonical_isbn13, 10, 1) AS INTEGER)
+          END
+      ) % 11 = 0;
+
+    -- Anything that isn't a 13 digit code with a valid EAN check digit by now
+    -- has no canonical form
+    UPDATE book_identifiers
+    SET canonical_isbn13 = NULL
+    WHERE LENGTH(canonical_isbn13) != 13
+      OR canonical_isbn13 GLOB '*[^0-9]*'
+      OR (
+        CAST(SUBSTR(canonical_isbn13, 1, 1) AS INTEGER)
+        + 3 * CAST(SUBSTR(canonical_isbn13, 2, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 3, 1) AS INTEGER)
+        + 3 * CAST(SUBSTR(canonical_isbn13, 4, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 5, 1) AS INTEGER)
+        + 3 * CAST(SUBSTR(canonical_isbn13, 6, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 7, 1) AS INTEGER)
+        + 3 * CAST(SUBSTR(canonical_isbn13, 8, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 9, 1) AS INTEGER)
+        + 3 * CAST(SUBSTR(canonical_isbn13, 10, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 11, 1) AS INTEGER)
+        + 3 * CAST(SUBSTR(canonical_isbn13, 12, 1) AS INTEGER)
+        + CAST(SUBSTR(canonical_isbn13, 13, 1) AS INTEGER)
+      ) % 10 != 0;
+
+    CREATE INDEX IF NOT EXISTS idx_identifiers_canonical ON book_identifiers(canonical_isbn13);
+    ",
+    kind: MigrationKind::Up,
+};
+
+// books_fts rows share their rowid with the book they index. Triggers can't
+// call each other, so every trigger that touches indexed data rebuilds the
+// whole row for its book.
+pub const MIGRATION009: Migration = Migration {
+    version: 9,
+    description: "add_books_fts",
+    sql: "
+    CREATE VIRTUAL TABLE IF NOT EXISTS books_fts USING fts5(
+      title,
+      authors,
+      description,
+      custom_fields,
+      tokenize = 'unicode61 remove_diacritics 2'
+    );
+
+    INSERT INTO books_fts (rowid, title, authors, description, custom_fields)
+    SELECT
+      b.rowid,
+      b.title,
+      (SELECT GROUP_CONCAT(a.name, ' ') FROM book_authors ba
+        JOIN authors a ON a.author_id = ba.author_id
+        WHERE ba.volume_id = b.volume_id),
+      b.description,
+      (SELECT GROUP_CONCAT(bcf.value, ' ') FROM book_custom_fields bcf
+        WHERE bcf.volume_id = b.volume_id)
+    FROM books b;
+
+    CREATE TRIGGER IF NOT EXISTS books_fts_books_insert AFTER INSERT ON books BEGIN
+      INSERT INTO books_fts (rowid, title, authors, description, custom_fields)
+      VALUES (NEW.rowid, NEW.title, NULL, NEW.description, NULL);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS books_fts_books_update
+    AFTER UPDATE OF title, description ON books BEGIN
+      DELETE FROM books_fts WHERE rowid = OLD.rowid;
+      INSERT INTO books_fts (rowid, title, authors, description, custom_fields)
+      SELECT
+        NEW.rowid,
+        NEW.title,
+        (SELECT GROUP_CONCAT(a.name, ' ') FROM book_authors ba
+          JOIN authors a ON a.author_id = ba.author_id
+          WHERE ba.volume_id = NEW.volume_id),
+        NEW.description,
+        (SELECT GROUP_CONCAT(bcf.value, ' ') FROM book_custom_fields bcf
+          WHERE bcf.volume_id = NEW.volume_id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS books_fts_books_delete AFTER DELETE ON books BEGIN
+      DELETE FROM books_fts WHERE rowid = OLD.rowid;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS books_fts_authors_insert AFTER INSERT ON book_authors BEGIN
+      UPDATE books_fts
+      SET authors = (SELECT GROUP_CONCAT(a.name, ' ') FROM book_authors ba
+        JOIN authors a ON a.author_id = ba.author_id
+        WHERE ba.volume_id = NEW.volume_id)
+      WHERE rowid = (SELECT rowid FROM books WHERE volume_id = NEW.volume_id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS books_fts_authors_delete AFTER DELETE ON book_authors BEGIN
+      UPDATE books_fts
+      SET authors = (SELECT GROUP_CONCAT(a.name, ' ') FROM book_authors ba
+        JOIN authors a ON a.author_id = ba.author_id
+        WHERE ba.volume_id = OLD.volume_id)
+      WHERE rowid = (SELECT rowid FROM books WHERE volume_id = OLD.volume_id);
+    END;
+
+    -- Positions change when the authors of a book are reordered
+    CREATE TRIGGER IF NOT EXISTS books_fts_authors_update AFTER UPDATE ON book_authors BEGIN
+      UPDATE books_fts
+      SET authors = (SELECT GROUP_CONCAT(a.name, ' ') FROM book_authors ba
+        JOIN authors a ON a.author_id = ba.author_id
+        WHERE ba.volume_id = (SELECT volume_id FROM books WHERE rowid = books_fts.rowid))
+      WHERE rowid IN (SELECT rowid FROM books WHERE volume_id IN (OLD.volume_id, NEW.volume_id));
+    END;
+
+    -- A renamed author is renamed in every book they wrote
+    CREATE TRIGGER IF NOT EXISTS books_fts_author_names_update
+    AFTER UPDATE OF name ON authors BEGIN
+      UPDATE books_fts
+      SET authors = (SELECT GROUP_CONCAT(a.name, ' ') FROM book_authors ba
+        JOIN authors a ON a.author_id = ba.author_id
+        WHERE ba.volume_id = (SELECT volume_id FROM books WHERE rowid = books_fts.rowid))
+      WHERE rowid IN (SELECT b.rowid FROM books b
+        JOIN book_authors ba ON ba.volume_id = b.volume_id
+        WHERE ba.author_id = NEW.author_id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS books_fts_custom_fields_insert
+    AFTER INSERT ON book_custom_fields BEGIN
+      UPDATE books_fts
+      SET custom_fields = (SELECT GROUP_CONCAT(bcf.value, ' ') FROM book_custom_fields bcf
+        WHERE bcf.volume_id = NEW.volume_id)
+      WHERE rowid = (SELECT rowid FROM books WHERE volume_id = NEW.volume_id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS books_fts_custom_fields_update
+    AFTER UPDATE OF value ON book_custom_fields BEGIN
+      UPDATE books_fts
+      SET custom_fields = (SELECT GROUP_CONCAT(bcf.value, ' ') FROM book_custom_fields bcf
+        WHERE bcf.volume_id = NEW.volume_id)
+      WHERE rowid = (SELECT rowid FROM books WHERE volume_id = NEW.volume_id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS books_fts_custom_fields_delete
+    AFTER DELETE ON book_custom_fields BEGIN
+      UPDATE books_fts
+      SET custom_fields = (SELECT GROUP_CONCAT(bcf.value, ' ') FROM book_custom_fields bcf
+        WHERE bcf.volume_id = OLD.volume_id)
+      WHERE rowid = (SELECT rowid FROM books WHERE volume_id = OLD.volume_id);
+    END;
+    ",
+    kind: MigrationKind::Up,
+};
+
+pub const MIGRATION010: Migration = Migration {
+    version: 10,
+    description: "add_loans",
+    sql: "
+    CREATE TABLE IF NOT EXISTS borrowers (
+      borrower_id INTEGER PRIMARY KEY AUTOINCREMENT,
+      name TEXT NOT NULL UNIQUE
+    );
+
+    -- Dates are local YYYY-MM-DD, a loan without returned_on is still out
+    CREATE TABLE IF NOT EXISTS loans (
+      loan_id INTEGER PRIMARY KEY AUTOINCREMENT,
+      volume_id TEXT NOT NULL,
+      borrower_id INTEGER NOT NULL,
+      lent_on TEXT NOT NULL,
+      due_on TEXT,
+      returned_on TEXT,
+      notes TEXT,
+      FOREIGN KEY (volume_id) REFERENCES books(volume_id) ON DELETE CASCADE,
+      FOREIGN KEY (borrower_id) REFERENCES borrowers(borrower_id) ON DELETE RESTRICT
+    );
+
+    -- A book is out to one borrower at a time
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_loans_active ON loans(volume_id)
+    WHERE returned_on IS NULL;
+    CREATE INDEX IF NOT EXISTS idx_loans_volume ON loans(volume_id);
+    CREATE INDEX IF NOT EXISTS idx_loans_borrower ON loans(borrower_id);
+    ",
+    kind: MigrationKind::Up,
+};
+
+pub const MIGRATION011: Migration = Migration {
+    version: 11,
+    description: "add_reading_status",
+    sql: "
+    -- One row per time a book is read, re-reads add a row. The latest row is
+    -- the book's status, want_to_read rows have no dates yet
+    CREATE TABLE IF NOT EXISTS readings (
+      reading_id INTEGER PRIMARY KEY AUTOINCREMENT,
+      volume_id TEXT NOT NULL,
+      status TEXT NOT NULL CHECK (status IN ('want_to_read', 'reading', 'read', 'abandoned')),
+      started_on TEXT,
+      finished_on TEXT,
+      current_page INTEGER CHECK (current_page >= 0),
+      FOREIGN KEY (volume_id) REFERENCES books(volume_id) ON DELETE CASCADE
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_readings_volume ON readings(volume_id);
+    CREATE INDEX IF NOT EXISTS idx_readings_finished ON readings(finished_on);
+
+    -- Ratings go from 0 to 5 in half stars
+    CREATE TABLE IF NOT EXISTS book_reviews (
+      volume_id TEXT PRIMARY KEY,
+      rating REAL CHECK (rating BETWEEN 0 AND 5),
+      review TEXT,
+      FOREIGN KEY (volume_id) REFERENCES books(volume_id) ON DELETE CASCADE
+    );
+    ",
+    kind: MigrationKind::Up,
+};
+
+pub const MIGRATION012: Migration = Migration {
+    version: 12,
+    description: "add_copies",
+    sql: "
+    -- The physical copies of a book, every book has at least one. Grades are
+    -- on the scale they're written for: Fine, Very Good... for books, the
+    -- 0.5 to 10.0 CGC numbers for comics
+    CREATE TABLE IF NOT EXISTS copies (
+      copy_id INTEGER PRIMARY KEY AUTOINCREMENT,
+      volume_id TEXT NOT NULL,
+      acquired_on TEXT,
+      source TEXT,
+      price REAL CHECK (price >= 0),
+      currency TEXT,
+      grade_scale TEXT CHECK (grade_scale IN ('book', 'comic')),
+      grade TEXT,
+      signed INTEGER NOT NULL DEFAULT 0,
+      first_edition INTEGER NOT NULL DEFAULT 0,
+      notes TEXT,
+      FOREIGN KEY (volume_id) REFERENCES books(volume_id) ON DELETE CASCADE
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_copies_volume ON copies(volume_id);
+
+    INSERT INTO copies (volume_id)
+    SELECT volume_id FROM books
+    WHERE volume_id NOT IN (SELECT volume_id FROM copies);
+    ",
+    kind: MigrationKind::Up,
+};
+
+pub const MIGRATION013: Migration = Migration {
+    version: 13,
+    description: "add_locations",
+    sql: "
+    -- Rooms hold bookcases and boxes, bookcases hold shelves, shelves hold
+    -- boxes. The kinds are checked when locations are added or moved
+    CREATE TABLE IF NOT EXISTS locations (
+      location_id INTEGER PRIMARY KEY AUTOINCREMENT,
+      parent_id INTEGER,
+      name TEXT NOT NULL,
+      kind TEXT NOT NULL CHECK (kind IN ('room', 'bookcase', 'shelf', 'box')),
+      FOREIGN KEY (parent_id) REFERENCES locations(location_id)
+    );
+
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_locations_name
+      ON locations(COALESCE(parent_id, 0), name);
+
+    ALTER TABLE copies ADD COLUMN location_id INTEGER REFERENCES locations(location_id);
+
+    CREATE INDEX IF NOT EXISTS idx_copies_location ON copies(location_id);
+    ",
+    kind: MigrationKind::Up,
+};
@@ -0,0 +1,133 @@
+pub mod open_library;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::settings::AppSettings;
+
+/// Book metadata as returned by a provider, already mapped to our `books`
+/// columns but not yet saved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NormalizedBook {
+    pub volume_id: String,
+    pub title: String,
+    pub publisher: Option<String>,
+    pub published_date: Option<String>,
+    pub description: Option<String>,
+    pub page_count: Option<i64>,
+    pub print_type: Option<String>,
+    pub maturity_rating: Option<String>,
+    pub language: Option<String>,
+    pub preview_link: Option<String>,
+    pub info_link: Option<String>,
+    pub canonical_link: Option<String>,
+    pub small_thumbnail: Option<String>,
+    pub thumbnail: Option<String>,
+    pub country: Option<String>,
+    pub saleability: Option<String>,
+    pub is_ebook: Option<i64>,
+    pub viewability: Option<String>,
+    pub embeddable: Option<i64>,
+    pub public_domain: Option<i64>,
+    pub text_to_speech_permission: Option<String>,
+    pub epub_available: Option<i64>,
+    pub pdf_available: Option<i64>,
+    pub web_reader_link: Option<String>,
+    pub access_view_status: Option<String>,
+    pub quote_sharing_allowed: Option<i64>,
+    pub metadata_source: Option<String>,
+    pub authors: Vec<String>,
+    pub categories: Vec<String>,
+    pub identifiers: Vec<NormalizedIdentifier>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizedIdentifier {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub identifier: String,
+}
+
+/// A source of book metadata that `fetch_isbn` can query.
+///
+/// Providers are tried in the order they're listed in `AppConfig`, the first
+/// one that knows the ISBN wins.
+#[async_trait]
+pub trait MetadataProvider: Send + Sync {
+    /// Stable name stored in `books.metadata_source` for books this provider found.
+    fn name(&self) -> &'static str;
+
+    // Returns None if the provider has no results for the ISBN
+    async fn fetch_by_isbn(
+        &self,
+        isbn: &str,
+        settings: &AppSettings,
+    ) -> anyhow::Result<Option<NormalizedBook>>;
+
+    /// Every match the provider has for the ISBN, best guess first.
+    ///
+    /// Providers that only ever return a single match can rely on the default.
+    async fn fetch_candidates_by_isbn(
+        &self,
+        isbn: &str,
+        settings: &AppSettings,
+    ) -> anyhow::Result<Vec<NormalizedBook>> {
+        Ok(self
+            .fetch_by_isbn(isbn, settings)
+            .await?
+            .into_iter()
+            .collect())
+    }
+}
+
+pub async fn download_thumbnail(
+    http: &Client,
+    volume_id: &str,
+    url: &str,
+    app_handle: &tauri::AppHandle,
+) -> anyhow::Result<()> {
+    let img_data = http.get(url).send().await?.bytes().await?;
+
+    let app_data_dir = app_handle.path().app_data_dir()?;
+    let books_dir = app_data_dir.join("books");
+
+    std::fs::create_dir_all(&books_dir)?;
+
+    let file_path = books_dir.join(format!("{}.jpg", volume_id));
+    std::fs::write(&file_path, img_data)?;
+
+    Ok(())
+}
+
+/// Answers the first request on a local port with `body` after `delay`, for
+/// testing providers. Returns the server's URL and the request head it got.
+#[cfg(test)]
+pub(crate) fn mock_server(
+    body: &'static str,
+    delay: std::time::Duration,
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    use std::io::{BufRead, BufReader, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let head: Vec<String> = BufReader::new(&stream)
+            .lines()
+            .map(Result::unwrap)
+            .take_while(|line| !line.is_empty())
+            .collect();
+        std::thread::sleep(delay);
+        // The client may have given up already
+        let _ = write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        head
+    });
+    (base_url, server)
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::providers::{MetadataProvider, NormalizedBook, NormalizedIdentifier};
+use crate::settings::AppSettings;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct OpenLibraryBook {
+    // e.g. "/books/OL7353617M"
+    key: String,
+    title: String,
+    url: Option<String>,
+    authors: Option<Vec<NamedEntry>>,
+    publishers: Option<Vec<NamedEntry>>,
+    publish_date: Option<String>,
+    number_of_pages: Option<i64>,
+    subjects: Option<Vec<NamedEntry>>,
+    identifiers: Option<Identifiers>,
+    cover: Option<Cover>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedEntry {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identifiers {
+    isbn_10: Option<Vec<String>>,
+    isbn_13: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Cover {
+    small: Option<String>,
+    medium: Option<String>,
+    large: Option<String>,
+}
+
+pub const DEFAULT_BASE_URL: &str = "https://openlibrary.org/api/books";
+// Shorter than Google's, a stalled lookup here holds up the whole fallback chain
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct OpenLibraryClient {
+    http: Client,
+    base_url: String,
+    timeout: Duration,
+    user_agent: Option<String>,
+}
+
+pub struct OpenLibraryClientBuilder {
+    http: Client,
+    base_url: String,
+    timeout: Duration,
+    user_agent: Option<String>,
+}
+
+impl OpenLibraryClientBuilder {
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    pub fn build(self) -> OpenLibraryClient {
+        OpenLibraryClient {
+            http: self.http,
+            base_url: self.base_url,
+            timeout: self.timeout,
+            user_agent: self.user_agent,
+        }
+    }
+}
+
+impl OpenLibraryClient {
+    pub fn builder() -> OpenLibraryClientBuilder {
+        OpenLibraryClientBuilder {
+            http: Client::new(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: None,
+        }
+    }
+
+    // Like the Google Books client, settings are layered on each lookup
+    fn with_settings(&self, settings: &AppSettings) -> Self {
+        let mut builder = OpenLibraryClientBuilder {
+            http: self.http.clone(),
+            base_url: self.base_url.clone(),
+            timeout: self.timeout,
+            user_agent: self.user_agent.clone(),
+        };
+        if let Some(base_url) = &settings.open_library_base_url {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(secs) = settings.open_library_timeout_secs {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+        builder.build()
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for OpenLibraryClient {
+    fn name(&self) -> &'static str {
+        "open_library"
+    }
+
+    async fn fetch_by_isbn(
+        &self,
+        isbn: &str,
+        settings: &AppSettings,
+    ) -> anyhow::Result<Option<NormalizedBook>> {
+        let client = self.with_settings(settings);
+        let mut req = client
+            .http
+            .get(&client.base_url)
+            .timeout(client.timeout)
+            .query(&[
+                ("bibkeys", format!("ISBN:{isbn}")),
+                ("format", "json".to_string()),
+                ("jscmd", "data".to_string()),
+            ]);
+        if let Some(user_agent) = &client.user_agent {
+            req = req.header(USER_AGENT, user_agent);
+        }
+
+        // The response is an object keyed by the requested bibkey
+        let mut resp = req
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<HashMap<String, OpenLibraryBook>>()
+            .await?;
+
+        Ok(resp
+            .remove(&format!("ISBN:{isbn}"))
+            .map(|b| NormalizedBook {
+                metadata_source: Some(self.name().to_string()),
+                ..normalize_book(b)
+            }))
+    }
+}
+
+fn normalize_book(b: OpenLibraryBook) -> NormalizedBook {
+    // Open Library keys look like "/books/OL7353617M", the last segment is
+    // unique enough to be used as our volume_id.
+    let volume_id = b.key.rsplit('/').next().unwrap_or(&b.key).to_string();
+
+    // Use the same identifier types Google Books reports
+    let mut identifiers = Vec::new();
+    if let Some(ids) = b.identifiers {
+        let isbn_13 = ids.isbn_13.into_iter().flatten().map(|i| ("ISBN_13", i));
+        let isbn_10 = ids.isbn_10.into_iter().flatten().map(|i| ("ISBN_10", i));
+        for (type_, identifier) in isbn_13.chain(isbn_10) {
+            identifiers.push(NormalizedIdentifier {
+                type_: type_.to_string(),
+                identifier,
+            });
+        }
+    }
+
+    let names = |entries: Option<Vec<NamedEntry>>| -> Vec<String> {
+        entries
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| e.name)
+            .collect()
+    };
+
+    NormalizedBook {
+        volume_id,
+        title: b.title,
+        publisher: names(b.publishers).into_iter().next(),
+        published_date: b.publish_date,
+        page_count: b.number_of_pages,
+        info_link: b.url,
+        small_thumbnail: b.cover.as_ref().and_then(|c| c.small.clone()),
+        thumbnail: b.cover.and_then(|c| c.medium),
+        authors: names(b.authors),
+        // Subjects are the closest thing Open Library has to categories
+        categories: names(b.subjects),
+        identifiers,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock_server;
+
+    // Trimmed from an api/books?bibkeys=ISBN:9780261102217&jscmd=data response
+    const BOOKS: &str = r#"{
+      "ISBN:9780261102217": {
+        "url": "https://openlibrary.org/books/OL7353617M/The_Hobbit",
+        "key": "/books/OL7353617M",
+        "title": "The Hobbit",
+        "authors": [
+          {"url": "https://openlibrary.org/authors/OL26320A", "name": "J.R.R. Tolkien"}
+        ],
+        "number_of_pages": 310,
+        "identifiers": {
+          "isbn_10": ["0261102214"],
+          "isbn_13": ["9780261102217"],
+          "openlibrary": ["OL7353617M"]
+        },
+        "publishers": [{"name": "HarperCollins"}, {"name": "Allen & Unwin"}],
+        "publish_date": "1999",
+        "subjects": [
+          {"name": "Fantasy fiction", "url": "https://openlibrary.org/subjects/fantasy_fiction"},
+          {"name": "Middle Earth", "url": "https://openlibrary.org/subjects/middle_earth"}
+        ],
+        "cover": {
+          "small": "https://covers.openlibrary.org/b/id/1-S.jpg",
+          "medium": "https://covers.openlibrary.org/b/id/1-M.jpg",
+          "large": "https://covers.openlibrary.org/b/id/1-L.jpg"
+        }
+      }
+    }"#;
+
+    #[test]
+    fn normalizes_book() {
+        let mut resp: HashMap<String, OpenLibraryBook> = serde_json::from_str(BOOKS).unwrap();
+        let book = normalize_book(resp.remove("ISBN:9780261102217").unwrap());
+
+        assert_eq!(book.volume_id, "OL7353617M");
+        assert_eq!(book.title, "The Hobbit");
+        assert_eq!(book.authors, vec!["J.R.R. Tolkien"]);
+        // The first publisher only
+        assert_eq!(book.publisher.as_deref(), Some("HarperCollins"));
+        assert_eq!(book.published_date.as_deref(), Some("1999"));
+        assert_eq!(book.page_count, Some(310));
+        assert_eq!(
+            book.info_link.as_deref(),
+            Some("https://openlibrary.org/books/OL7353617M/The_Hobbit")
+        );
+        assert_eq!(book.categories, vec!["Fantasy fiction", "Middle Earth"]);
+        assert_eq!(
+            book.small_thumbnail.as_deref(),
+            Some("https://covers.openlibrary.org/b/id/1-S.jpg")
+        );
+        assert_eq!(
+            book.thumbnail.as_deref(),
+            Some("https://covers.openlibrary.org/b/id/1-M.jpg")
+        );
+        // ISBN-13s first, other identifier kinds left out
+        let identifiers: Vec<(&str, &str)> = book
+            .identifiers
+            .iter()
+            .map(|i| (i.type_.as_str(), i.identifier.as_str()))
+            .collect();
+        assert_eq!(
+            identifiers,
+            vec![("ISBN_13", "9780261102217"), ("ISBN_10", "0261102214")]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_from_the_configured_base_url() {
+        let (base_url, server) = mock_server(BOOKS, Duration::ZERO);
+        let client = OpenLibraryClient::builder()
+            .base_url(format!("{base_url}/api/books"))
+            .user_agent("isbn-scanner-test")
+            .build();
+
+        let book = client
+            .fetch_by_isbn("9780261102217", &AppSettings::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(book.volume_id, "OL7353617M");
+        assert_eq!(book.metadata_source.as_deref(), Some("open_library"));
+
+        let head = server.join().unwrap();
+        assert_eq!(
+            head[0],
+            "GET /api/books?bibkeys=ISBN%3A9780261102217&format=json&jscmd=data HTTP/1.1"
+        );
+        assert!(head
+            .iter()
+            .any(|line| line.eq_ignore_ascii_case("user-agent: isbn-scanner-test")));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_timeout() {
+        let (base_url, server) = mock_server(BOOKS, Duration::from_secs(1));
+        let client = OpenLibraryClient::builder()
+            .base_url(format!("{base_url}/api/books"))
+            .timeout(Duration::from_millis(100))
+            .build();
+
+        let err = client
+            .fetch_by_isbn("9780261102217", &AppSettings::default())
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<reqwest::Error>().unwrap().is_timeout());
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn settings_override_the_endpoint_and_timeout() {
+        let (base_url, server) = mock_server(BOOKS, Duration::from_secs(2));
+        let settings = AppSettings {
+            open_library_base_url: Some(format!("{base_url}/api/books")),
+            open_library_timeout_secs: Some(1),
+            ..Default::default()
+        };
+
+        let err = OpenLibraryClient::builder()
+            .build()
+            .fetch_by_isbn("9780261102217", &settings)
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<reqwest::Error>().unwrap().is_timeout());
+        assert!(server.join().unwrap()[0].starts_with("GET /api/books?"));
+    }
+}
@@ -11,6 +11,25 @@ pub struct AppSettings {
     pub success_sound_enable: Option<bool>,
     #[serde(rename = "errorSoundEnable")]
     pub error_sound_enable: Option<bool>,
+    // Overrides for the Google Books endpoint, mostly useful to point the app
+    // at a local mock server.
+    #[serde(rename = "googleBooksBaseUrl")]
+    pub google_books_base_url: Option<String>,
+    #[serde(rename = "googleBooksTimeoutSecs")]
+    pub google_books_timeout_secs: Option<u64>,
+    #[serde(rename = "googleBooksUserAgent")]
+    pub google_books_user_agent: Option<String>,
+    // Same for Open Library
+    #[serde(rename = "openLibraryBaseUrl")]
+    pub open_library_base_url: Option<String>,
+    #[serde(rename = "openLibraryTimeoutSecs")]
+    pub open_library_timeout_secs: Option<u64>,
+    // Number of automatic database backups kept, 0 disables them
+    #[serde(rename = "backupRetention")]
+    pub backup_retention: Option<u32>,
+    // A backup is also taken after this many writes, 0 only backs up on startup
+    #[serde(rename = "backupEveryWrites")]
+    pub backup_every_writes: Option<u32>,
 }
 
 const STORE_PATH: &str = ".settings.json";
@@ -19,11 +38,7 @@ const SETTINGS_KEY: &str = "settings";
 pub fn load_settings(app: &AppHandle) -> Result<AppSettings, String> {
     let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
 
-    // get returns Option<&serde_json::Value>; clone the inner Value
-    let value = store
-        .get(SETTINGS_KEY)
-        .map(|v| v.clone())
-        .unwrap_or_else(|| json!({}));
+    let value = store.get(SETTINGS_KEY).unwrap_or_else(|| json!({}));
 
     serde_json::from_value(value).map_err(|e| e.to_string())
 }
@@ -34,3 +49,15 @@ pub fn save_settings(app: &AppHandle, next: &AppSettings) -> Result<(), String>
     store.set(SETTINGS_KEY, value);
     store.save().map_err(|e| e.to_string())
 }
+
+/// The settings exactly as stored, including the keys only the frontend knows.
+pub fn load_settings_value(app: &AppHandle) -> Result<serde_json::Value, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    Ok(store.get(SETTINGS_KEY).unwrap_or_else(|| json!({})))
+}
+
+pub fn save_settings_value(app: &AppHandle, value: serde_json::Value) -> Result<(), String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    store.set(SETTINGS_KEY, value);
+    store.save().map_err(|e| e.to_string())
+}
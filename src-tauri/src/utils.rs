@@ -1,36 +1,25 @@
-fn is_ean13(identifier: &str) -> bool {
-    let digits: String = identifier.chars().filter(|c| c.is_numeric()).collect();
-    if digits.len() != 13 {
-        return false;
-    }
+pub mod barcode;
+pub mod isbn;
 
-    let mut sum = 0;
-    for (i, ch) in digits[..12].chars().enumerate() {
-        if let Ok(d) = ch.to_string().parse::<u32>() {
-            sum += d * if i % 2 == 0 { 1 } else { 3 };
-        }
-    }
+/// Validates a scanned or typed identifier and returns its type together
+/// with the normalized form that should be stored.
+pub fn normalize_identifier(identifier: &str) -> anyhow::Result<(&'static str, String)> {
+    let normalized = isbn::normalize(identifier);
 
-    let check_digit = (10 - (sum % 10)) % 10;
-    if let Ok(d) = digits.chars().nth(12).unwrap().to_string().parse::<u32>() {
-        d == check_digit
-    } else {
-        false
+    match normalized.len() {
+        10 if isbn::is_valid_isbn10(&normalized) => Ok(("ISBN_10", normalized)),
+        13 if isbn::is_valid_isbn13(&normalized) => Ok(("ISBN_13", normalized)),
+        13 if isbn::is_valid_ean13(&normalized) => Ok(("EAN_13", normalized)),
+        10 | 13 => anyhow::bail!("Invalid checksum for identifier {identifier}"),
+        _ => anyhow::bail!("Invalid identifier length"),
     }
 }
 
-pub fn get_identifier_type(identifier: &str) -> anyhow::Result<&'static str> {
-    let digits: String = identifier.chars().filter(|c| c.is_numeric()).collect();
-
-    match digits.len() {
-        10 => Ok("ISBN_10"),
-        13 => {
-            if is_ean13(&digits) {
-                Ok("EAN_13")
-            } else {
-                Ok("ISBN_13")
-            }
-        }
-        _ => anyhow::bail!("Invalid identifier length"),
+/// The form identifiers are matched on, so the ISBN-10 and ISBN-13 of the same
+/// book compare equal. Other valid EAN-13s are their own canonical form.
+pub fn canonical_isbn13(identifier: &str) -> Option<String> {
+    match normalize_identifier(identifier).ok()? {
+        ("ISBN_10", isbn) => isbn::isbn10_to_isbn13(&isbn),
+        (_, ean) => Some(ean),
     }
 }
@@ -0,0 +1,93 @@
+//! Scanner input made of an EAN-13 optionally followed by an EAN-2 or EAN-5
+//! supplement, as printed on periodicals and many comics.
+//!
+//! Scanners configured to read supplements send them right after the main
+//! code, so we get 15 or 18 digits in a single string.
+
+use crate::utils::isbn;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Barcode {
+    pub ean: String,
+    pub add_on: Option<String>,
+}
+
+pub fn parse(input: &str) -> anyhow::Result<Barcode> {
+    let digits = isbn::normalize(input);
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        anyhow::bail!("Barcode must only contain digits");
+    }
+
+    let (ean, add_on) = match digits.len() {
+        13 => (digits, None),
+        15 | 18 => {
+            let (ean, add_on) = digits.split_at(13);
+            (ean.to_string(), Some(add_on.to_string()))
+        }
+        n => anyhow::bail!("Unsupported barcode length {n}"),
+    };
+
+    if !isbn::is_valid_ean13(&ean) {
+        anyhow::bail!("Invalid EAN-13 checksum for {ean}");
+    }
+
+    Ok(Barcode { ean, add_on })
+}
+
+impl Barcode {
+    /// The issue number carried by the supplement.
+    ///
+    /// An EAN-5 on a periodical is the issue in its first three digits, then
+    /// the cover variant and the printing, so `00111` is issue 1. An EAN-2 is
+    /// the issue as a whole. On books (978/979 codes) an EAN-5 is the
+    /// suggested retail price rather than an issue number, so it's ignored.
+    pub fn issue_number(&self) -> Option<i64> {
+        let add_on = self.add_on.as_deref()?;
+        let issue = match add_on.len() {
+            5 if isbn::has_isbn_prefix(&self.ean) => return None,
+            5 => &add_on[..3],
+            _ => add_on,
+        };
+        issue.parse().ok().filter(|n| *n > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(input: &str) -> Option<i64> {
+        parse(input).unwrap().issue_number()
+    }
+
+    #[test]
+    fn ean2_is_the_issue() {
+        assert_eq!(issue("076194133450912"), Some(12));
+        assert_eq!(issue("076194133450907"), Some(7));
+    }
+
+    #[test]
+    fn ean5_issue_leaves_out_cover_and_printing() {
+        assert_eq!(issue("076194133450900111"), Some(1));
+        assert_eq!(issue("076194133450900121"), Some(1));
+        assert_eq!(issue("076194133450912312"), Some(123));
+    }
+
+    #[test]
+    fn book_price_add_on_is_no_issue() {
+        assert_eq!(issue("978026110221751299"), None);
+        assert_eq!(issue("979123456789690000"), None);
+    }
+
+    #[test]
+    fn zero_issue_is_none() {
+        assert_eq!(issue("076194133450900000"), None);
+        assert_eq!(issue("0761941334509"), None);
+    }
+
+    #[test]
+    fn rejects_bad_barcodes() {
+        assert!(parse("076194133450812").is_err());
+        assert!(parse("0761941334509123").is_err());
+    }
+}
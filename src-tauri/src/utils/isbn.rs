@@ -0,0 +1,418 @@
+//! ISBN validation, ISBN-10/13 conversion and hyphenation.
+//!
+//! Every function accepts input with or without hyphens and spaces, so
+//! "978-88-04-66829-9", "978 88 04 66829 9" and "9788804668299" are all the same.
+
+use serde::Serialize;
+
+/// A registration group or registrant range, taken from the International
+/// ISBN Agency range table (RangeMessage.xml).
+///
+/// `start` and `end` are compared against the 7 digits that follow the
+/// previous element, right-padded with zeros, `length` is how many of those
+/// digits belong to the element. A length of 0 means the range is unassigned.
+struct Range {
+    start: u32,
+    end: u32,
+    length: usize,
+}
+
+const fn range(start: u32, end: u32, length: usize) -> Range {
+    Range { start, end, length }
+}
+
+// Registration groups for each EAN prefix.
+const GROUPS: &[(&str, &[Range])] = &[
+    (
+        "978",
+        &[
+            range(0, 5999999, 1),
+            range(6000000, 6499999, 3),
+            range(6500000, 6599999, 2),
+            range(6600000, 6999999, 0),
+            range(7000000, 7999999, 1),
+            range(8000000, 9499999, 2),
+            range(9500000, 9899999, 3),
+            range(9900000, 9989999, 4),
+            range(9990000, 9999999, 5),
+        ],
+    ),
+    (
+        "979",
+        &[
+            range(0, 999999, 0),
+            range(1000000, 1299999, 2),
+            range(1300000, 7999999, 0),
+            range(8000000, 8099999, 1),
+            range(8100000, 9999999, 0),
+        ],
+    ),
+];
+
+// Registrant ranges, this is the subset of the range table covering the
+// groups that make up most of our catalog. Books from other groups are still
+// valid ISBNs, they just can't be hyphenated.
+const REGISTRANTS: &[(&str, &str, &[Range])] = &[
+    // English language
+    (
+        "978",
+        "0",
+        &[
+            range(0, 1999999, 2),
+            range(2000000, 6999999, 3),
+            range(7000000, 8499999, 4),
+            range(8500000, 8999999, 5),
+            range(9000000, 9499999, 6),
+            range(9500000, 9999999, 7),
+        ],
+    ),
+    (
+        "978",
+        "1",
+        &[
+            range(0, 999999, 2),
+            range(1000000, 3999999, 3),
+            range(4000000, 5499999, 4),
+            range(5500000, 8697999, 5),
+            range(8698000, 9989999, 6),
+            range(9990000, 9999999, 7),
+        ],
+    ),
+    // German language
+    (
+        "978",
+        "3",
+        &[
+            range(0, 299999, 2),
+            range(300000, 333999, 3),
+            range(334000, 369999, 4),
+            range(370000, 399999, 5),
+            range(400000, 1999999, 2),
+            range(2000000, 6999999, 3),
+            range(7000000, 8499999, 4),
+            range(8500000, 8999999, 5),
+            range(9000000, 9499999, 6),
+            range(9500000, 9539999, 7),
+            range(9540000, 9699999, 5),
+            range(9700000, 9849999, 7),
+            range(9850000, 9999999, 5),
+        ],
+    ),
+    // Japan
+    (
+        "978",
+        "4",
+        &[
+            range(0, 1999999, 2),
+            range(2000000, 6999999, 3),
+            range(7000000, 8499999, 4),
+            range(8500000, 8999999, 5),
+            range(9000000, 9499999, 6),
+            range(9500000, 9999999, 7),
+        ],
+    ),
+    // China
+    (
+        "978",
+        "7",
+        &[
+            range(0, 999999, 2),
+            range(1000000, 4999999, 3),
+            range(5000000, 7999999, 4),
+            range(8000000, 8999999, 5),
+            range(9000000, 9999999, 6),
+        ],
+    ),
+    // Italy
+    (
+        "978",
+        "88",
+        &[
+            range(0, 1999999, 2),
+            range(2000000, 3119999, 3),
+            range(3120000, 3149999, 5),
+            range(3150000, 3189999, 3),
+            range(3190000, 3229999, 5),
+            range(3230000, 3269999, 3),
+            range(3270000, 3389999, 4),
+            range(3390000, 3609999, 3),
+            range(3610000, 3629999, 4),
+            range(3630000, 5489999, 3),
+            range(5490000, 5549999, 4),
+            range(5550000, 5999999, 3),
+            range(6000000, 8499999, 4),
+            range(8500000, 8999999, 5),
+            range(9000000, 9099999, 6),
+            range(9100000, 9269999, 3),
+            range(9270000, 9399999, 4),
+            range(9400000, 9479999, 6),
+            range(9480000, 9999999, 5),
+        ],
+    ),
+];
+
+/// The elements of a hyphenated ISBN-13, formatting it gives the hyphenated
+/// form, e.g. "978-88-04-66829-9".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IsbnParts {
+    pub prefix: String,
+    pub group: String,
+    pub registrant: String,
+    pub publication: String,
+    pub check_digit: char,
+}
+
+impl std::fmt::Display for IsbnParts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}-{}-{}",
+            self.prefix, self.group, self.registrant, self.publication, self.check_digit
+        )
+    }
+}
+
+/// Strips hyphens and whitespace and uppercases the ISBN-10 "x" check digit.
+pub fn normalize(input: &str) -> String {
+    input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn digit(c: char) -> Option<u32> {
+    c.to_digit(10)
+}
+
+fn isbn10_check_digit(first_nine: &str) -> Option<char> {
+    let mut sum = 0;
+    for (i, c) in first_nine.chars().enumerate() {
+        sum += digit(c)? * (10 - i as u32);
+    }
+    match (11 - sum % 11) % 11 {
+        10 => Some('X'),
+        d => char::from_digit(d, 10),
+    }
+}
+
+fn ean13_check_digit(first_twelve: &str) -> Option<char> {
+    let mut sum = 0;
+    for (i, c) in first_twelve.chars().enumerate() {
+        sum += digit(c)? * if i % 2 == 0 { 1 } else { 3 };
+    }
+    char::from_digit((10 - sum % 10) % 10, 10)
+}
+
+pub fn is_valid_isbn10(input: &str) -> bool {
+    let isbn = normalize(input);
+    if isbn.len() != 10 || !isbn.is_ascii() {
+        return false;
+    }
+    isbn10_check_digit(&isbn[..9]) == isbn.chars().last()
+}
+
+/// Checks the EAN-13 checksum, this doesn't care whether the code is an ISBN.
+pub fn is_valid_ean13(input: &str) -> bool {
+    let ean = normalize(input);
+    if ean.len() != 13 || !ean.is_ascii() {
+        return false;
+    }
+    ean13_check_digit(&ean[..12]) == ean.chars().last()
+}
+
+/// Whether the code has one of the "Bookland" prefixes reserved for ISBNs.
+pub fn has_isbn_prefix(input: &str) -> bool {
+    let isbn = normalize(input);
+    isbn.starts_with("978") || isbn.starts_with("979")
+}
+
+pub fn is_valid_isbn13(input: &str) -> bool {
+    has_isbn_prefix(input) && is_valid_ean13(input)
+}
+
+pub fn isbn10_to_isbn13(input: &str) -> Option<String> {
+    let isbn = normalize(input);
+    if !is_valid_isbn10(&isbn) {
+        return None;
+    }
+    let first_twelve = format!("978{}", &isbn[..9]);
+    let check = ean13_check_digit(&first_twelve)?;
+    Some(format!("{first_twelve}{check}"))
+}
+
+/// Only 978 ISBNs have an ISBN-10 equivalent, 979 ones return None.
+pub fn isbn13_to_isbn10(input: &str) -> Option<String> {
+    let isbn = normalize(input);
+    if !is_valid_isbn13(&isbn) || !isbn.starts_with("978") {
+        return None;
+    }
+    let first_nine = &isbn[3..12];
+    let check = isbn10_check_digit(first_nine)?;
+    Some(format!("{first_nine}{check}"))
+}
+
+/// Returns the ISBN-13 form of a valid ISBN-10 or ISBN-13, None otherwise.
+pub fn to_isbn13(input: &str) -> Option<String> {
+    let isbn = normalize(input);
+    match isbn.len() {
+        10 => isbn10_to_isbn13(&isbn),
+        13 if is_valid_isbn13(&isbn) => Some(isbn),
+        _ => None,
+    }
+}
+
+fn find_length(ranges: &[Range], digits: &str) -> Option<usize> {
+    // Ranges are expressed on 7 digits, shorter tails are right-padded
+    let padded = format!("{:0<7}", &digits[..digits.len().min(7)]);
+    let value: u32 = padded.parse().ok()?;
+    ranges
+        .iter()
+        .find(|r| r.start <= value && value <= r.end)
+        .map(|r| r.length)
+        .filter(|&length| length > 0)
+}
+
+/// Splits a valid ISBN into prefix, registration group, registrant and
+/// publication using the range table.
+///
+/// Returns None for invalid ISBNs and for groups we don't have registrant
+/// ranges for.
+pub fn parts(input: &str) -> Option<IsbnParts> {
+    let isbn = to_isbn13(input)?;
+    let (prefix, rest) = isbn.split_at(3);
+    let body = &rest[..9];
+    let check_digit = rest.chars().last()?;
+
+    let (_, group_ranges) = GROUPS.iter().find(|(p, _)| *p == prefix)?;
+    let group_len = find_length(group_ranges, body)?;
+    let (group, after_group) = body.split_at(group_len);
+
+    let (_, _, registrant_ranges) = REGISTRANTS
+        .iter()
+        .find(|(p, g, _)| *p == prefix && *g == group)?;
+    let registrant_len = find_length(registrant_ranges, after_group)?;
+    if registrant_len >= after_group.len() {
+        return None;
+    }
+    let (registrant, publication) = after_group.split_at(registrant_len);
+
+    Some(IsbnParts {
+        prefix: prefix.to_string(),
+        group: group.to_string(),
+        registrant: registrant.to_string(),
+        publication: publication.to_string(),
+        check_digit,
+    })
+}
+
+/// Everything we can tell about an ISBN, for display.
+#[derive(Debug, Serialize)]
+pub struct IsbnDetails {
+    pub isbn_13: String,
+    pub isbn_10: Option<String>,
+    pub hyphenated: Option<String>,
+    pub parts: Option<IsbnParts>,
+}
+
+pub fn details(input: &str) -> Option<IsbnDetails> {
+    let isbn_13 = to_isbn13(input)?;
+    let parts = parts(&isbn_13);
+    Some(IsbnDetails {
+        isbn_10: isbn13_to_isbn10(&isbn_13),
+        hyphenated: parts.as_ref().map(|p| p.to_string()),
+        parts,
+        isbn_13,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_isbn10() {
+        assert!(is_valid_isbn10("0-261-10221-4"));
+        assert!(is_valid_isbn10("0 261 10221 4"));
+        assert!(!is_valid_isbn10("0-261-10221-5"));
+        assert!(!is_valid_isbn10("026110221"));
+        assert!(!is_valid_isbn10("02611022A4"));
+    }
+
+    #[test]
+    fn validates_x_check_digit() {
+        assert!(is_valid_isbn10("0-8044-2957-X"));
+        assert!(is_valid_isbn10("080442957x"));
+        assert!(!is_valid_isbn10("0804429570"));
+        assert_eq!(
+            isbn10_to_isbn13("080442957X").as_deref(),
+            Some("9780804429573")
+        );
+        assert_eq!(
+            isbn13_to_isbn10("9780804429573").as_deref(),
+            Some("080442957X")
+        );
+    }
+
+    #[test]
+    fn validates_isbn13() {
+        assert!(is_valid_isbn13("978-88-04-66829-9"));
+        assert!(is_valid_isbn13("9791032305690"));
+        assert!(!is_valid_isbn13("978-88-04-66829-5"));
+        // A valid EAN-13 that isn't an ISBN
+        assert!(is_valid_ean13("9771121580009"));
+        assert!(!is_valid_isbn13("9771121580009"));
+    }
+
+    #[test]
+    fn converts_between_10_and_13() {
+        assert_eq!(
+            isbn10_to_isbn13("0261102214").as_deref(),
+            Some("9780261102217")
+        );
+        assert_eq!(
+            isbn13_to_isbn10("9780261102217").as_deref(),
+            Some("0261102214")
+        );
+        assert_eq!(isbn10_to_isbn13("0261102215"), None);
+        assert_eq!(to_isbn13("0-261-10221-4").as_deref(), Some("9780261102217"));
+    }
+
+    #[test]
+    fn isbn979_has_no_isbn10() {
+        assert_eq!(isbn13_to_isbn10("9791032305690"), None);
+    }
+
+    fn hyphenated(isbn: &str) -> Option<String> {
+        parts(isbn).map(|p| p.to_string())
+    }
+
+    #[test]
+    fn hyphenates_every_group_in_the_table() {
+        let cases = [
+            ("9780261102217", "978-0-261-10221-7"),
+            ("9781402894626", "978-1-4028-9462-6"),
+            ("9783161484100", "978-3-16-148410-0"),
+            ("9784061234567", "978-4-06-123456-7"),
+            ("9787020024964", "978-7-02-002496-4"),
+            ("9788804668299", "978-88-04-66829-9"),
+        ];
+        for (isbn, expected) in cases {
+            assert_eq!(hyphenated(isbn).as_deref(), Some(expected), "{isbn}");
+        }
+        // ISBN-10s are hyphenated as their ISBN-13
+        assert_eq!(
+            hyphenated("0261102214").as_deref(),
+            Some("978-0-261-10221-7")
+        );
+    }
+
+    #[test]
+    fn groups_without_registrants_are_not_hyphenated() {
+        assert_eq!(hyphenated("9791032305690"), None);
+        assert_eq!(hyphenated("9788804668295"), None);
+        let details = details("9791032305690").unwrap();
+        assert_eq!(details.isbn_10, None);
+        assert_eq!(details.hyphenated, None);
+    }
+}
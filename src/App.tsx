@@ -16,6 +16,14 @@ import {
   LibraryBig,
   X,
   Layers,
+  Archive,
+  ArchiveRestore,
+  History,
+  FolderInput,
+  FileCode,
+  Quote,
+  Handshake,
+  MapPin,
 } from "lucide-react";
 import { SettingsDialog } from "./components/SettingsDialog";
 import { Toaster, toast } from "sonner";
@@ -25,9 +33,22 @@ import { DetailsDialog } from "./components/DetailsDialog";
 import { cn, getColorForGroup, isISBN, isEAN13, isOnlyDigits } from "./utils";
 import { loadSettings } from "./lib/store";
 import { BookNumberDialog } from "./components/BookNumberDialog";
+import { ImportReportDialog } from "./components/ImportReportDialog";
+import { BackupsDialog } from "./components/BackupsDialog";
+import { ExportDialog } from "./components/ExportDialog";
+import { LendDialog } from "./components/LendDialog";
+import { LoansDialog } from "./components/LoansDialog";
+import { LocationsDialog } from "./components/LocationsDialog";
 import { emit, listen } from "@tauri-apps/api/event";
 import { readFile } from "@tauri-apps/plugin-fs";
-import { Book } from "./types";
+import { ask } from "@tauri-apps/plugin-dialog";
+import {
+  Book,
+  ComicInfoImport,
+  ImportMode,
+  ImportReport,
+  Loan,
+} from "./types";
 import { GroupingDialog } from "./components/GroupingDialog";
 import { GroupDetailsDialog } from "./components/GroupDetailsDialog";
 import { SearchBox } from "./components/SearchBox";
@@ -57,6 +78,15 @@ function App() {
   const [addOpenWithIdentifier, setAddOpenWithIdentifier] = useState("");
   const [addOpenWithSeries, setAddOpenWithSeries] = useState("");
   const [settingsOpen, setSettingsOpen] = useState(false);
+  const [backupsOpen, setBackupsOpen] = useState(false);
+  const [citationsOpen, setCitationsOpen] = useState(false);
+  const [loansOpen, setLoansOpen] = useState(false);
+  const [locationsOpen, setLocationsOpen] = useState(false);
+  const [stocktaking, setStocktaking] = useState(false);
+  const [bookToLend, setBookToLend] = useState<Book | undefined>(undefined);
+  const [importReport, setImportReport] = useState<ImportReport | undefined>(
+    undefined,
+  );
   const [theme, setTheme] = useState<Theme>(() => {
     const saved = localStorage.getItem("theme");
     return (saved as Theme) || "system";
@@ -203,6 +233,15 @@ function App() {
     };
   }, []);
 
+  useEffect(() => {
+    const unlistener = listen<string>("backup-failed", (event) => {
+      toast.error(`Automatic backup failed: ${event.payload}`);
+    });
+    return () => {
+      unlistener.then((f) => f());
+    };
+  }, []);
+
   const applyTheme = (t: Theme) => {
     const html = document.documentElement;
     if (t === "system") {
@@ -253,6 +292,27 @@ function App() {
     toast.success(`Added ${book.title}`);
   };
 
+  const handleOwnedISBN = (book: Book) => {
+    toast.info(`${book.title} is already in the library`, {
+      action: {
+        label: "Add another copy",
+        onClick: async () => {
+          try {
+            await invoke("add_copy", {
+              volumeId: book.volume_id,
+              details: { signed: false, first_edition: false },
+            });
+            toast.success(
+              `You have ${book.copies.length + 1} copies of ${book.title}`,
+            );
+          } catch (err) {
+            toast.error(err instanceof Error ? err.message : String(err));
+          }
+        },
+      },
+    });
+  };
+
   const handleNewEAN = async (ean: string) => {
     setAddOpenWithIdentifier(ean);
     setAddOpen(true);
@@ -312,10 +372,13 @@ function App() {
 
     if (isISBNFormat) {
       try {
-        const exists = await invoke<boolean>("isbn_exists", {
+        const owned = await invoke<Book | null>("find_book_by_isbn", {
           isbn: text,
         });
-        if (exists) return;
+        if (owned) {
+          handleOwnedISBN(owned);
+          return;
+        }
 
         const book = await invoke<Book>("fetch_isbn", {
           isbn: text,
@@ -337,6 +400,20 @@ function App() {
       } else {
         await handleNewEAN(text);
       }
+    } else if (digits.length === 15 || digits.length === 18) {
+      // EAN-13 followed by an EAN-2/EAN-5 supplement with the issue number
+      try {
+        const book = await invoke<Book | null>("add_comic_issue_by_barcode", {
+          barcode: text,
+        });
+        if (book) {
+          await handleBookSaved(book);
+        } else {
+          await handleNewEAN(digits.slice(0, 13));
+        }
+      } catch (error) {
+        handleClipboardError(text, String(error));
+      }
     } else if (isOnlyDigits(text)) {
       toast.error("Unknown barcode format");
     }
@@ -409,7 +486,113 @@ function App() {
 
   const openImportDialog = async () => {
     try {
-      const message = await invoke<string>("import_books_csv");
+      const report = await invoke<ImportReport>("import_books_csv", {
+        dryRun: true,
+      });
+      setImportReport(report);
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const openCalibreImportDialog = async () => {
+    try {
+      const report = await invoke<ImportReport>("import_calibre_library", {
+        dryRun: true,
+      });
+      setImportReport(report);
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const importComicInfoFolder = async () => {
+    try {
+      const { imported, skipped, errors } = await invoke<ComicInfoImport>(
+        "import_comicinfo_folder",
+      );
+      toast.success(
+        skipped > 0
+          ? `Imported ${imported} comics, ${skipped} already in the catalog`
+          : `Imported ${imported} comics`,
+      );
+      if (errors.length > 0) {
+        toast.error(`${errors.length} files could not be imported`, {
+          description: errors.join("\n"),
+        });
+      }
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const exportComicInfo = async (volumeId: string) => {
+    try {
+      const message = await invoke<string>("export_comicinfo", { volumeId });
+      toast.success(message);
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const handleImportModeChange = async (
+    { path, format }: ImportReport,
+    mode: ImportMode,
+  ) => {
+    try {
+      const report = await invoke<ImportReport>("import_books_csv", {
+        path,
+        format,
+        mode,
+        dryRun: true,
+      });
+      setImportReport(report);
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const handleConfirmImport = async (
+    { path, format }: ImportReport,
+    mode: ImportMode,
+  ) => {
+    try {
+      const report = await invoke<ImportReport>("import_books_csv", {
+        path,
+        format,
+        mode,
+        dryRun: false,
+      });
+      toast.success(
+        mode === "merge"
+          ? `Imported ${report.inserted} books, updated ${report.updated}`
+          : `Imported ${report.inserted} books`,
+      );
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+      throw err;
+    }
+  };
+
+  const exportArchive = async () => {
+    try {
+      const message = await invoke<string>("export_library_archive");
+      toast.success(message);
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const restoreArchive = async () => {
+    const confirmed = await ask(
+      "Restoring a backup replaces every book, cover and setting in this library.",
+      { title: "Restore backup", kind: "warning", okLabel: "Choose backup" },
+    );
+    if (!confirmed) {
+      return;
+    }
+    try {
+      const message = await invoke<string>("restore_library_archive");
       toast.success(message);
     } catch (err) {
       toast.error(err instanceof Error ? err.message : String(err));
@@ -445,12 +628,60 @@ function App() {
       ariaLabel: "Import",
       onClick: openImportDialog,
     },
+    {
+      id: "import-calibre",
+      icon: <FolderInput size={18} />,
+      ariaLabel: "Import Calibre library",
+      onClick: openCalibreImportDialog,
+    },
+    {
+      id: "import-comicinfo",
+      icon: <FileCode size={18} />,
+      ariaLabel: "Import ComicInfo.xml folder",
+      onClick: importComicInfoFolder,
+    },
     {
       id: "export",
       icon: <Download size={18} />,
       ariaLabel: "Export",
       onClick: openExportDialog,
     },
+    {
+      id: "export-citations",
+      icon: <Quote size={18} />,
+      ariaLabel: "Export citations and catalog records",
+      onClick: () => setCitationsOpen(true),
+    },
+    {
+      id: "loans",
+      icon: <Handshake size={18} />,
+      ariaLabel: "Loans",
+      onClick: () => setLoansOpen(true),
+    },
+    {
+      id: "locations",
+      icon: <MapPin size={18} />,
+      ariaLabel: "Locations",
+      onClick: () => setLocationsOpen(true),
+    },
+    {
+      id: "backup",
+      icon: <Archive size={18} />,
+      ariaLabel: "Back up library",
+      onClick: exportArchive,
+    },
+    {
+      id: "restore",
+      icon: <ArchiveRestore size={18} />,
+      ariaLabel: "Restore library",
+      onClick: restoreArchive,
+    },
+    {
+      id: "backups",
+      icon: <History size={18} />,
+      ariaLabel: "Automatic backups",
+      onClick: () => setBackupsOpen(true),
+    },
     {
       id: "theme",
       icon: getThemeIcon(),
@@ -518,7 +749,7 @@ function App() {
   return (
     <div className="h-screen w-screen flex flex-col">
       <Toaster richColors />
-      <KeypressListener onScan={handleScan} />
+      <KeypressListener onScan={stocktaking ? undefined : handleScan} />
 
       <div className="fixed top-0 left-0 right-0 z-20 bg-transparent pointer-events-none select-none">
         <div className="flex justify-center">
@@ -656,11 +887,41 @@ function App() {
               return Array.from(all).sort();
             });
           }}
+          onExportComicInfo={() => exportComicInfo(selectedBook.volume_id)}
+          onLend={() => setBookToLend(selectedBook)}
+          onReadingChange={(reading) =>
+            setSelectedBook({ ...selectedBook, reading })
+          }
+          onCopiesChange={(copies) =>
+            setSelectedBook({ ...selectedBook, copies })
+          }
+          onReturn={async () => {
+            try {
+              const loan = await invoke<Loan>("return_book", {
+                volumeId: selectedBook.volume_id,
+              });
+              toast.success(`${loan.title} is back from ${loan.borrower}`);
+              setSelectedBook({ ...selectedBook, loan: null });
+            } catch (err) {
+              toast.error(err instanceof Error ? err.message : String(err));
+            }
+          }}
           onDelete={async () => {
             if (!selectedBook) return;
+            const loan = selectedBook.loan;
+            if (loan) {
+              const confirmed = await ask(
+                `${selectedBook.title} is lent to ${loan.borrower} since ${loan.lent_on}. Delete it and its loans anyway?`,
+                { title: "Delete book", kind: "warning", okLabel: "Delete" },
+              );
+              if (!confirmed) {
+                return;
+              }
+            }
             try {
               await invoke("delete_book", {
                 volumeId: selectedBook.volume_id,
+                force: !!loan,
               });
               setDetailsOpen(false);
               setBooks((prev) =>
@@ -672,6 +933,38 @@ function App() {
           }}
         />
       )}
+      <ImportReportDialog
+        report={importReport}
+        onClose={() => setImportReport(undefined)}
+        onConfirm={handleConfirmImport}
+        onModeChange={handleImportModeChange}
+      />
+      <BackupsDialog
+        open={backupsOpen}
+        onClose={() => setBackupsOpen(false)}
+      />
+      <LendDialog
+        book={bookToLend}
+        onClose={() => setBookToLend(undefined)}
+        onLent={(loan) => {
+          setSelectedBook((prev) =>
+            prev?.volume_id === loan.volume_id ? { ...prev, loan } : prev,
+          );
+        }}
+      />
+      <LoansDialog open={loansOpen} onClose={() => setLoansOpen(false)} />
+      <LocationsDialog
+        open={locationsOpen}
+        onClose={() => setLocationsOpen(false)}
+        shownVolumeIds={filteredBooks.map((book) => book.volume_id)}
+        onStocktakingChange={setStocktaking}
+      />
+      <ExportDialog
+        open={citationsOpen}
+        onClose={() => setCitationsOpen(false)}
+        shownVolumeIds={filteredBooks.map((book) => book.volume_id)}
+        knownGroups={knownGroups}
+      />
       <BookNumberDialog
         open={bookWithoutNumber !== undefined}
         book={bookWithoutNumber}
@@ -0,0 +1,147 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { ask } from "@tauri-apps/plugin-dialog";
+import { toast } from "sonner";
+import { cn } from "../utils";
+import { BackupInfo } from "../types";
+
+type BackupsDialogProps = {
+  open: boolean;
+  onClose: () => void;
+  className?: string;
+};
+
+function formatSize(bytes: number) {
+  if (bytes < 1024 * 1024) {
+    return `${Math.max(1, Math.round(bytes / 1024))} KB`;
+  }
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+export function BackupsDialog({ open, onClose, className }: BackupsDialogProps) {
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+  const [restoring, setRestoring] = useState<string | undefined>(undefined);
+
+  useEffect(() => {
+    if (!open) return;
+
+    invoke<BackupInfo[]>("list_backups")
+      .then(setBackups)
+      .catch((err) => toast.error(String(err)));
+  }, [open]);
+
+  if (!open) {
+    return null;
+  }
+
+  const handleRestore = async (backup: BackupInfo) => {
+    const date = new Date(backup.created_at * 1000).toLocaleString();
+    const confirmed = await ask(
+      `Replace the library with the backup from ${date}? The current library is backed up first.`,
+      { title: "Restore backup", kind: "warning", okLabel: "Restore" },
+    );
+    if (!confirmed) {
+      return;
+    }
+    setRestoring(backup.name);
+    try {
+      const message = await invoke<string>("restore_backup", {
+        name: backup.name,
+      });
+      toast.success(message);
+      onClose();
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    } finally {
+      setRestoring(undefined);
+    }
+  };
+
+  return (
+    <div
+      className={cn(
+        "fixed inset-0 z-50 flex items-center justify-center",
+        "bg-black/40 backdrop-blur-sm",
+        className,
+      )}
+      onClick={onClose}
+    >
+      <div
+        role="dialog"
+        aria-modal="true"
+        aria-labelledby="backups-title"
+        className={cn(
+          "w-full max-w-lg mx-4 rounded-2xl",
+          "bg-white dark:bg-zinc-900 border border-black/10 dark:border-white/10",
+          "shadow-xl",
+        )}
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="p-5 border-b border-black/10 dark:border-white/10">
+          <h2
+            id="backups-title"
+            className="text-lg font-semibold text-zinc-900 dark:text-zinc-100"
+          >
+            Backups
+          </h2>
+          <p className="mt-1 text-sm text-zinc-500 dark:text-zinc-400">
+            Taken automatically on startup and while you edit the library.
+          </p>
+        </div>
+
+        <div className="p-5 max-h-96 overflow-y-auto">
+          {backups.length === 0 ? (
+            <p className="text-sm text-zinc-500 dark:text-zinc-400">
+              No backups yet.
+            </p>
+          ) : (
+            <ul className="space-y-1">
+              {backups.map((backup) => (
+                <li
+                  key={backup.name}
+                  className="flex items-center justify-between gap-3 py-1.5"
+                >
+                  <div className="text-sm text-zinc-900 dark:text-zinc-100">
+                    {new Date(backup.created_at * 1000).toLocaleString()}
+                    <span className="ml-2 text-xs text-zinc-500 dark:text-zinc-400">
+                      {formatSize(backup.size)}
+                    </span>
+                  </div>
+                  <button
+                    disabled={restoring !== undefined}
+                    onClick={() => handleRestore(backup)}
+                    className={cn(
+                      "inline-flex h-8 items-center justify-center rounded-md px-3",
+                      "text-sm text-zinc-700 dark:text-zinc-300",
+                      "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+                      "transition-colors",
+                      restoring !== undefined && "opacity-60 cursor-not-allowed",
+                    )}
+                  >
+                    {restoring === backup.name ? "Restoring..." : "Restore"}
+                  </button>
+                </li>
+              ))}
+            </ul>
+          )}
+        </div>
+
+        <div className="p-5 pt-0">
+          <div className="flex justify-end">
+            <button
+              onClick={onClose}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm text-zinc-700 dark:text-zinc-300",
+                "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+                "transition-colors",
+              )}
+            >
+              Close
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,293 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { Plus, Trash2 } from "lucide-react";
+import { cn } from "../utils";
+import {
+  Book,
+  BookCopy,
+  ConditionGrade,
+  CopyDetails,
+  GradeScale,
+  Location,
+} from "../types";
+
+type CopiesSectionProps = {
+  book: Book;
+  onChange: (copies: BookCopy[]) => void;
+};
+
+const inputClass = cn(
+  "w-full rounded-lg border border-gray-300 dark:border-zinc-600 px-2.5 py-1.5 text-sm outline-none",
+  "bg-white dark:bg-zinc-700 text-gray-900 dark:text-white",
+  "focus:border-blue-500 dark:focus:border-blue-400",
+);
+
+function summary(
+  copy: BookCopy,
+  grades: ConditionGrade[],
+  locations: Location[],
+) {
+  const grade = grades.find(
+    (g) => g.scale === copy.grade_scale && g.grade === copy.grade,
+  );
+  return [
+    grade &&
+      (grade.grade === grade.label
+        ? grade.label
+        : `${grade.grade} ${grade.label}`),
+    copy.first_edition && "first edition",
+    copy.signed && "signed",
+    copy.acquired_on,
+    copy.source,
+    copy.price != null && `${copy.price} ${copy.currency ?? ""}`.trim(),
+    locations.find((l) => l.location_id === copy.location_id)?.path,
+  ]
+    .filter(Boolean)
+    .join(", ");
+}
+
+function CopyForm({
+  copy,
+  grades,
+  locations,
+  onSaved,
+  onDeleted,
+}: {
+  copy: BookCopy;
+  grades: ConditionGrade[];
+  locations: Location[];
+  onSaved: (copy: BookCopy) => void;
+  onDeleted: () => void;
+}) {
+  const [details, setDetails] = useState<CopyDetails>(copy);
+  const [price, setPrice] = useState(copy.price?.toString() ?? "");
+
+  const set = (patch: Partial<CopyDetails>) =>
+    setDetails((prev) => ({ ...prev, ...patch }));
+
+  const handleSave = async () => {
+    try {
+      const saved = await invoke<BookCopy>("update_copy", {
+        copyId: copy.copy_id,
+        details: {
+          ...details,
+          price: price.trim() === "" ? null : Number(price.replace(",", ".")),
+        },
+      });
+      onSaved(saved);
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const handleDelete = async () => {
+    try {
+      await invoke("delete_copy", { copyId: copy.copy_id });
+      onDeleted();
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  return (
+    <div className="space-y-2 p-3 rounded-lg bg-gray-50 dark:bg-zinc-700/50">
+      <div className="grid grid-cols-2 gap-2">
+        <input
+          type="date"
+          value={details.acquired_on ?? ""}
+          onChange={(e) => set({ acquired_on: e.target.value })}
+          className={inputClass}
+        />
+        <input
+          placeholder="Source"
+          value={details.source ?? ""}
+          onChange={(e) => set({ source: e.target.value })}
+          className={inputClass}
+        />
+        <input
+          placeholder="Price"
+          inputMode="decimal"
+          value={price}
+          onChange={(e) => setPrice(e.target.value)}
+          className={inputClass}
+        />
+        <input
+          placeholder="Currency"
+          maxLength={3}
+          value={details.currency ?? ""}
+          onChange={(e) => set({ currency: e.target.value })}
+          className={inputClass}
+        />
+        <select
+          value={details.grade_scale ?? ""}
+          onChange={(e) =>
+            set({
+              grade_scale: (e.target.value || null) as GradeScale | null,
+              grade: null,
+            })
+          }
+          className={inputClass}
+        >
+          <option value="">No grade</option>
+          <option value="book">Book grade</option>
+          <option value="comic">Comic grade</option>
+        </select>
+        <select
+          value={details.grade ?? ""}
+          disabled={!details.grade_scale}
+          onChange={(e) => set({ grade: e.target.value || null })}
+          className={inputClass}
+        >
+          <option value="" />
+          {grades
+            .filter((g) => g.scale === details.grade_scale)
+            .map((g) => (
+              <option key={g.grade} value={g.grade}>
+                {g.grade === g.label ? g.label : `${g.grade} ${g.label}`}
+              </option>
+            ))}
+        </select>
+      </div>
+      <div className="flex gap-4 text-sm text-gray-700 dark:text-gray-300">
+        <label className="flex items-center gap-1.5">
+          <input
+            type="checkbox"
+            checked={details.first_edition}
+            onChange={(e) => set({ first_edition: e.target.checked })}
+          />
+          First edition
+        </label>
+        <label className="flex items-center gap-1.5">
+          <input
+            type="checkbox"
+            checked={details.signed}
+            onChange={(e) => set({ signed: e.target.checked })}
+          />
+          Signed
+        </label>
+      </div>
+      <select
+        value={details.location_id?.toString() ?? ""}
+        onChange={(e) =>
+          set({
+            location_id: e.target.value === "" ? null : Number(e.target.value),
+          })
+        }
+        className={inputClass}
+      >
+        <option value="">Not shelved</option>
+        {locations.map((l) => (
+          <option key={l.location_id} value={l.location_id}>
+            {l.path}
+          </option>
+        ))}
+      </select>
+      <input
+        placeholder="Notes"
+        value={details.notes ?? ""}
+        onChange={(e) => set({ notes: e.target.value })}
+        className={inputClass}
+      />
+      <div className="flex justify-between">
+        <button
+          onClick={handleDelete}
+          className="inline-flex items-center gap-1.5 rounded-lg px-2 py-1 text-sm text-red-600 dark:text-red-400 hover:bg-red-50 dark:hover:bg-red-900/20 transition-colors"
+        >
+          <Trash2 size={14} />
+          Remove copy
+        </button>
+        <button
+          onClick={handleSave}
+          className="rounded-lg px-3 py-1 text-sm font-medium text-white bg-blue-600 hover:bg-blue-700 transition-colors"
+        >
+          Save
+        </button>
+      </div>
+    </div>
+  );
+}
+
+export function CopiesSection({ book, onChange }: CopiesSectionProps) {
+  const [grades, setGrades] = useState<ConditionGrade[]>([]);
+  const [locations, setLocations] = useState<Location[]>([]);
+  const [editing, setEditing] = useState<number | undefined>(undefined);
+
+  useEffect(() => {
+    invoke<ConditionGrade[]>("get_condition_grades")
+      .then(setGrades)
+      .catch((err) => toast.error(String(err)));
+    invoke<Location[]>("get_locations")
+      .then(setLocations)
+      .catch((err) => toast.error(String(err)));
+  }, []);
+
+  useEffect(() => {
+    setEditing(undefined);
+  }, [book.volume_id]);
+
+  const handleAdd = async () => {
+    try {
+      const copy = await invoke<BookCopy>("add_copy", {
+        volumeId: book.volume_id,
+        details: { signed: false, first_edition: false },
+      });
+      onChange([...book.copies, copy]);
+      setEditing(copy.copy_id);
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <div className="flex items-center justify-between">
+        <h3 className="text-sm font-semibold text-gray-700 dark:text-gray-300">
+          {book.copies.length === 1
+            ? "Copy"
+            : `Copies (${book.copies.length})`}
+        </h3>
+        <button
+          onClick={handleAdd}
+          className="inline-flex items-center gap-1 rounded-lg px-2 py-1 text-sm text-gray-700 dark:text-gray-300 hover:bg-gray-100 dark:hover:bg-zinc-700 transition-colors"
+        >
+          <Plus size={14} />
+          Add copy
+        </button>
+      </div>
+      {book.copies.map((copy, index) =>
+        editing === copy.copy_id ? (
+          <CopyForm
+            key={copy.copy_id}
+            copy={copy}
+            grades={grades}
+            locations={locations}
+            onSaved={(saved) => {
+              onChange(
+                book.copies.map((c) =>
+                  c.copy_id === saved.copy_id ? saved : c,
+                ),
+              );
+              setEditing(undefined);
+            }}
+            onDeleted={() => {
+              onChange(book.copies.filter((c) => c.copy_id !== copy.copy_id));
+              setEditing(undefined);
+            }}
+          />
+        ) : (
+          <button
+            key={copy.copy_id}
+            onClick={() => setEditing(copy.copy_id)}
+            className="block w-full text-left rounded-lg px-3 py-2 text-sm text-gray-700 dark:text-gray-300 hover:bg-gray-100 dark:hover:bg-zinc-700 transition-colors"
+          >
+            <span className="font-medium">#{index + 1}</span>{" "}
+            <span className="text-gray-500 dark:text-gray-400">
+              {summary(copy, grades, locations) || "No details"}
+            </span>
+          </button>
+        ),
+      )}
+    </div>
+  );
+}
@@ -1,8 +1,19 @@
 import { cn, getColorForGroup } from "../utils";
 import { useEffect, useMemo, useState, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { X, Save, ChevronLeft, ChevronRight, Trash2 } from "lucide-react";
-import { Book } from "../types";
+import {
+  X,
+  Save,
+  ChevronLeft,
+  ChevronRight,
+  Trash2,
+  FileCode,
+  BookUp,
+  BookDown,
+} from "lucide-react";
+import { Book, BookCopy, ReadingState } from "../types";
+import { ReadingSection } from "./ReadingSection";
+import { CopiesSection } from "./CopiesSection";
 
 export interface DetailsDialogProps {
   open: boolean;
@@ -17,6 +28,11 @@ export interface DetailsDialogProps {
   knownCustomFields?: string[];
   knownSeries?: string[];
   onDelete?: () => void;
+  onExportComicInfo?: () => void;
+  onLend?: () => void;
+  onReturn?: () => void;
+  onReadingChange?: (reading: ReadingState) => void;
+  onCopiesChange?: (copies: BookCopy[]) => void;
   onSaveComplete?: (book: Book) => void;
 }
 
@@ -33,6 +49,11 @@ export function DetailsDialog({
   knownCustomFields = [],
   knownSeries = [],
   onDelete,
+  onExportComicInfo,
+  onLend,
+  onReturn,
+  onReadingChange,
+  onCopiesChange,
   onSaveComplete,
 }: DetailsDialogProps) {
   const [saving, setSaving] = useState(false);
@@ -794,6 +815,27 @@ export function DetailsDialog({
                   <ViewField label="Year" value={form.published_date} />
                 </div>
                 <ViewField label="Authors" value={form.authors} />
+                {initial.loan && (
+                  <div
+                    className={cn(
+                      "rounded-lg px-3 py-2 text-sm",
+                      initial.loan.overdue
+                        ? "bg-red-50 dark:bg-red-900/20 text-red-700 dark:text-red-300"
+                        : "bg-amber-50 dark:bg-amber-900/20 text-amber-800 dark:text-amber-200",
+                    )}
+                  >
+                    Lent to {initial.loan.borrower} since{" "}
+                    {initial.loan.lent_on}
+                    {initial.loan.due_on && `, due ${initial.loan.due_on}`}
+                    {initial.loan.notes && ` (${initial.loan.notes})`}
+                  </div>
+                )}
+                {onReadingChange && (
+                  <ReadingSection book={initial} onChange={onReadingChange} />
+                )}
+                {onCopiesChange && (
+                  <CopiesSection book={initial} onChange={onCopiesChange} />
+                )}
                 <div>
                   <h3 className="text-sm font-semibold text-gray-700 dark:text-gray-300 mb-1">
                     Groups
@@ -930,6 +972,33 @@ export function DetailsDialog({
                   Delete
                 </button>
               )}
+              {!editMode && onExportComicInfo && (
+                <button
+                  className="inline-flex items-center gap-2 rounded-lg px-4 py-2 text-sm font-medium text-gray-700 dark:text-gray-300 hover:bg-gray-100 dark:hover:bg-zinc-700 transition-colors"
+                  onClick={onExportComicInfo}
+                >
+                  <FileCode size={16} />
+                  ComicInfo.xml
+                </button>
+              )}
+              {!editMode && !initial.loan && onLend && (
+                <button
+                  className="inline-flex items-center gap-2 rounded-lg px-4 py-2 text-sm font-medium text-gray-700 dark:text-gray-300 hover:bg-gray-100 dark:hover:bg-zinc-700 transition-colors"
+                  onClick={onLend}
+                >
+                  <BookUp size={16} />
+                  Lend
+                </button>
+              )}
+              {!editMode && initial.loan && onReturn && (
+                <button
+                  className="inline-flex items-center gap-2 rounded-lg px-4 py-2 text-sm font-medium text-gray-700 dark:text-gray-300 hover:bg-gray-100 dark:hover:bg-zinc-700 transition-colors"
+                  onClick={onReturn}
+                >
+                  <BookDown size={16} />
+                  Returned
+                </button>
+              )}
             </div>
             <div className="flex items-center gap-3">
               <button
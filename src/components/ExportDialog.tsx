@@ -0,0 +1,202 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { cn } from "../utils";
+import { ExportFormat, ExportSelection } from "../types";
+
+type ExportDialogProps = {
+  open: boolean;
+  onClose: () => void;
+  // The books currently shown, after search and group filters
+  shownVolumeIds: string[];
+  knownGroups: string[];
+  className?: string;
+};
+
+type Scope = "all" | "shown" | "group";
+
+const formatLabel: Record<ExportFormat, string> = {
+  bibtex: "BibTeX",
+  ris: "RIS",
+  csl_json: "CSL-JSON",
+  marc21: "MARC 21",
+  marcxml: "MARCXML",
+  dublin_core: "Dublin Core",
+};
+
+export function ExportDialog({
+  open,
+  onClose,
+  shownVolumeIds,
+  knownGroups,
+  className,
+}: ExportDialogProps) {
+  const [format, setFormat] = useState<ExportFormat>("bibtex");
+  const [scope, setScope] = useState<Scope>("all");
+  const [group, setGroup] = useState("");
+  const [exporting, setExporting] = useState(false);
+
+  useEffect(() => {
+    if (!open) return;
+
+    setGroup((prev) =>
+      knownGroups.includes(prev) ? prev : (knownGroups[0] ?? ""),
+    );
+  }, [open, knownGroups]);
+
+  if (!open) {
+    return null;
+  }
+
+  const scopeLabel: Record<Scope, string> = {
+    all: "Whole library",
+    shown: `Shown books (${shownVolumeIds.length})`,
+    group: "Group",
+  };
+
+  const selection = (): ExportSelection => {
+    if (scope === "shown") {
+      return { kind: "books", volume_ids: shownVolumeIds };
+    }
+    if (scope === "group") {
+      return { kind: "group", name: group };
+    }
+    return { kind: "all" };
+  };
+
+  const handleExport = async () => {
+    setExporting(true);
+    try {
+      const message = await invoke<string>("export_books", {
+        format,
+        selection: selection(),
+      });
+      toast.success(message);
+      onClose();
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    } finally {
+      setExporting(false);
+    }
+  };
+
+  const toggleClass = (active: boolean) =>
+    cn(
+      "flex-1 py-1.5 px-3 text-sm font-medium rounded-md transition-colors",
+      active
+        ? "bg-white dark:bg-zinc-700 text-zinc-900 dark:text-zinc-100 shadow-sm"
+        : "text-zinc-600 dark:text-zinc-400 hover:text-zinc-900 dark:hover:text-zinc-200",
+    );
+
+  return (
+    <div
+      className={cn(
+        "fixed inset-0 z-50 flex items-center justify-center",
+        "bg-black/40 backdrop-blur-sm",
+        className,
+      )}
+      onClick={onClose}
+    >
+      <div
+        role="dialog"
+        aria-modal="true"
+        aria-labelledby="export-title"
+        className={cn(
+          "w-full max-w-lg mx-4 rounded-2xl",
+          "bg-white dark:bg-zinc-900 border border-black/10 dark:border-white/10",
+          "shadow-xl",
+        )}
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="p-5 border-b border-black/10 dark:border-white/10">
+          <h2
+            id="export-title"
+            className="text-lg font-semibold text-zinc-900 dark:text-zinc-100"
+          >
+            Export Books
+          </h2>
+          <p className="mt-1 text-sm text-zinc-500 dark:text-zinc-400">
+            Citations for Zotero, EndNote or LaTeX, records for library
+            catalogs.
+          </p>
+        </div>
+
+        <div className="p-5 space-y-4">
+          <div className="grid grid-cols-3 gap-1 rounded-lg bg-zinc-100 dark:bg-zinc-800 p-1">
+            {(Object.keys(formatLabel) as ExportFormat[]).map((f) => (
+              <button
+                key={f}
+                disabled={exporting}
+                onClick={() => setFormat(f)}
+                className={toggleClass(format === f)}
+              >
+                {formatLabel[f]}
+              </button>
+            ))}
+          </div>
+
+          <div className="flex rounded-lg bg-zinc-100 dark:bg-zinc-800 p-1">
+            {(Object.keys(scopeLabel) as Scope[]).map((s) => (
+              <button
+                key={s}
+                disabled={
+                  exporting || (s === "group" && knownGroups.length === 0)
+                }
+                onClick={() => setScope(s)}
+                className={toggleClass(scope === s)}
+              >
+                {scopeLabel[s]}
+              </button>
+            ))}
+          </div>
+
+          {scope === "group" && (
+            <select
+              value={group}
+              onChange={(e) => setGroup(e.target.value)}
+              className={cn(
+                "w-full h-9 rounded-md px-2 text-sm",
+                "bg-white dark:bg-zinc-800 text-zinc-900 dark:text-zinc-100",
+                "border border-black/10 dark:border-white/10",
+              )}
+            >
+              {knownGroups.map((g) => (
+                <option key={g} value={g}>
+                  {g}
+                </option>
+              ))}
+            </select>
+          )}
+        </div>
+
+        <div className="p-5 pt-0">
+          <div className="flex justify-end gap-2">
+            <button
+              onClick={onClose}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm text-zinc-700 dark:text-zinc-300",
+                "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+                "transition-colors",
+              )}
+            >
+              Cancel
+            </button>
+            <button
+              onClick={handleExport}
+              disabled={exporting || (scope === "group" && !group)}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm font-medium text-white bg-blue-600 hover:bg-blue-700",
+                "transition-colors",
+                exporting && "opacity-60 cursor-not-allowed",
+              )}
+            >
+              {exporting ? "Exporting..." : "Export"}
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
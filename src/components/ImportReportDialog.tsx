@@ -0,0 +1,204 @@
+import { useEffect, useState } from "react";
+import { cn } from "../utils";
+import {
+  ImportFormat,
+  ImportMode,
+  ImportReport,
+  ImportRowReport,
+} from "../types";
+
+type ImportReportDialogProps = {
+  report?: ImportReport;
+  onClose: () => void;
+  onConfirm: (report: ImportReport, mode: ImportMode) => Promise<void>;
+  onModeChange: (report: ImportReport, mode: ImportMode) => Promise<void>;
+  className?: string;
+};
+
+const formatLabel: Record<ImportFormat, string> = {
+  native: "CSV export",
+  goodreads: "Goodreads export",
+  librarything: "LibraryThing export",
+  calibre: "Calibre library",
+  clz: "CLZ Comics export",
+  comicrack: "ComicRack database",
+};
+
+const statusLabel: Record<ImportRowReport["status"], string> = {
+  inserted: "New",
+  updated: "Updated",
+  skipped: "Skipped",
+  rejected: "Rejected",
+};
+
+const statusClassName: Record<ImportRowReport["status"], string> = {
+  inserted: "text-green-600 dark:text-green-400",
+  updated: "text-blue-600 dark:text-blue-400",
+  skipped: "text-zinc-500 dark:text-zinc-400",
+  rejected: "text-red-500 dark:text-red-400",
+};
+
+export function ImportReportDialog({
+  report,
+  onClose,
+  onConfirm,
+  onModeChange,
+  className,
+}: ImportReportDialogProps) {
+  const [submitting, setSubmitting] = useState(false);
+
+  useEffect(() => {
+    setSubmitting(false);
+  }, [report]);
+
+  if (!report) {
+    return null;
+  }
+
+  const changes = report.inserted + report.updated;
+  const canSubmit = changes > 0 && !submitting;
+
+  const handleSubmit = async () => {
+    if (!canSubmit) {
+      return;
+    }
+    setSubmitting(true);
+    try {
+      await onConfirm(report, report.mode);
+      onClose();
+    } catch (e) {
+      console.error("Failed to import books:", e);
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  return (
+    <div
+      className={cn(
+        "fixed inset-0 z-50 flex items-center justify-center",
+        "bg-black/40 backdrop-blur-sm",
+        className,
+      )}
+      onClick={onClose}
+    >
+      <div
+        role="dialog"
+        aria-modal="true"
+        aria-labelledby="import-report-title"
+        className={cn(
+          "w-full max-w-2xl mx-4 rounded-2xl",
+          "bg-white dark:bg-zinc-900 border border-black/10 dark:border-white/10",
+          "shadow-xl",
+        )}
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="p-5 border-b border-black/10 dark:border-white/10">
+          <h2
+            id="import-report-title"
+            className="text-lg font-semibold text-zinc-900 dark:text-zinc-100"
+          >
+            Import Preview
+          </h2>
+          <p className="mt-1 text-sm text-zinc-500 dark:text-zinc-400">
+            {formatLabel[report.format]}:{" "}
+            <strong>{report.inserted}</strong> new,{" "}
+            {report.mode === "merge" && (
+              <>
+                <strong>{report.updated}</strong> updated,{" "}
+              </>
+            )}
+            <strong>{report.skipped}</strong> already in the library,{" "}
+            <strong>{report.rejected}</strong> rejected
+          </p>
+        </div>
+
+        <div className="px-5 pt-5">
+          {/* Mode Toggle */}
+          <div className="flex rounded-lg bg-zinc-100 dark:bg-zinc-800 p-1">
+            {(["insert", "merge"] as const).map((mode) => (
+              <button
+                key={mode}
+                disabled={submitting}
+                onClick={() => {
+                  if (mode !== report.mode) {
+                    onModeChange(report, mode);
+                  }
+                }}
+                className={cn(
+                  "flex-1 py-1.5 px-3 text-sm font-medium rounded-md transition-colors",
+                  report.mode === mode
+                    ? "bg-white dark:bg-zinc-700 text-zinc-900 dark:text-zinc-100 shadow-sm"
+                    : "text-zinc-600 dark:text-zinc-400 hover:text-zinc-900 dark:hover:text-zinc-200",
+                )}
+              >
+                {mode === "insert" ? "Add new books" : "Update existing books"}
+              </button>
+            ))}
+          </div>
+        </div>
+
+        <div className="p-5 max-h-96 overflow-y-auto">
+          <table className="w-full text-sm">
+            <thead>
+              <tr className="text-left text-zinc-500 dark:text-zinc-400">
+                <th className="pb-2 pr-3 font-medium">Line</th>
+                <th className="pb-2 pr-3 font-medium">Title</th>
+                <th className="pb-2 font-medium">Status</th>
+              </tr>
+            </thead>
+            <tbody className="text-zinc-900 dark:text-zinc-100">
+              {report.rows.map((row) => (
+                <tr
+                  key={row.line}
+                  className="border-t border-black/5 dark:border-white/5"
+                >
+                  <td className="py-1.5 pr-3 tabular-nums text-zinc-500">
+                    {row.line}
+                  </td>
+                  <td className="py-1.5 pr-3">{row.title ?? "—"}</td>
+                  <td className={cn("py-1.5", statusClassName[row.status])}>
+                    {statusLabel[row.status]}
+                    {row.status !== "inserted" && (
+                      <span className="block text-xs">{row.reason}</span>
+                    )}
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+
+        <div className="p-5 pt-0">
+          <div className="flex justify-end gap-2">
+            <button
+              onClick={onClose}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm text-zinc-700 dark:text-zinc-300",
+                "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+                "transition-colors",
+              )}
+            >
+              Cancel
+            </button>
+            <button
+              disabled={!canSubmit}
+              onClick={handleSubmit}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm text-white bg-blue-600 hover:bg-blue-700",
+                "shadow-sm transition-colors",
+                !canSubmit && "opacity-60 cursor-not-allowed",
+              )}
+            >
+              {submitting
+                ? "Importing..."
+                : `Import ${changes} ${changes === 1 ? "Book" : "Books"}`}
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
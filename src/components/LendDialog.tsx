@@ -0,0 +1,171 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { cn } from "../utils";
+import { Book, Loan } from "../types";
+
+type LendDialogProps = {
+  book?: Book;
+  onClose: () => void;
+  onLent: (loan: Loan) => void;
+  className?: string;
+};
+
+const inputClass = cn(
+  "w-full h-9 rounded-md px-2 text-sm",
+  "bg-white dark:bg-zinc-800 text-zinc-900 dark:text-zinc-100",
+  "border border-black/10 dark:border-white/10",
+);
+
+export function LendDialog({
+  book,
+  onClose,
+  onLent,
+  className,
+}: LendDialogProps) {
+  const [borrower, setBorrower] = useState("");
+  const [dueOn, setDueOn] = useState("");
+  const [notes, setNotes] = useState("");
+  const [knownBorrowers, setKnownBorrowers] = useState<string[]>([]);
+  const [lending, setLending] = useState(false);
+
+  useEffect(() => {
+    if (!book) return;
+
+    setBorrower("");
+    setDueOn("");
+    setNotes("");
+    invoke<string[]>("get_all_borrowers")
+      .then(setKnownBorrowers)
+      .catch((err) => toast.error(String(err)));
+  }, [book]);
+
+  if (!book) {
+    return null;
+  }
+
+  const handleLend = async () => {
+    setLending(true);
+    try {
+      const loan = await invoke<Loan>("lend_book", {
+        volumeId: book.volume_id,
+        borrower,
+        dueOn: dueOn || null,
+        notes: notes || null,
+      });
+      toast.success(`Lent ${book.title} to ${loan.borrower}`);
+      onLent(loan);
+      onClose();
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    } finally {
+      setLending(false);
+    }
+  };
+
+  return (
+    <div
+      className={cn(
+        "fixed inset-0 z-50 flex items-center justify-center",
+        "bg-black/40 backdrop-blur-sm",
+        className,
+      )}
+      onClick={onClose}
+    >
+      <div
+        role="dialog"
+        aria-modal="true"
+        aria-labelledby="lend-title"
+        className={cn(
+          "w-full max-w-md mx-4 rounded-2xl",
+          "bg-white dark:bg-zinc-900 border border-black/10 dark:border-white/10",
+          "shadow-xl",
+        )}
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="p-5 border-b border-black/10 dark:border-white/10">
+          <h2
+            id="lend-title"
+            className="text-lg font-semibold text-zinc-900 dark:text-zinc-100"
+          >
+            Lend Book
+          </h2>
+          <p className="mt-1 text-sm text-zinc-500 dark:text-zinc-400">
+            {book.title}
+          </p>
+        </div>
+
+        <div className="p-5 space-y-4">
+          <label className="block space-y-1">
+            <span className="text-sm font-medium text-zinc-700 dark:text-zinc-300">
+              Borrower
+            </span>
+            <input
+              autoFocus
+              autoComplete="off"
+              list="known-borrowers"
+              value={borrower}
+              onChange={(e) => setBorrower(e.target.value)}
+              className={inputClass}
+            />
+            <datalist id="known-borrowers">
+              {knownBorrowers.map((b) => (
+                <option key={b} value={b} />
+              ))}
+            </datalist>
+          </label>
+          <label className="block space-y-1">
+            <span className="text-sm font-medium text-zinc-700 dark:text-zinc-300">
+              Due
+            </span>
+            <input
+              type="date"
+              value={dueOn}
+              onChange={(e) => setDueOn(e.target.value)}
+              className={inputClass}
+            />
+          </label>
+          <label className="block space-y-1">
+            <span className="text-sm font-medium text-zinc-700 dark:text-zinc-300">
+              Notes
+            </span>
+            <input
+              autoComplete="off"
+              value={notes}
+              onChange={(e) => setNotes(e.target.value)}
+              className={inputClass}
+            />
+          </label>
+        </div>
+
+        <div className="p-5 pt-0">
+          <div className="flex justify-end gap-2">
+            <button
+              onClick={onClose}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm text-zinc-700 dark:text-zinc-300",
+                "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+                "transition-colors",
+              )}
+            >
+              Cancel
+            </button>
+            <button
+              onClick={handleLend}
+              disabled={lending || !borrower.trim()}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm font-medium text-white bg-blue-600 hover:bg-blue-700",
+                "transition-colors",
+                (lending || !borrower.trim()) && "opacity-60 cursor-not-allowed",
+              )}
+            >
+              {lending ? "Lending..." : "Lend"}
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
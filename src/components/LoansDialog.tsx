@@ -0,0 +1,182 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { cn } from "../utils";
+import { Loan } from "../types";
+
+type LoansDialogProps = {
+  open: boolean;
+  onClose: () => void;
+  className?: string;
+};
+
+type View = "active" | "overdue" | "history";
+
+const viewLabel: Record<View, string> = {
+  active: "Lent",
+  overdue: "Overdue",
+  history: "History",
+};
+
+const viewCommand: Record<View, string> = {
+  active: "list_active_loans",
+  overdue: "list_overdue_loans",
+  history: "loan_history",
+};
+
+export function LoansDialog({ open, onClose, className }: LoansDialogProps) {
+  const [view, setView] = useState<View>("active");
+  const [loans, setLoans] = useState<Loan[]>([]);
+  const [returning, setReturning] = useState<number | undefined>(undefined);
+
+  const loadLoans = () =>
+    invoke<Loan[]>(viewCommand[view])
+      .then(setLoans)
+      .catch((err) => toast.error(String(err)));
+
+  useEffect(() => {
+    if (!open) return;
+
+    loadLoans();
+  }, [open, view]);
+
+  if (!open) {
+    return null;
+  }
+
+  const handleReturn = async (loan: Loan) => {
+    setReturning(loan.loan_id);
+    try {
+      await invoke<Loan>("return_book", { volumeId: loan.volume_id });
+      toast.success(`${loan.title} is back from ${loan.borrower}`);
+      await loadLoans();
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    } finally {
+      setReturning(undefined);
+    }
+  };
+
+  const toggleClass = (active: boolean) =>
+    cn(
+      "flex-1 py-1.5 px-3 text-sm font-medium rounded-md transition-colors",
+      active
+        ? "bg-white dark:bg-zinc-700 text-zinc-900 dark:text-zinc-100 shadow-sm"
+        : "text-zinc-600 dark:text-zinc-400 hover:text-zinc-900 dark:hover:text-zinc-200",
+    );
+
+  return (
+    <div
+      className={cn(
+        "fixed inset-0 z-50 flex items-center justify-center",
+        "bg-black/40 backdrop-blur-sm",
+        className,
+      )}
+      onClick={onClose}
+    >
+      <div
+        role="dialog"
+        aria-modal="true"
+        aria-labelledby="loans-title"
+        className={cn(
+          "w-full max-w-lg mx-4 rounded-2xl",
+          "bg-white dark:bg-zinc-900 border border-black/10 dark:border-white/10",
+          "shadow-xl",
+        )}
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="p-5 border-b border-black/10 dark:border-white/10">
+          <h2
+            id="loans-title"
+            className="text-lg font-semibold text-zinc-900 dark:text-zinc-100"
+          >
+            Loans
+          </h2>
+          <p className="mt-1 text-sm text-zinc-500 dark:text-zinc-400">
+            Books you lent and who has them.
+          </p>
+        </div>
+
+        <div className="p-5 space-y-4">
+          <div className="flex rounded-lg bg-zinc-100 dark:bg-zinc-800 p-1">
+            {(Object.keys(viewLabel) as View[]).map((v) => (
+              <button
+                key={v}
+                onClick={() => setView(v)}
+                className={toggleClass(view === v)}
+              >
+                {viewLabel[v]}
+              </button>
+            ))}
+          </div>
+
+          <div className="max-h-96 overflow-y-auto">
+            {loans.length === 0 ? (
+              <p className="text-sm text-zinc-500 dark:text-zinc-400">
+                No loans.
+              </p>
+            ) : (
+              <ul className="space-y-1">
+                {loans.map((loan) => (
+                  <li
+                    key={loan.loan_id}
+                    className="flex items-center justify-between gap-3 py-1.5"
+                  >
+                    <div className="min-w-0 text-sm text-zinc-900 dark:text-zinc-100">
+                      <div className="truncate">{loan.title}</div>
+                      <div
+                        className={cn(
+                          "text-xs",
+                          loan.overdue
+                            ? "text-red-600 dark:text-red-400"
+                            : "text-zinc-500 dark:text-zinc-400",
+                        )}
+                      >
+                        {loan.borrower}, {loan.lent_on}
+                        {loan.returned_on
+                          ? ` to ${loan.returned_on}`
+                          : loan.due_on && `, due ${loan.due_on}`}
+                      </div>
+                    </div>
+                    {!loan.returned_on && (
+                      <button
+                        disabled={returning !== undefined}
+                        onClick={() => handleReturn(loan)}
+                        className={cn(
+                          "inline-flex h-8 items-center justify-center rounded-md px-3",
+                          "text-sm text-zinc-700 dark:text-zinc-300",
+                          "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+                          "transition-colors",
+                          returning !== undefined &&
+                            "opacity-60 cursor-not-allowed",
+                        )}
+                      >
+                        {returning === loan.loan_id ? "Returning..." : "Return"}
+                      </button>
+                    )}
+                  </li>
+                ))}
+              </ul>
+            )}
+          </div>
+        </div>
+
+        <div className="p-5 pt-0">
+          <div className="flex justify-end">
+            <button
+              onClick={onClose}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm text-zinc-700 dark:text-zinc-300",
+                "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+                "transition-colors",
+              )}
+            >
+              Close
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,529 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { X } from "lucide-react";
+import { cn } from "../utils";
+import { KeypressListener } from "./KeypressListener";
+import {
+  LocatedCopy,
+  Location,
+  LocationKind,
+  StocktakeReport,
+} from "../types";
+
+type LocationsDialogProps = {
+  open: boolean;
+  onClose: () => void;
+  // The books currently shown, after search and group filters
+  shownVolumeIds: string[];
+  // Scans go to the stocktake while it runs, not to adding books
+  onStocktakingChange: (stocktaking: boolean) => void;
+  className?: string;
+};
+
+// null is the copies that aren't shelved
+type Selection = number | null;
+
+const kindLabel: Record<LocationKind, string> = {
+  room: "Room",
+  bookcase: "Bookcase",
+  shelf: "Shelf",
+  box: "Box",
+};
+
+const inputClass = cn(
+  "h-9 rounded-md px-2 text-sm",
+  "bg-white dark:bg-zinc-800 text-zinc-900 dark:text-zinc-100",
+  "border border-black/10 dark:border-white/10",
+);
+
+const buttonClass = cn(
+  "inline-flex h-8 items-center justify-center rounded-md px-3",
+  "text-sm text-zinc-700 dark:text-zinc-300",
+  "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+  "transition-colors disabled:opacity-60 disabled:cursor-not-allowed",
+);
+
+function copyTitle(copy: LocatedCopy) {
+  return copy.number != null ? `${copy.title} #${copy.number}` : copy.title;
+}
+
+function depth(location: Location, byId: Map<number, Location>) {
+  let level = 0;
+  let parent = location.parent_id;
+  while (parent != null && byId.has(parent)) {
+    level += 1;
+    parent = byId.get(parent)?.parent_id;
+  }
+  return level;
+}
+
+function CopyList({
+  copies,
+  showLocation,
+}: {
+  copies: LocatedCopy[];
+  showLocation: boolean;
+}) {
+  return (
+    <ul className="space-y-1">
+      {copies.map((copy) => (
+        <li
+          key={copy.copy_id}
+          className="text-sm text-zinc-900 dark:text-zinc-100"
+        >
+          <div className="truncate">{copyTitle(copy)}</div>
+          {showLocation && (
+            <div className="text-xs text-zinc-500 dark:text-zinc-400">
+              {copy.location ?? "Not shelved"}
+            </div>
+          )}
+        </li>
+      ))}
+    </ul>
+  );
+}
+
+export function LocationsDialog({
+  open,
+  onClose,
+  shownVolumeIds,
+  onStocktakingChange,
+  className,
+}: LocationsDialogProps) {
+  const [locations, setLocations] = useState<Location[]>([]);
+  const [selected, setSelected] = useState<Selection>(null);
+  const [contents, setContents] = useState<LocatedCopy[]>([]);
+  const [newName, setNewName] = useState("");
+  const [newKind, setNewKind] = useState<LocationKind>("room");
+  const [editName, setEditName] = useState("");
+  const [editParent, setEditParent] = useState("");
+  // Codes scanned so far, undefined when there's no stocktake
+  const [scanned, setScanned] = useState<string[] | undefined>(undefined);
+  const [report, setReport] = useState<StocktakeReport | undefined>(
+    undefined,
+  );
+  const [busy, setBusy] = useState(false);
+
+  const byId = new Map(locations.map((l) => [l.location_id, l]));
+  const location = selected != null ? byId.get(selected) : undefined;
+
+  const loadLocations = () =>
+    invoke<Location[]>("get_locations")
+      .then(setLocations)
+      .catch((err) => toast.error(String(err)));
+
+  const loadContents = () =>
+    invoke<LocatedCopy[]>("list_location_contents", { locationId: selected })
+      .then(setContents)
+      .catch((err) => toast.error(String(err)));
+
+  useEffect(() => {
+    if (!open) return;
+
+    loadLocations();
+  }, [open]);
+
+  useEffect(() => {
+    if (!open) return;
+
+    loadContents();
+    setEditName(location?.name ?? "");
+    setEditParent(location?.parent_id?.toString() ?? "");
+  }, [open, selected]);
+
+  useEffect(() => {
+    onStocktakingChange(open && scanned !== undefined);
+  }, [open, scanned]);
+
+  useEffect(() => {
+    if (selected == null || scanned === undefined) return;
+
+    invoke<StocktakeReport>("stocktake", { locationId: selected, scanned })
+      .then(setReport)
+      .catch((err) => toast.error(String(err)));
+  }, [selected, scanned]);
+
+  if (!open) {
+    return null;
+  }
+
+  const run = async (action: () => Promise<void>) => {
+    setBusy(true);
+    try {
+      await action();
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleAdd = () =>
+    run(async () => {
+      const added = await invoke<Location>("add_location", {
+        name: newName,
+        kind: newKind,
+        parentId: selected,
+      });
+      setNewName("");
+      await loadLocations();
+      setSelected(added.location_id);
+    });
+
+  const handleUpdate = () =>
+    run(async () => {
+      await invoke<Location>("update_location", {
+        locationId: selected,
+        name: editName,
+        parentId: editParent === "" ? null : Number(editParent),
+      });
+      await loadLocations();
+      await loadContents();
+    });
+
+  const handleDelete = () =>
+    run(async () => {
+      await invoke("delete_location", { locationId: selected });
+      setSelected(location?.parent_id ?? null);
+      await loadLocations();
+    });
+
+  const moveHere = (items: { volume_ids?: string[]; copy_ids?: number[] }) =>
+    run(async () => {
+      const moved = await invoke<number>("move_to_location", {
+        items,
+        locationId: selected,
+      });
+      toast.success(
+        moved === 1
+          ? `Moved 1 copy to ${location?.path ?? "Not shelved"}`
+          : `Moved ${moved} copies to ${location?.path ?? "Not shelved"}`,
+      );
+      await loadLocations();
+      await loadContents();
+      // Runs the stocktake again with what moved
+      setScanned((prev) => prev && [...prev]);
+    });
+
+  const handleStocktakeScan = async (code: string) => {
+    setScanned((prev) => (prev ? [...prev, code] : prev));
+  };
+
+  const finishStocktake = () => {
+    setScanned(undefined);
+    setReport(undefined);
+  };
+
+  const handleClose = () => {
+    finishStocktake();
+    onClose();
+  };
+
+  const selectClass = (active: boolean) =>
+    cn(
+      "block w-full text-left truncate rounded-md py-1 pr-2 text-sm transition-colors",
+      active
+        ? "bg-zinc-100 dark:bg-zinc-800 text-zinc-900 dark:text-zinc-100 font-medium"
+        : "text-zinc-700 dark:text-zinc-300 hover:bg-zinc-50 dark:hover:bg-zinc-800/60",
+    );
+
+  const stocktake = scanned !== undefined && location !== undefined;
+
+  return (
+    <div
+      className={cn(
+        "fixed inset-0 z-50 flex items-center justify-center",
+        "bg-black/40 backdrop-blur-sm",
+        className,
+      )}
+      onClick={handleClose}
+    >
+      {stocktake && <KeypressListener onScan={handleStocktakeScan} />}
+      <div
+        role="dialog"
+        aria-modal="true"
+        aria-labelledby="locations-title"
+        className={cn(
+          "w-full max-w-3xl mx-4 rounded-2xl",
+          "bg-white dark:bg-zinc-900 border border-black/10 dark:border-white/10",
+          "shadow-xl",
+        )}
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="p-5 border-b border-black/10 dark:border-white/10">
+          <h2
+            id="locations-title"
+            className="text-lg font-semibold text-zinc-900 dark:text-zinc-100"
+          >
+            Locations
+          </h2>
+          <p className="mt-1 text-sm text-zinc-500 dark:text-zinc-400">
+            Rooms, bookcases, shelves and boxes, and what's in them.
+          </p>
+        </div>
+
+        <div className="p-5 grid grid-cols-[14rem_1fr] gap-5">
+          <div className="space-y-3">
+            <div className="max-h-80 overflow-y-auto">
+              <button
+                disabled={stocktake}
+                onClick={() => setSelected(null)}
+                className={cn(selectClass(selected === null), "pl-2")}
+              >
+                Not shelved
+              </button>
+              {locations.map((l) => (
+                <button
+                  key={l.location_id}
+                  disabled={stocktake}
+                  onClick={() => setSelected(l.location_id)}
+                  style={{ paddingLeft: `${0.5 + depth(l, byId)}rem` }}
+                  className={selectClass(selected === l.location_id)}
+                >
+                  {l.name}
+                  {l.copy_count > 0 && (
+                    <span className="ml-1 text-xs text-zinc-500 dark:text-zinc-400">
+                      {l.copy_count}
+                    </span>
+                  )}
+                </button>
+              ))}
+            </div>
+
+            <div className="space-y-2">
+              <input
+                placeholder={location ? `New in ${location.name}` : "New"}
+                value={newName}
+                disabled={stocktake}
+                onChange={(e) => setNewName(e.target.value)}
+                className={cn(inputClass, "w-full")}
+              />
+              <div className="flex gap-2">
+                <select
+                  value={newKind}
+                  disabled={stocktake}
+                  onChange={(e) => setNewKind(e.target.value as LocationKind)}
+                  className={cn(inputClass, "flex-1")}
+                >
+                  {(Object.keys(kindLabel) as LocationKind[]).map((k) => (
+                    <option key={k} value={k}>
+                      {kindLabel[k]}
+                    </option>
+                  ))}
+                </select>
+                <button
+                  disabled={busy || stocktake || !newName.trim()}
+                  onClick={handleAdd}
+                  className={cn(
+                    "inline-flex h-9 items-center justify-center rounded-md px-3",
+                    "text-sm font-medium text-white bg-blue-600 hover:bg-blue-700",
+                    "transition-colors disabled:opacity-60 disabled:cursor-not-allowed",
+                  )}
+                >
+                  Add
+                </button>
+              </div>
+            </div>
+          </div>
+
+          <div className="min-w-0 space-y-3">
+            {location && !stocktake && (
+              <div className="flex gap-2">
+                <input
+                  value={editName}
+                  onChange={(e) => setEditName(e.target.value)}
+                  className={cn(inputClass, "flex-1 min-w-0")}
+                />
+                <select
+                  value={editParent}
+                  onChange={(e) => setEditParent(e.target.value)}
+                  className={cn(inputClass, "flex-1 min-w-0")}
+                >
+                  <option value="">Top level</option>
+                  {locations
+                    .filter((l) => l.location_id !== location.location_id)
+                    .map((l) => (
+                      <option key={l.location_id} value={l.location_id}>
+                        {l.path}
+                      </option>
+                    ))}
+                </select>
+                <button
+                  disabled={
+                    busy ||
+                    (editName === location.name &&
+                      editParent === (location.parent_id?.toString() ?? ""))
+                  }
+                  onClick={handleUpdate}
+                  className={buttonClass}
+                >
+                  Save
+                </button>
+              </div>
+            )}
+
+            {!stocktake && (
+              <div className="flex flex-wrap gap-1">
+                <button
+                  disabled={busy || shownVolumeIds.length === 0}
+                  onClick={() => moveHere({ volume_ids: shownVolumeIds })}
+                  className={buttonClass}
+                >
+                  Move shown books here ({shownVolumeIds.length})
+                </button>
+                {location && (
+                  <>
+                    <button
+                      disabled={busy}
+                      onClick={() => setScanned([])}
+                      className={buttonClass}
+                    >
+                      Stocktake
+                    </button>
+                    <button
+                      disabled={busy}
+                      onClick={handleDelete}
+                      className={cn(
+                        buttonClass,
+                        "text-red-600 dark:text-red-400",
+                      )}
+                    >
+                      Delete
+                    </button>
+                  </>
+                )}
+              </div>
+            )}
+
+            {stocktake ? (
+              <div className="space-y-3">
+                <div className="flex items-center justify-between gap-3">
+                  <p className="text-sm text-zinc-700 dark:text-zinc-300">
+                    Scan every book in {location.path}.{" "}
+                    {scanned.length === 1
+                      ? "1 scan"
+                      : `${scanned.length} scans`}
+                    {report && `, ${report.found.length} as expected`}.
+                  </p>
+                  <button onClick={finishStocktake} className={buttonClass}>
+                    Finish
+                  </button>
+                </div>
+
+                <div className="max-h-80 overflow-y-auto space-y-4">
+                  {report && report.missing.length > 0 && (
+                    <section>
+                      <h3 className="mb-1 text-sm font-semibold text-red-600 dark:text-red-400">
+                        Missing ({report.missing.length})
+                      </h3>
+                      <CopyList
+                        copies={report.missing}
+                        showLocation={false}
+                      />
+                    </section>
+                  )}
+                  {report && report.misplaced.length > 0 && (
+                    <section>
+                      <div className="mb-1 flex items-center justify-between">
+                        <h3 className="text-sm font-semibold text-amber-600 dark:text-amber-400">
+                          Misplaced ({report.misplaced.length})
+                        </h3>
+                        <button
+                          disabled={busy}
+                          onClick={() =>
+                            moveHere({
+                              copy_ids: report.misplaced.map((c) => c.copy_id),
+                            })
+                          }
+                          className={buttonClass}
+                        >
+                          Move here
+                        </button>
+                      </div>
+                      <CopyList copies={report.misplaced} showLocation />
+                    </section>
+                  )}
+                  {report && report.unknown.length > 0 && (
+                    <section>
+                      <h3 className="mb-1 text-sm font-semibold text-zinc-700 dark:text-zinc-300">
+                        Not in the library ({report.unknown.length})
+                      </h3>
+                      <ul className="space-y-1">
+                        {report.unknown.map((code, index) => (
+                          <li
+                            key={`${code}-${index}`}
+                            className="text-sm font-mono text-zinc-900 dark:text-zinc-100"
+                          >
+                            {code}
+                          </li>
+                        ))}
+                      </ul>
+                    </section>
+                  )}
+                  {scanned.length > 0 && (
+                    <section>
+                      <h3 className="mb-1 text-sm font-semibold text-zinc-700 dark:text-zinc-300">
+                        Scanned
+                      </h3>
+                      <ul className="flex flex-wrap gap-1">
+                        {scanned.map((code, index) => (
+                          <li
+                            key={`${code}-${index}`}
+                            className="inline-flex items-center gap-1 rounded-md bg-zinc-100 dark:bg-zinc-800 pl-2 pr-1 py-0.5 text-xs font-mono text-zinc-700 dark:text-zinc-300"
+                          >
+                            {code}
+                            <button
+                              aria-label={`Remove ${code}`}
+                              onClick={() =>
+                                setScanned(scanned.filter((_, i) => i !== index))
+                              }
+                              className="rounded hover:bg-zinc-200 dark:hover:bg-zinc-700"
+                            >
+                              <X size={12} />
+                            </button>
+                          </li>
+                        ))}
+                      </ul>
+                    </section>
+                  )}
+                </div>
+              </div>
+            ) : (
+              <div className="max-h-80 overflow-y-auto">
+                {contents.length === 0 ? (
+                  <p className="text-sm text-zinc-500 dark:text-zinc-400">
+                    Nothing here.
+                  </p>
+                ) : (
+                  <CopyList
+                    copies={contents}
+                    showLocation={
+                      location !== undefined && location.kind !== "shelf"
+                    }
+                  />
+                )}
+              </div>
+            )}
+          </div>
+        </div>
+
+        <div className="p-5 pt-0">
+          <div className="flex justify-end">
+            <button
+              onClick={handleClose}
+              className={cn(
+                "inline-flex h-9 items-center justify-center rounded-md px-3",
+                "text-sm text-zinc-700 dark:text-zinc-300",
+                "hover:bg-zinc-100 dark:hover:bg-zinc-800",
+                "transition-colors",
+              )}
+            >
+              Close
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
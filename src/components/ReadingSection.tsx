@@ -0,0 +1,148 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { Star, StarHalf } from "lucide-react";
+import { cn } from "../utils";
+import { Book, ReadingState, ReadingStatus } from "../types";
+
+type ReadingSectionProps = {
+  book: Book;
+  onChange: (reading: ReadingState) => void;
+};
+
+const statusLabel: Record<ReadingStatus, string> = {
+  want_to_read: "Want to read",
+  reading: "Reading",
+  read: "Read",
+  abandoned: "Abandoned",
+};
+
+export function ReadingSection({ book, onChange }: ReadingSectionProps) {
+  const reading = book.reading;
+  const [page, setPage] = useState("");
+  const [review, setReview] = useState("");
+
+  useEffect(() => {
+    setPage(reading?.current_page?.toString() ?? "");
+    setReview(reading?.review ?? "");
+  }, [reading]);
+
+  const update = async (command: string, args: Record<string, unknown>) => {
+    try {
+      const state = await invoke<ReadingState>(command, {
+        volumeId: book.volume_id,
+        ...args,
+      });
+      onChange(state);
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const savePage = () => {
+    const currentPage = Number(page);
+    if (page === "" || currentPage === reading?.current_page) return;
+    update("set_reading_progress", { currentPage });
+  };
+
+  const saveReview = () => {
+    if (review.trim() === (reading?.review ?? "")) return;
+    update("rate_book", { rating: reading?.rating ?? null, review });
+  };
+
+  // Clicking the current rating again clears it
+  const rate = (stars: number) =>
+    update("rate_book", {
+      rating: reading?.rating === stars ? null : stars,
+      review: reading?.review ?? null,
+    });
+
+  const toggleClass = (active: boolean) =>
+    cn(
+      "flex-1 py-1.5 px-3 text-sm font-medium rounded-md transition-colors",
+      active
+        ? "bg-white dark:bg-zinc-700 text-zinc-900 dark:text-zinc-100 shadow-sm"
+        : "text-zinc-600 dark:text-zinc-400 hover:text-zinc-900 dark:hover:text-zinc-200",
+    );
+
+  const dates = [
+    reading?.started_on && `started ${reading.started_on}`,
+    reading?.finished_on && `finished ${reading.finished_on}`,
+    reading?.times_read &&
+      `read ${reading.times_read} ${reading.times_read === 1 ? "time" : "times"}`,
+  ].filter(Boolean);
+
+  return (
+    <div className="space-y-3">
+      <h3 className="text-sm font-semibold text-gray-700 dark:text-gray-300">
+        Reading
+      </h3>
+      <div className="flex rounded-lg bg-zinc-100 dark:bg-zinc-800 p-1">
+        {(Object.keys(statusLabel) as ReadingStatus[]).map((status) => (
+          <button
+            key={status}
+            onClick={() => update("set_reading_status", { status })}
+            className={toggleClass(reading?.status === status)}
+          >
+            {statusLabel[status]}
+          </button>
+        ))}
+      </div>
+      {dates.length > 0 && (
+        <p className="text-sm text-gray-500 dark:text-gray-400">
+          {dates.join(", ")}
+        </p>
+      )}
+      {reading?.status === "reading" && (
+        <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300">
+          Page
+          <input
+            inputMode="numeric"
+            value={page}
+            onChange={(e) => setPage(e.target.value.replace(/\D/g, ""))}
+            onBlur={savePage}
+            onKeyDown={(e) => e.key === "Enter" && savePage()}
+            className={cn(
+              "w-20 rounded-lg border border-gray-300 dark:border-zinc-600 px-2 py-1 text-sm outline-none",
+              "bg-white dark:bg-zinc-700 text-gray-900 dark:text-white",
+              "focus:border-blue-500 dark:focus:border-blue-400",
+            )}
+          />
+          {book.page_count ? `of ${book.page_count}` : null}
+        </label>
+      )}
+      <div className="flex items-center gap-1">
+        {[1, 2, 3, 4, 5].map((stars) => {
+          const rating = reading?.rating ?? 0;
+          const Icon = rating === stars - 0.5 ? StarHalf : Star;
+          return (
+            <button
+              key={stars}
+              aria-label={`${stars} stars`}
+              onClick={() => rate(stars)}
+              className="text-amber-500 hover:scale-110 transition-transform"
+            >
+              <Icon
+                size={18}
+                fill={rating >= stars - 0.5 ? "currentColor" : "none"}
+              />
+            </button>
+          );
+        })}
+      </div>
+      <textarea
+        value={review}
+        onChange={(e) => setReview(e.target.value)}
+        onBlur={saveReview}
+        placeholder="Review"
+        rows={3}
+        className={cn(
+          "w-full rounded-lg border border-gray-300 dark:border-zinc-600 px-3.5 py-2.5 text-sm outline-none transition-all",
+          "placeholder:text-gray-400 dark:placeholder:text-gray-500",
+          "bg-white dark:bg-zinc-700 text-gray-900 dark:text-white",
+          "focus:border-blue-500 dark:focus:border-blue-400 focus:ring-2 focus:ring-blue-500/20 dark:focus:ring-blue-400/20",
+        )}
+      />
+    </div>
+  );
+}
@@ -22,6 +22,18 @@ const settingsSchema = {
     type: "checkbox",
     helpText: "Enables the sound played when a book is not found",
   },
+  backupRetention: {
+    label: "Backups to keep",
+    type: "number",
+    placeholder: "10",
+    helpText: "Older automatic backups are deleted. 0 turns backups off.",
+  },
+  backupEveryWrites: {
+    label: "Back up after every",
+    type: "number",
+    placeholder: "25",
+    helpText: "Number of changes between backups. 0 only backs up on startup.",
+  },
 };
 
 interface SettingsDialogProps {
@@ -4,6 +4,13 @@ export type AppSettings = {
   googleBooksApiKey?: string;
   successSound?: boolean;
   errorSound?: boolean;
+  googleBooksBaseUrl?: string;
+  googleBooksTimeoutSecs?: number;
+  googleBooksUserAgent?: string;
+  openLibraryBaseUrl?: string;
+  openLibraryTimeoutSecs?: number;
+  backupRetention?: number;
+  backupEveryWrites?: number;
 };
 
 const STORE_FILE = ".settings.json";
@@ -25,9 +25,209 @@ export interface Book {
   web_reader_link?: string | null;
   access_view_status?: string | null;
   quote_sharing_allowed?: boolean | null;
+  metadata_source?: string | null;
   authors: string[];
+  categories: string[];
   groups: string[];
   isbns: string[];
+  identifiers: { type: string; identifier: string }[];
   custom_fields: Record<string, string>;
   thumbnail?: string;
+  // Set while the book is lent
+  loan?: Loan | null;
+  // Set once the book is shelved, read or rated
+  reading?: ReadingState | null;
+  // At least one
+  copies: BookCopy[];
 }
+
+export type GradeScale = "book" | "comic";
+
+export interface ConditionGrade {
+  scale: GradeScale;
+  grade: string;
+  label: string;
+}
+
+export interface CopyDetails {
+  acquired_on?: string | null;
+  source?: string | null;
+  price?: number | null;
+  // ISO 4217, like EUR
+  currency?: string | null;
+  grade_scale?: GradeScale | null;
+  grade?: string | null;
+  signed: boolean;
+  first_edition: boolean;
+  notes?: string | null;
+  // Not shelved when missing
+  location_id?: number | null;
+}
+
+export interface BookCopy extends CopyDetails {
+  copy_id: number;
+  volume_id: string;
+}
+
+export type LocationKind = "room" | "bookcase" | "shelf" | "box";
+
+export interface Location {
+  location_id: number;
+  parent_id?: number | null;
+  name: string;
+  kind: LocationKind;
+  // "Study › Bookcase 2 › Top shelf"
+  path: string;
+  // Copies right in it, not in the locations inside it
+  copy_count: number;
+}
+
+export interface LocatedCopy {
+  copy_id: number;
+  volume_id: string;
+  title: string;
+  series?: string | null;
+  number?: number | null;
+  location_id?: number | null;
+  location?: string | null;
+}
+
+export interface StocktakeReport {
+  found: LocatedCopy[];
+  missing: LocatedCopy[];
+  // Scanned here but recorded somewhere else, or nowhere
+  misplaced: LocatedCopy[];
+  // Books we don't have, or more copies than we have
+  unknown: string[];
+}
+
+export type ReadingStatus = "want_to_read" | "reading" | "read" | "abandoned";
+
+export interface ReadingState {
+  volume_id: string;
+  status?: ReadingStatus | null;
+  started_on?: string | null;
+  finished_on?: string | null;
+  current_page?: number | null;
+  times_read: number;
+  // 0 to 5 in half stars
+  rating?: number | null;
+  review?: string | null;
+}
+
+export interface Reading {
+  reading_id: number;
+  volume_id: string;
+  status: ReadingStatus;
+  started_on?: string | null;
+  finished_on?: string | null;
+  current_page?: number | null;
+}
+
+export interface Loan {
+  loan_id: number;
+  volume_id: string;
+  title: string;
+  borrower: string;
+  lent_on: string;
+  due_on?: string | null;
+  returned_on?: string | null;
+  notes?: string | null;
+  overdue: boolean;
+}
+
+export interface BookFilter {
+  text?: string;
+  group?: string;
+  series?: string;
+  language?: string;
+  custom_field?: { name: string; value: string };
+  has_number?: boolean;
+  has_thumbnail?: boolean;
+  reading_status?: ReadingStatus;
+  // Books finished in this year
+  read_in?: number;
+}
+
+export type BookSort = "title" | "series" | "publisher" | "published_date";
+
+export interface BookQuery {
+  filter?: BookFilter;
+  sort?: BookSort;
+  descending?: boolean;
+  offset?: number;
+  limit?: number;
+}
+
+export interface BookPage {
+  books: Book[];
+  total: number;
+}
+
+export interface SearchResult {
+  book: Book;
+  // Matches are wrapped in <mark></mark>, the rest isn't HTML-escaped
+  snippet: string;
+  rank: number;
+}
+
+export type ImportFormat =
+  | "native"
+  | "goodreads"
+  | "librarything"
+  | "calibre"
+  | "clz"
+  | "comicrack";
+
+export type ImportMode = "insert" | "merge";
+
+export type ImportRowStatus =
+  | { status: "inserted" }
+  | { status: "updated"; reason: string }
+  | { status: "skipped"; reason: string }
+  | { status: "rejected"; reason: string };
+
+export type ImportRowReport = ImportRowStatus & {
+  line: number;
+  title?: string;
+  volume_id?: string;
+};
+
+export interface ImportReport {
+  path: string;
+  format: ImportFormat;
+  mode: ImportMode;
+  dry_run: boolean;
+  inserted: number;
+  updated: number;
+  skipped: number;
+  rejected: number;
+  rows: ImportRowReport[];
+}
+
+export interface BackupInfo {
+  name: string;
+  size: number;
+  // Seconds since the Unix epoch
+  created_at: number;
+}
+
+export interface ComicInfoImport {
+  imported: number;
+  // Issues of a series already in the catalog
+  skipped: number;
+  errors: string[];
+}
+
+export type ExportFormat =
+  | "bibtex"
+  | "ris"
+  | "csl_json"
+  | "marc21"
+  | "marcxml"
+  | "dublin_core";
+
+export type ExportSelection =
+  | { kind: "all" }
+  | { kind: "books"; volume_ids: string[] }
+  | { kind: "group"; name: string };